@@ -1,4 +1,5 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hgindex::{BedRecord, BinningIndex, BinningSchema, GenomicDataStore};
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -87,5 +88,96 @@ fn bench_queries(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_queries);
+/// Sweep `GenomicDataStore::set_read_batch_size` over a synthetic store.
+fn bench_read_batch_sizes(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = GenomicDataStore::<BedRecord>::create(dir.path(), None).unwrap();
+    for i in 0..200_000u32 {
+        let record = BedRecord {
+            start: i * 50,
+            end: i * 50 + 500,
+            rest: format!("feature{}\t0\t+", i),
+        };
+        store.add_record("chr1", &record).unwrap();
+    }
+    store.finalize().unwrap();
+    let mut store = GenomicDataStore::<BedRecord>::open(dir.path(), None).unwrap();
+
+    let mut group = c.benchmark_group("read_batch_size");
+    for batch_size in [None, Some(8), Some(32), Some(128), Some(512)] {
+        store.set_read_batch_size(batch_size);
+        let label = batch_size.map_or("none".to_string(), |size| size.to_string());
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter(|| {
+                let mut total = 0;
+                for start in (0..10_000_000).step_by(100_000) {
+                    total += store
+                        .get_overlapping_batch("chr1", start, start + 20_000)
+                        .unwrap()
+                        .len();
+                }
+                total
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Compare `find_overlapping`, which skips bins by their min start and max
+/// end, against scanning every feature of every candidate bin. The dense
+/// dataset stacks thousands of gene-sized features at one locus per megabase,
+/// so they land in large bins that most queries share but don't overlap.
+fn bench_bin_pruning(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.bin");
+    let mut index = BinningIndex::new(&BinningSchema::Dense);
+    let mut i = 0u64;
+    for locus in (0..100_000_000u32).step_by(1_000_000) {
+        for j in 0..2_000u32 {
+            let start = locus + 100_000 + j * 5;
+            let end = start + 20_000 + (j * 7919) % 20_000;
+            index.add_feature("chr1", start, end, i, 1).unwrap();
+            i += 1;
+        }
+    }
+    index.finalize(&path).unwrap();
+    let index = BinningIndex::open(&path).unwrap();
+    let queries: Vec<(u32, u32)> = (0..100_000_000)
+        .step_by(250_000)
+        .map(|start| (start, start + 1_000))
+        .collect();
+
+    let mut group = c.benchmark_group("bin_pruning");
+    group.bench_function("pruned", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&(start, end)| index.find_overlapping("chr1", start, end).len())
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("unpruned", |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|&(start, end)| {
+                    let bins = index.bins.region_to_bins(start, end);
+                    index
+                        .features_in_bins("chr1", &bins)
+                        .into_iter()
+                        .filter(|f| f.start < end && f.end > start)
+                        .count()
+                })
+                .sum::<usize>()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_queries,
+    bench_read_batch_sizes,
+    bench_bin_pruning
+);
 criterion_main!(benches);
@@ -0,0 +1,110 @@
+// aliases.rs
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HgIndexError;
+use crate::index::SequenceMap;
+
+/// A map from alternative chromosome names (e.g. `1`, `NC_000001.11`) to the
+/// canonical name used in a store (e.g. `chr1`).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ChromAliases {
+    aliases: SequenceMap<String>,
+}
+
+impl ChromAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load aliases from a TSV file, where each line is a canonical name
+    /// followed by any number of its aliases. Lines starting with `#` and
+    /// blank lines are skipped.
+    pub fn from_tsv(path: &Path) -> Result<Self, HgIndexError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut aliases = Self::new();
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let canonical = fields.next().unwrap_or_default();
+            if canonical.is_empty() {
+                return Err(format!(
+                    "Missing canonical chromosome name on line {} of {}",
+                    line_number + 1,
+                    path.display()
+                )
+                .into());
+            }
+            for alias in fields.filter(|alias| !alias.is_empty()) {
+                aliases.insert(alias, canonical);
+            }
+        }
+        Ok(aliases)
+    }
+
+    /// Add an alias for a canonical name.
+    pub fn insert(&mut self, alias: &str, canonical: &str) {
+        if alias != canonical {
+            self.aliases
+                .insert(alias.to_string(), canonical.to_string());
+        }
+    }
+
+    /// Return the canonical name for `name` if it is a known alias.
+    pub fn canonical(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Return the canonical name for `name`, or `name` itself if it is not an alias.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.canonical(name).unwrap_or(name)
+    }
+
+    /// Merge another alias table into this one; `other` wins on conflicts.
+    pub fn extend(&mut self, other: ChromAliases) {
+        self.aliases.extend(other.aliases);
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_utils::TestDir;
+    use std::io::Write;
+
+    #[test]
+    fn test_from_tsv() {
+        let test_dir = TestDir::new("chrom_aliases").expect("Failed to create test dir");
+        let path = test_dir.path().join("aliases.tsv");
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "# canonical\taliases...").unwrap();
+        writeln!(file, "chr1\t1\tNC_000001.11").unwrap();
+        writeln!(file, "chrM\tMT").unwrap();
+        writeln!(file).unwrap();
+
+        let aliases = ChromAliases::from_tsv(&path).unwrap();
+        assert_eq!(aliases.len(), 3);
+        assert_eq!(aliases.resolve("1"), "chr1");
+        assert_eq!(aliases.resolve("NC_000001.11"), "chr1");
+        assert_eq!(aliases.resolve("MT"), "chrM");
+        assert_eq!(aliases.resolve("chr1"), "chr1");
+        assert_eq!(aliases.canonical("chr2"), None);
+    }
+}
@@ -0,0 +1,147 @@
+// bin/commands/diff.rs
+
+use clap::Args;
+use hgindex::error::HgIndexError;
+use hgindex::store::{GenomicDataStore, StoredPayload};
+use hgindex::{natural_chrom_cmp, BedRecord, BedRecordSlice, RecordSlice};
+use std::cmp::Ordering;
+use std::io::{self, BufWriter, Write};
+use std::iter::Peekable;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// First .hgidx directory; its features missing from the second are
+    /// printed with a "<" prefix
+    #[arg(value_name = "a.hgidx")]
+    pub a: PathBuf,
+
+    /// Second .hgidx directory; its features missing from the first are
+    /// printed with a ">" prefix
+    #[arg(value_name = "b.hgidx")]
+    pub b: PathBuf,
+}
+
+/// Report features (by chrom, start, end, and stored payload) present in one
+/// store but not the other, failing if there are any.
+pub fn run(args: DiffArgs) -> Result<(), HgIndexError> {
+    for path in [&args.a, &args.b] {
+        if !path.exists() {
+            return Err(format!("Input file {} does not exist.", path.display()).into());
+        }
+    }
+    let mut a = GenomicDataStore::<BedRecord>::open(&args.a, None)?;
+    let mut b = GenomicDataStore::<BedRecord>::open(&args.b, None)?;
+
+    let mut chroms = a.sequence_names();
+    chroms.extend(b.sequence_names());
+    chroms.sort_by(|x, y| natural_chrom_cmp(x, y));
+    chroms.dedup();
+
+    let mut writer = BufWriter::new(io::stdout());
+    let (mut only_a, mut only_b) = (0, 0);
+    for chrom in &chroms {
+        let mut a_records = a.sequence_payloads(chrom)?.peekable();
+        let mut b_records = b.sequence_payloads(chrom)?.peekable();
+        // Merge by (start, end); within a run of equal coordinates, compare
+        // payloads as sorted multisets, since insertion order may differ
+        loop {
+            let key = match (peek_key(&mut a_records)?, peek_key(&mut b_records)?) {
+                (None, None) => break,
+                (Some(a_key), Some(b_key)) => a_key.min(b_key),
+                (Some(key), None) | (None, Some(key)) => key,
+            };
+            let mut a_run = take_run(&mut a_records, key)?;
+            let mut b_run = take_run(&mut b_records, key)?;
+            a_run.sort_unstable();
+            b_run.sort_unstable();
+
+            let (mut i, mut j) = (0, 0);
+            while i < a_run.len() || j < b_run.len() {
+                let ordering = match (a_run.get(i), b_run.get(j)) {
+                    (Some(x), Some(y)) => x.cmp(y),
+                    (Some(_), None) => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+                match ordering {
+                    Ordering::Equal => {
+                        i += 1;
+                        j += 1;
+                    }
+                    Ordering::Less => {
+                        write_record(&mut writer, "<", chrom, a_run[i])?;
+                        only_a += 1;
+                        i += 1;
+                    }
+                    Ordering::Greater => {
+                        write_record(&mut writer, ">", chrom, b_run[j])?;
+                        only_b += 1;
+                        j += 1;
+                    }
+                }
+            }
+        }
+    }
+    writer.flush()?;
+
+    if only_a + only_b > 0 {
+        return Err(format!(
+            "Stores differ: {} features only in {}, {} only in {}.",
+            only_a,
+            args.a.display(),
+            only_b,
+            args.b.display()
+        )
+        .into());
+    }
+    eprintln!("Stores match.");
+    Ok(())
+}
+
+/// The `(start, end)` of the next record, surfacing a read error.
+fn peek_key<'a, I>(records: &mut Peekable<I>) -> Result<Option<(u32, u32)>, HgIndexError>
+where
+    I: Iterator<Item = Result<StoredPayload<'a>, HgIndexError>>,
+{
+    match records.peek() {
+        Some(Ok((start, end, _))) => Ok(Some((*start, *end))),
+        Some(Err(_)) => Err(records.next().unwrap().unwrap_err()),
+        None => Ok(None),
+    }
+}
+
+/// Take the payloads of the records at the head of `records` with `key`.
+fn take_run<'a, I>(
+    records: &mut Peekable<I>,
+    key: (u32, u32),
+) -> Result<Vec<&'a [u8]>, HgIndexError>
+where
+    I: Iterator<Item = Result<StoredPayload<'a>, HgIndexError>>,
+{
+    let mut run = Vec::new();
+    while peek_key(records)? == Some(key) {
+        let (_, _, payload) = records.next().unwrap()?;
+        run.push(payload);
+    }
+    Ok(run)
+}
+
+fn write_record<W: Write>(
+    writer: &mut W,
+    prefix: &str,
+    chrom: &str,
+    payload: &[u8],
+) -> Result<(), HgIndexError> {
+    let record = BedRecordSlice::try_from_bytes(payload)?;
+    write!(
+        writer,
+        "{} {}\t{}\t{}",
+        prefix, chrom, record.start, record.end
+    )?;
+    if !record.rest.is_empty() {
+        writer.write_all(b"\t")?;
+        writer.write_all(record.rest)?;
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
+}
@@ -1,5 +1,7 @@
 // bin/commands/mod.rs
 
+#[cfg(feature = "cli")]
+pub mod diff;
 #[cfg(feature = "cli")]
 pub mod pack;
 #[cfg(feature = "cli")]
@@ -7,4 +9,10 @@ pub mod query;
 #[cfg(all(feature = "cli", feature = "dev"))]
 pub mod random_bed;
 #[cfg(feature = "cli")]
+pub mod reindex;
+#[cfg(feature = "cli")]
 pub mod stats;
+#[cfg(feature = "cli")]
+pub mod to_bed;
+#[cfg(feature = "cli")]
+pub mod verify;
@@ -1,11 +1,14 @@
 // bin/commands/pack.rs
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use csv::ReaderBuilder;
 use hgindex::error::HgIndexError;
 use hgindex::store::GenomicDataStore;
-use hgindex::{BedRecord, InputStream};
+use hgindex::{BedRecord, ChromAliases, CoordinateSystem, InputStream, RecordStreamReader};
 use indicatif::{ProgressBar, ProgressStyle};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, Read};
 use std::path::{Path, PathBuf};
@@ -13,11 +16,14 @@ use std::time::Instant;
 
 #[derive(Args)]
 pub struct PackArgs {
-    /// Input TSV/BED file to pack and index (suffix should be data.hgidx)
-    #[arg(value_name = "FILE")]
-    pub input: PathBuf,
-
-    /// Output path. If not specified, will append .hgidx to input path
+    /// Input TSV/BED files to pack and index, read in order. Each chromosome's
+    /// records must be sorted; with --streaming-index, the files must also be
+    /// ordered by chromosome (e.g. chr1.bed chr2.bed).
+    #[arg(value_name = "FILE", required = true)]
+    pub inputs: Vec<PathBuf>,
+
+    /// Output path. If not specified, will append .hgidx to the input path
+    /// (required with multiple inputs)
     #[arg(short = 'o', long)]
     pub output: Option<PathBuf>,
 
@@ -36,45 +42,221 @@ pub struct PackArgs {
     /// Hierarchical binning schema to use
     #[arg(long, value_enum, default_value_t = hgindex::BinningSchema::Dense)]
     pub schema: hgindex::BinningSchema,
+
+    /// Custom binning: log2 of the finest bins' width. With any of
+    /// --base-shift, --level-shift, --num-levels, and --linear-shift, the
+    /// index uses custom bins, taking unset parameters from --schema.
+    #[arg(long, value_name = "BITS")]
+    pub base_shift: Option<u32>,
+
+    /// Custom binning: log2 of how much wider each coarser level's bins are.
+    #[arg(long, value_name = "BITS")]
+    pub level_shift: Option<u32>,
+
+    /// Custom binning: the number of bin levels.
+    #[arg(long, value_name = "N")]
+    pub num_levels: Option<usize>,
+
+    /// Custom binning: log2 of the linear index's window width.
+    #[arg(long, value_name = "BITS")]
+    pub linear_shift: Option<u32>,
+
+    /// Choose custom bins from the feature sizes of the first
+    /// --auto-schema-records records of the input, instead of --schema.
+    #[arg(
+        long,
+        conflicts_with_all = ["base_shift", "level_shift", "num_levels", "linear_shift"]
+    )]
+    pub auto_schema: bool,
+
+    /// The number of records --auto-schema samples.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 100_000,
+        requires = "auto_schema"
+    )]
+    pub auto_schema_records: usize,
+
+    /// Write each chromosome's index to disk as soon as it is complete, so
+    /// peak memory is one chromosome's index. Requires chromosome-grouped input.
+    #[arg(long)]
+    pub streaming_index: bool,
+
+    /// Build the index without a linear index, even if the schema has one.
+    /// Shrinks the index when the linear index doesn't speed up queries.
+    #[arg(long)]
+    pub no_linear_index: bool,
+
+    /// Skip checking that records are sorted, for input already sorted
+    /// upstream. Unsorted input then produces a silently broken index.
+    #[arg(long)]
+    pub assume_sorted: bool,
+
+    /// Keep only this pseudo-random fraction of records (e.g. 0.01), for
+    /// building small test indices. Input order is still validated.
+    #[arg(long, value_name = "FRACTION")]
+    pub sample_fraction: Option<f64>,
+
+    /// Seed for --sample-fraction, for reproducible subsets
+    #[arg(long, requires = "sample_fraction")]
+    pub seed: Option<u64>,
+
+    /// Match chromosome names case-insensitively by lowercasing them, both
+    /// here and in later queries of this store (e.g. Chr1 finds chr1).
+    #[arg(long)]
+    pub lowercase_chroms: bool,
+
+    /// TSV of chromosome aliases to store with the index; each line is a
+    /// canonical name followed by its aliases (e.g. "chr1<TAB>1<TAB>NC_000001.11").
+    #[arg(long, value_name = "aliases.tsv")]
+    pub chrom_aliases: Option<PathBuf>,
+
+    /// Sequence lengths file (chrom<TAB>length, e.g. a .genome or .fai file).
+    /// Short sequences such as unplaced contigs get a compact flat index.
+    #[arg(long, value_name = "genome.txt")]
+    pub genome: Option<PathBuf>,
+
+    /// Input format: TSV/BED text, or a binary record stream written by
+    /// `query --format hgidx-records`
+    #[arg(long, value_enum, default_value_t = RecordFormat::Tsv)]
+    pub format: RecordFormat,
+
+    /// Fail unless exactly this many records are indexed, e.g. to catch
+    /// upstream data changes or dropped records in CI
+    #[arg(long, value_name = "N")]
+    pub expect_records: Option<u64>,
+
+    /// Only pack records on these chromosomes (as named in the input),
+    /// skipping and counting the rest
+    #[arg(long, value_name = "chr1,chr2", value_delimiter = ',')]
+    pub chroms: Option<Vec<String>>,
+}
+
+/// Record formats for `pack` input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+    /// Tab-delimited text (BED-like)
+    Tsv,
+    /// Length-prefixed stored record payloads with inline sequence names,
+    /// for store-to-store pipelines (see hgindex::records::stream)
+    HgidxRecords,
+}
+
+/// Metadata recorded by `pack` in the store's index.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackMetadata {
+    /// The coordinate convention of the packed source file.
+    pub coordinates: CoordinateSystem,
 }
 
 pub fn run(args: PackArgs) -> Result<(), HgIndexError> {
     // For timing the pack operation
     let start = Instant::now();
+    let schema = binning_schema(&args)?;
 
     // Create the output path by stemming the path.
-    let output_path = args.output.unwrap_or_else(|| {
-        let name = args.input.file_stem().unwrap_or_default().to_string_lossy();
-        let parent = args.input.parent().unwrap_or_else(|| Path::new("."));
-        parent.join(name.to_string()).with_extension("hgidx")
-    });
+    let output_path = match (args.output, args.inputs.as_slice()) {
+        (Some(output), _) => output,
+        (None, [input]) => {
+            let name = input.file_stem().unwrap_or_default().to_string_lossy();
+            let parent = input.parent().unwrap_or_else(|| Path::new("."));
+            parent.join(name.to_string()).with_extension("hgidx")
+        }
+        (None, _) => return Err("--output is required with multiple input files.".into()),
+    };
+
+    if let Some(fraction) = args.sample_fraction {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err("--sample-fraction must be in (0, 1].".into());
+        }
+    }
 
     // Check if output exists and handle --force
     if output_path.exists() && !args.force {
         return Err("Output file exists. Use --force to overwrite.".into());
     }
 
+    let included: Option<HashSet<String>> = args
+        .chroms
+        .as_ref()
+        .map(|chroms| chroms.iter().cloned().collect());
+
+    let input_names: Vec<_> = args
+        .inputs
+        .iter()
+        .map(|input| input.display().to_string())
+        .collect();
     eprintln!(
         "Packing {} to {}",
-        args.input.display(),
+        input_names.join(", "),
         output_path.display()
     );
 
     // Create store
-    eprintln!("Index binning schema: {:?}", args.schema);
-    let mut store =
-        GenomicDataStore::<BedRecord>::create_with_schema(&output_path, None, &args.schema)?;
-
-    let mut csv_reader = build_tsv_reader(
-        &args.input,
-        Some(args.comment as u8),
-        true,  // flexible
-        false, // has_headers
-    )?;
+    eprintln!("Index binning schema: {}", schema);
+    let mut store = if args.streaming_index {
+        GenomicDataStore::<BedRecord>::create_streaming(&output_path, None, &schema)?
+    } else {
+        GenomicDataStore::<BedRecord>::create_with_schema(&output_path, None, &schema)?
+    };
+
+    if args.no_linear_index {
+        store.disable_linear_index();
+    }
+    store.set_unchecked_insertion(args.assume_sorted);
+    store.set_lowercase_chroms(args.lowercase_chroms);
+    if let Some(path) = &args.chrom_aliases {
+        store.add_chrom_aliases(ChromAliases::from_tsv(path)?);
+    }
+    if let Some(path) = &args.genome {
+        let lengths = read_sequence_lengths(path, args.comment as u8)?;
+        let max = hgindex::HierarchicalBins::from_schema(&schema).max_coordinate();
+        for (chrom, length) in &lengths {
+            if *length as u64 > max {
+                eprintln!(
+                    "Warning: {} is {} bp, but the {} schema only addresses coordinates \
+                     below {}; features past that will be rejected. Consider --schema dense.",
+                    chrom, length, schema, max
+                );
+            }
+        }
+        store.set_sequence_lengths(lengths);
+    }
+
+    if args.format == RecordFormat::HgidxRecords {
+        if args.one_based || args.sample_fraction.is_some() {
+            return Err(
+                "--one-based and --sample-fraction do not apply to hgidx-records input.".into(),
+            );
+        }
+        let (mut count, mut skipped) = (0, 0);
+        for input in &args.inputs {
+            let (packed, excluded) = pack_record_stream(&mut store, input, included.as_ref())
+                .map_err(|error| with_input_context(error, input))?;
+            count += packed;
+            skipped += excluded;
+        }
+        store.finalize_with_metadata(&PackMetadata {
+            coordinates: CoordinateSystem::ZeroBased,
+        })?;
+        store.running_stats().print_summary();
+        report_skipped(included.as_ref(), skipped);
+        check_expected_records(&store, args.expect_records)?;
+        eprintln!(
+            "Successfully packed {} records in {:?}",
+            count,
+            start.elapsed()
+        );
+        return Ok(());
+    }
 
     // Estimate total records
-    let estimated_records =
-        estimate_total_records(&args.input, Some(args.comment as u8), b'\t', false, true)?;
+    let mut estimated_records = 0;
+    for input in &args.inputs {
+        estimated_records +=
+            estimate_total_records(input, Some(args.comment as u8), b'\t', false, true)?;
+    }
 
     // Set up the progress bar.
     let pb = ProgressBar::new(estimated_records).with_style(
@@ -93,56 +275,124 @@ pub fn run(args: PackArgs) -> Result<(), HgIndexError> {
     let update_frequency = 1000;
     let mut counter = 0;
 
-    // Process records
-    for result in csv_reader.byte_records() {
-        let record = result?;
-
-        // Safe conversion of chromosome name
-        let chrom = String::from_utf8_lossy(&record[0]).into_owned();
-
-        // Parse start and end positions
-        let start: u32 = String::from_utf8_lossy(&record[1]).parse()?;
-        let end: u32 = String::from_utf8_lossy(&record[2]).parse()?;
-
-        // Handle coordinate system
-        let (adj_start, adj_end) = if args.one_based {
-            (start - 1, end)
-        } else {
-            (start, end)
-        };
-
-        // Join remaining fields using lossy UTF-8 conversion
-        let rest = if record.len() > 3 {
-            record
-                .iter()
-                .skip(3)
-                .map(|bytes| String::from_utf8_lossy(bytes))
-                .collect::<Vec<_>>()
-                .join("\t")
-        } else {
-            String::new()
+    // Optional subsampling, seeded for reproducible subsets
+    let mut sampler = args.sample_fraction.map(|fraction| {
+        let rng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
         };
+        (fraction, rng)
+    });
+    let mut last_position: Option<(String, u32)> = None;
+    let mut kept = 0;
+    let mut skipped = 0;
+
+    // Process records, file by file
+    for input in &args.inputs {
+        let mut csv_reader = build_tsv_reader(
+            input,
+            Some(args.comment as u8),
+            true,  // flexible
+            false, // has_headers
+        )?;
+        for result in csv_reader.byte_records() {
+            let record = result?;
+
+            // Safe conversion of chromosome name
+            let chrom = String::from_utf8_lossy(&record[0]).into_owned();
+            if included
+                .as_ref()
+                .is_some_and(|chroms| !chroms.contains(&chrom))
+            {
+                skipped += 1;
+                continue;
+            }
 
-        // Create BedRecord
-        let bed_record = BedRecord {
-            start: adj_start,
-            end: adj_end,
-            rest,
-        };
+            // Parse start and end positions
+            let start: u32 = String::from_utf8_lossy(&record[1]).parse()?;
+            let end: u32 = String::from_utf8_lossy(&record[2]).parse()?;
+
+            // Handle coordinate system
+            let (adj_start, adj_end) = if args.one_based {
+                (start - 1, end)
+            } else {
+                (start, end)
+            };
+
+            // Update progress bar less frequently
+            counter += 1;
+            if counter % update_frequency == 0 {
+                pb.set_position(counter);
+            }
 
-        // Add to store
-        store.add_record(&chrom, &bed_record)?;
+            if let Some((fraction, rng)) = &mut sampler {
+                // Check order before sampling, so skipped records are still validated
+                if let Some((last_chrom, last_start)) = &last_position {
+                    if *last_chrom == chrom && adj_start < *last_start {
+                        return Err(HgIndexError::UnsortedFeatures {
+                            chrom,
+                            bin_id: 0,
+                            previous: *last_start,
+                            current: adj_start,
+                        });
+                    }
+                }
+                last_position = Some((chrom.clone(), adj_start));
+
+                if !rng.gen_bool(*fraction) {
+                    continue;
+                }
+            }
 
-        // Update progress bar less frequently
-        counter += 1;
-        if counter % update_frequency == 0 {
-            pb.set_position(counter);
+            // Join remaining fields using lossy UTF-8 conversion
+            let rest = if record.len() > 3 {
+                record
+                    .iter()
+                    .skip(3)
+                    .map(|bytes| String::from_utf8_lossy(bytes))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            } else {
+                String::new()
+            };
+
+            // Create BedRecord
+            let bed_record = BedRecord {
+                start: adj_start,
+                end: adj_end,
+                rest,
+            };
+
+            // Add to store
+            store
+                .add_record(&chrom, &bed_record)
+                .map_err(|error| with_input_context(error, input))?;
+            kept += 1;
         }
     }
-    // Finalize the store
-    store.finalize()?;
+    // Finalize the store, recording the source's coordinate convention
+    let metadata = PackMetadata {
+        coordinates: if args.one_based {
+            CoordinateSystem::OneBased
+        } else {
+            CoordinateSystem::ZeroBased
+        },
+    };
+    store.finalize_with_metadata(&metadata)?;
 
     pb.finish_with_message("Packing complete!");
+    store.running_stats().print_summary();
+    report_skipped(included.as_ref(), skipped);
+    check_expected_records(&store, args.expect_records)?;
+
+    if args.sample_fraction.is_some() {
+        eprintln!(
+            "Sampling kept {} of {} records ({:.2}%).",
+            kept,
+            counter,
+            kept as f64 / counter.max(1) as f64 * 100.0
+        );
+    }
 
     // If --features=dev,report how off this is
     #[cfg(feature = "dev")]
@@ -168,6 +418,146 @@ pub fn run(args: PackArgs) -> Result<(), HgIndexError> {
     Ok(())
 }
 
+/// The binning schema: `--schema`, custom bins if any of the custom binning
+/// options are given (with the rest taken from `--schema`), or bins tuned to
+/// a sample of the input with `--auto-schema`.
+fn binning_schema(args: &PackArgs) -> Result<hgindex::BinningSchema, HgIndexError> {
+    if args.auto_schema {
+        if args.format != RecordFormat::Tsv {
+            return Err("--auto-schema only applies to TSV input.".into());
+        }
+        let sizes = sample_feature_sizes(args, args.auto_schema_records)?;
+        let bins = hgindex::HierarchicalBins::tune_for_sizes(&sizes);
+        eprintln!(
+            "Auto schema from {} sampled records: --base-shift {} --level-shift {} \
+             --num-levels {} --linear-shift {}",
+            sizes.len(),
+            bins.base_shift,
+            bins.level_shift,
+            bins.num_levels,
+            bins.linear_shift.unwrap_or(bins.base_shift)
+        );
+        return Ok(bins.schema);
+    }
+    if args.base_shift.is_none()
+        && args.level_shift.is_none()
+        && args.num_levels.is_none()
+        && args.linear_shift.is_none()
+    {
+        return Ok(args.schema.clone());
+    }
+    let preset = hgindex::HierarchicalBins::from_schema(&args.schema);
+    let bins = hgindex::HierarchicalBins::try_new(
+        args.base_shift.unwrap_or(preset.base_shift),
+        args.level_shift.unwrap_or(preset.level_shift),
+        args.num_levels.unwrap_or(preset.num_levels),
+        args.linear_shift.or(preset.linear_shift),
+    )?;
+    Ok(bins.schema)
+}
+
+/// The widths of up to the first `limit` records of the inputs, in order.
+fn sample_feature_sizes(args: &PackArgs, limit: usize) -> Result<Vec<u32>, HgIndexError> {
+    let mut sizes = Vec::new();
+    for input in &args.inputs {
+        let mut csv_reader = build_tsv_reader(input, Some(args.comment as u8), true, false)?;
+        for result in csv_reader.byte_records() {
+            if sizes.len() >= limit {
+                return Ok(sizes);
+            }
+            let record = result?;
+            let start: u32 = String::from_utf8_lossy(&record[1]).parse()?;
+            let end: u32 = String::from_utf8_lossy(&record[2]).parse()?;
+            // 1-based, fully closed intervals include their end
+            let width = end.saturating_sub(start) + u32::from(args.one_based);
+            sizes.push(width);
+        }
+    }
+    Ok(sizes)
+}
+
+/// Check the number of indexed records against `--expect-records`.
+fn check_expected_records(
+    store: &GenomicDataStore<BedRecord>,
+    expected: Option<u64>,
+) -> Result<(), HgIndexError> {
+    let indexed = store.running_stats().total_features;
+    match expected {
+        Some(expected) if indexed != expected => Err(format!(
+            "Expected {} records, but {} were indexed.",
+            expected, indexed
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Name the offending input file when a sequence reappears after other
+/// sequences in streaming mode, which across files means they weren't ordered
+/// by sequence.
+fn with_input_context(error: HgIndexError, input: &Path) -> HgIndexError {
+    match error {
+        HgIndexError::UngroupedSequence(chrom) => format!(
+            "Sequence {} in {} appeared again after other sequences; each input \
+             must be sorted, and input files ordered by sequence.",
+            chrom,
+            input.display()
+        )
+        .into(),
+        error => error,
+    }
+}
+
+/// Add every record of an hgidx-records stream to the store, passing the
+/// stored payloads through without parsing.
+fn pack_record_stream(
+    store: &mut GenomicDataStore<BedRecord>,
+    path: &Path,
+    included: Option<&HashSet<String>>,
+) -> Result<(u64, u64), HgIndexError> {
+    let mut reader = RecordStreamReader::new(InputStream::new(path).reader()?)?;
+    let (mut count, mut skipped) = (0, 0);
+    while let Some((chrom, payload)) = reader.next_record()? {
+        if included.is_some_and(|chroms| !chroms.contains(chrom)) {
+            skipped += 1;
+            continue;
+        }
+        store.add_record_bytes(chrom, payload)?;
+        count += 1;
+    }
+    Ok((count, skipped))
+}
+
+/// Report records skipped for being on chromosomes excluded by `--chroms`.
+fn report_skipped(included: Option<&HashSet<String>>, skipped: u64) {
+    if included.is_some() {
+        eprintln!(
+            "Skipped {} records on chromosomes not given to --chroms.",
+            skipped
+        );
+    }
+}
+
+/// Read `chrom<TAB>length` lines from a .genome or .fai-style file.
+pub fn read_sequence_lengths(
+    path: &Path,
+    comment_char: u8,
+) -> Result<Vec<(String, u32)>, HgIndexError> {
+    let mut reader = build_tsv_reader(path, Some(comment_char), true, false)?;
+    let mut lengths = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let chrom = record.get(0).ok_or("Missing sequence name")?.to_string();
+        let length: u32 = record
+            .get(1)
+            .ok_or("Missing sequence length")?
+            .parse()
+            .map_err(|_| format!("Invalid length for sequence {}", chrom))?;
+        lengths.push((chrom, length));
+    }
+    Ok(lengths)
+}
+
 pub fn build_tsv_reader(
     filepath: impl Into<PathBuf>,
     comment_char: Option<u8>,
@@ -1,17 +1,25 @@
 // bin/commands/query.rs
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use flate2::Compression;
 use hgindex::error::HgIndexError;
 use hgindex::io::OutputStream;
 use hgindex::store::GenomicDataStore;
-use hgindex::{BedRecord, BedRecordSlice};
+use hgindex::{parse_region, touching_window};
+use hgindex::{
+    BedRecord, BedRecordSlice, ChromAliases, CoordinateSystem, IntervalFormat, RecordSlice,
+    RecordStreamWriter,
+};
 use itoa;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use crate::commands::pack::build_tsv_reader;
+use crate::commands::pack::{build_tsv_reader, PackMetadata};
 
 #[derive(Args)]
 pub struct QueryArgs {
@@ -24,22 +32,206 @@ pub struct QueryArgs {
     pub comment: char,
 
     /// The query region, in the format seqname:start-end where start and end are
-    /// 1-based inclusive coordinates (like tabix's region argument).
+    /// 1-based inclusive coordinates (like tabix's region argument). Overlapping
+    /// records are output in (start, end) order, other ties in input order.
     #[arg(
         value_name = "chr17:7661779-7687538",
         required_unless_present = "regions"
     )]
     pub region: Option<String>,
 
-    /// Input BED file for batch queries
+    /// Input BED file for batch queries. Regions are queried grouped by
+    /// chromosome, so output follows chromosome order (see --ordered).
     #[arg(long, value_name = "regions.bed", required_unless_present = "region")]
     pub regions: Option<PathBuf>,
 
+    /// Format of the --regions file: BED, or VCF (each variant's POS over its
+    /// REF allele) or GFF (1-based, inclusive start and end), for using
+    /// variants or features directly as query regions.
+    #[arg(long, value_enum, default_value_t = IntervalFormat::Bed, requires = "regions")]
+    pub regions_format: IntervalFormat,
+
     /// Input .hgidx directory. If not specified, a file with the suffix .hgidx
     /// will be looked for in the current directory. If a single match is found,
     /// it will be used.
     #[arg(short, long, value_name = "scores.hgidx")]
     pub input: Option<PathBuf>,
+
+    /// Only output these 1-based columns (1-3 are chrom, start, end), e.g. 1,2,3,5.
+    #[arg(long, value_name = "1,2,3", value_delimiter = ',')]
+    pub columns: Option<Vec<usize>>,
+
+    /// Computed columns to append to each output row: overlap_len (bp of
+    /// overlap with the query), feature_width (end - start), and distance
+    /// (bp between feature and query; 0 for overlaps).
+    #[arg(
+        long,
+        value_enum,
+        value_name = "overlap_len,feature_width,distance",
+        value_delimiter = ','
+    )]
+    pub add_columns: Vec<ComputedColumn>,
+
+    /// Also report features touching but not overlapping the query, i.e.
+    /// book-ended features ending exactly at its start or starting exactly
+    /// at its end. Coordinates are half-open, so these share no base with
+    /// the query and are excluded by default.
+    #[arg(long)]
+    pub allow_touching: bool,
+
+    /// Only output the sorted, distinct values of the --name-col column among
+    /// all overlapping records, e.g. the genes hit by a set of peaks.
+    #[arg(
+        long,
+        conflicts_with_all = ["columns", "add_columns", "ordered", "region_key", "write_regions", "split_output"]
+    )]
+    pub names: bool,
+
+    /// The 1-based column holding feature names for --names (4 is the BED
+    /// name column).
+    #[arg(long, value_name = "N", default_value_t = 4, requires = "names")]
+    pub name_col: usize,
+
+    /// With --regions, group output by input region in file order, with each
+    /// region's overlaps sorted by start and end.
+    #[arg(long, requires = "regions")]
+    pub ordered: bool,
+
+    /// With --regions, prefix each output line with the matching input region
+    /// as a chrom:start-end key column.
+    #[arg(long, requires = "regions")]
+    pub region_key: bool,
+
+    /// With --regions, prefix each output line with the full matching region
+    /// record, including any extra columns, like bedtools intersect -wa -wb.
+    #[arg(
+        long,
+        visible_alias = "echo-query",
+        requires = "regions",
+        conflicts_with = "region_key"
+    )]
+    pub write_regions: bool,
+
+    /// For a single-region query, omit the leading chromosome column, writing
+    /// start, end, and the remaining fields.
+    #[arg(long, conflicts_with_all = ["regions", "columns", "names"])]
+    pub no_chrom: bool,
+
+    /// The --regions BED file uses 1-based, fully-closed coordinates (e.g. the same
+    /// convention as a source packed with `pack --one-based`).
+    #[arg(long, requires = "regions")]
+    pub one_based: bool,
+
+    /// TSV of chromosome aliases to apply in addition to any stored with the
+    /// index; each line is a canonical name followed by its aliases.
+    #[arg(long, value_name = "aliases.tsv")]
+    pub chrom_aliases: Option<PathBuf>,
+
+    /// Maximum number of chromosome data files to keep mapped at once,
+    /// closing the least-recently-used; bounds open file descriptors on
+    /// genomes with many contigs.
+    #[arg(long, value_name = "N")]
+    pub max_open_files: Option<usize>,
+
+    /// Output format: TSV; JSON or NDJSON objects of each record's chrom,
+    /// start, end, and remaining columns; or a binary record stream that
+    /// `pack --format hgidx-records` can ingest directly (each region's
+    /// records are sorted; regions should be sorted and non-overlapping for
+    /// the stream to pack)
+    #[arg(long, value_enum, default_value_t = QueryFormat::Tsv)]
+    pub format: QueryFormat,
+
+    /// With --regions, write one output file per chromosome into this
+    /// directory (e.g. outdir/chr1.bed) instead of a single output.
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "regions",
+        conflicts_with = "output"
+    )]
+    pub split_output: Option<PathBuf>,
+
+    /// File suffix for --split-output files; a .gz suffix compresses them.
+    #[arg(long, default_value = ".bed", requires = "split_output")]
+    pub split_suffix: String,
+
+    /// Gzip compression level (0-9) for .gz outputs, trading ratio for
+    /// speed; defaults to 9 (best). 0 still writes valid gzip, but with
+    /// stored, uncompressed blocks.
+    #[arg(long, value_name = "0-9", value_parser = clap::value_parser!(u32).range(0..=9))]
+    pub gzip_level: Option<u32>,
+}
+
+/// Output formats for `query`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum QueryFormat {
+    /// Tab-delimited text (BED-like)
+    Tsv,
+    /// A JSON array of record objects
+    Json,
+    /// One JSON record object per line
+    Ndjson,
+    /// Length-prefixed stored record payloads with inline sequence names,
+    /// for store-to-store pipelines (see hgindex::records::stream)
+    HgidxRecords,
+}
+
+/// A record as a JSON object, borrowing from its stored slice.
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    chrom: &'a str,
+    start: u32,
+    end: u32,
+    rest: Cow<'a, str>,
+}
+
+/// A column computed from a feature and the query interval it overlaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ComputedColumn {
+    #[value(name = "overlap_len")]
+    OverlapLen,
+    #[value(name = "feature_width")]
+    FeatureWidth,
+    #[value(name = "distance")]
+    Distance,
+}
+
+impl ComputedColumn {
+    /// The value of this column for a feature and a 0-based, half-open query.
+    fn value(self, record: &BedRecordSlice<'_>, query: (u32, u32)) -> u32 {
+        let (start, end) = query;
+        match self {
+            ComputedColumn::OverlapLen => {
+                end.min(record.end).saturating_sub(start.max(record.start))
+            }
+            ComputedColumn::FeatureWidth => record.end.saturating_sub(record.start),
+            ComputedColumn::Distance => start
+                .saturating_sub(record.end)
+                .max(record.start.saturating_sub(end)),
+        }
+    }
+}
+
+/// Output formatting options shared by the single-region and batch paths.
+struct OutputOptions<'a> {
+    columns: Option<&'a [usize]>,
+    add_columns: &'a [ComputedColumn],
+    allow_touching: bool,
+    ordered: bool,
+    region_key: bool,
+    write_regions: bool,
+    no_chrom: bool,
+}
+
+impl OutputOptions<'_> {
+    /// The interval to search the store with for a query of `[start, end)`.
+    fn search_window(&self, start: u32, end: u32) -> (u32, u32) {
+        if self.allow_touching {
+            touching_window(start, end)
+        } else {
+            (start, end)
+        }
+    }
 }
 
 pub fn run(args: QueryArgs) -> Result<(), HgIndexError> {
@@ -49,7 +241,7 @@ pub fn run(args: QueryArgs) -> Result<(), HgIndexError> {
     let output_stream = OutputStream::builder()
         .filepath(args.output)
         .buffer_size(1024 * 1024)
-        .compression_level(None::<Compression>)
+        .compression_level(args.gzip_level.map(Compression::new))
         .build();
     let mut output_writer = output_stream.writer()?;
 
@@ -64,13 +256,147 @@ pub fn run(args: QueryArgs) -> Result<(), HgIndexError> {
         return Err(format!("Input file {} does not exist.", input_path.display()).into());
     }
 
+    if let Some(columns) = &args.columns {
+        if columns.contains(&0) {
+            return Err("Columns are 1-based; column 0 does not exist.".into());
+        }
+    }
+    let options = OutputOptions {
+        columns: args.columns.as_deref(),
+        add_columns: &args.add_columns,
+        allow_touching: args.allow_touching,
+        ordered: args.ordered,
+        region_key: args.region_key,
+        write_regions: args.write_regions,
+        no_chrom: args.no_chrom,
+    };
+
     // Open store once for all queries
     let mut store = GenomicDataStore::<BedRecord>::open(&input_path, None)?;
+    if let Some(path) = &args.chrom_aliases {
+        store.add_chrom_aliases(ChromAliases::from_tsv(path)?);
+    }
+    store.set_max_open_files(args.max_open_files);
 
-    if let Some(region) = args.region {
+    // Warn if the regions file convention differs from the packed source's
+    let regions_coordinates = if args.one_based {
+        CoordinateSystem::OneBased
+    } else {
+        CoordinateSystem::ZeroBased
+    };
+    if args.one_based && args.regions_format != IntervalFormat::Bed {
+        return Err(
+            "--one-based only applies to BED regions; VCF and GFF regions \
+                    are always read as 1-based."
+                .into(),
+        );
+    }
+    if let (Some(metadata), Some(_), IntervalFormat::Bed) = (
+        store.metadata::<PackMetadata>(),
+        &args.regions,
+        args.regions_format,
+    ) {
+        if metadata.coordinates != regions_coordinates {
+            eprintln!(
+                "Warning: {} was packed from {} input, but --regions is read as {}. \
+                 Stored coordinates are 0-based; pass --one-based if the regions file \
+                 uses 1-based coordinates.",
+                input_path.display(),
+                metadata.coordinates,
+                regions_coordinates
+            );
+        }
+    }
+
+    if args.names && args.format != QueryFormat::Tsv {
+        return Err("--names only applies to TSV output.".into());
+    }
+
+    if args.format != QueryFormat::Tsv {
+        if options.columns.is_some()
+            || !options.add_columns.is_empty()
+            || options.ordered
+            || options.region_key
+            || options.write_regions
+            || options.no_chrom
+        {
+            return Err("--columns, --add-columns, --ordered, --region-key, \
+                        --write-regions, and --no-chrom only apply to TSV output."
+                .into());
+        }
+        if args.split_output.is_some() {
+            return Err("--split-output only applies to TSV output.".into());
+        }
+    }
+
+    if matches!(args.format, QueryFormat::Json | QueryFormat::Ndjson) {
+        let regions = all_regions(
+            args.region.as_deref(),
+            args.regions.as_ref(),
+            &args.comment,
+            args.regions_format,
+            regions_coordinates,
+        )?;
+        let count = write_json_records(
+            &mut store,
+            &regions,
+            &options,
+            args.format == QueryFormat::Json,
+            &mut output_writer,
+        )?;
+        eprintln!("Wrote {} records.", count);
+    } else if args.format == QueryFormat::HgidxRecords {
+        let mut stream = RecordStreamWriter::new(&mut output_writer)?;
+        let regions = all_regions(
+            args.region.as_deref(),
+            args.regions.as_ref(),
+            &args.comment,
+            args.regions_format,
+            regions_coordinates,
+        )?;
+        // Overlaps are found in bin order; sort each region's payloads so the
+        // stream can be packed (given sorted, non-overlapping regions)
+        let mut total_records = 0;
+        let mut payloads: Vec<(u32, u32, Vec<u8>)> = Vec::new();
+        for (chrom, start, end) in regions {
+            payloads.clear();
+            let (start, end) = options.search_window(start, end);
+            store.map_overlapping_bytes(&chrom, start, end, |payload| {
+                let record = BedRecordSlice::from_bytes(payload);
+                payloads.push((record.start, record.end, payload.to_vec()));
+                Ok(())
+            })?;
+            payloads.sort_by_key(|(start, end, _)| (*start, *end));
+            for (_, _, payload) in &payloads {
+                stream.write_record(&chrom, payload)?;
+            }
+            total_records += payloads.len();
+        }
+        stream.flush()?;
+        eprintln!("Wrote {} records.", total_records);
+    } else if args.names {
+        if args.name_col == 0 {
+            return Err("Columns are 1-based; column 0 does not exist.".into());
+        }
+        let regions = all_regions(
+            args.region.as_deref(),
+            args.regions.as_ref(),
+            &args.comment,
+            args.regions_format,
+            regions_coordinates,
+        )?;
+        let count = query_names(
+            &mut store,
+            &regions,
+            args.name_col,
+            &options,
+            &mut output_writer,
+        )?;
+        eprintln!("{} distinct names.", count);
+    } else if let Some(region) = args.region {
         // Single region query
         eprintln!("Query region {} in {}", region, input_path.display());
-        query_single_region(&mut store, &region, &mut output_writer)?;
+        query_single_region(&mut store, &region, &mut output_writer, &options)?;
     } else if let Some(regions_file) = args.regions {
         // Batch query from BED file
         eprintln!(
@@ -78,7 +404,28 @@ pub fn run(args: QueryArgs) -> Result<(), HgIndexError> {
             regions_file.display(),
             input_path.display()
         );
-        query_bed_regions(&mut store, &regions_file, &mut output_writer, &args.comment)?;
+        let mut outputs = match &args.split_output {
+            Some(dir) => {
+                fs::create_dir_all(dir)?;
+                RegionOutput::Split {
+                    dir,
+                    suffix: &args.split_suffix,
+                    compression: args.gzip_level.map(Compression::new),
+                    writers: HashMap::new(),
+                }
+            }
+            None => RegionOutput::Single(&mut output_writer),
+        };
+        query_bed_regions(
+            &mut store,
+            &regions_file,
+            &mut outputs,
+            &args.comment,
+            args.regions_format,
+            regions_coordinates,
+            &options,
+        )?;
+        outputs.flush()?;
     }
 
     let duration = duration_start.elapsed();
@@ -86,28 +433,196 @@ pub fn run(args: QueryArgs) -> Result<(), HgIndexError> {
     Ok(())
 }
 
+/// All query regions, from the single region or the --regions file.
+fn all_regions(
+    region: Option<&str>,
+    regions_file: Option<&PathBuf>,
+    comment_char: &char,
+    format: IntervalFormat,
+    coordinates: CoordinateSystem,
+) -> Result<Vec<(String, u32, u32)>, HgIndexError> {
+    match (region, regions_file) {
+        (Some(region), _) => Ok(vec![parse_region(region)?]),
+        (None, Some(regions_file)) => read_regions(regions_file, comment_char, format, coordinates),
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+/// Write the records overlapping each region, by start within each region,
+/// as JSON objects: one per line, or with `array`, as the elements of a
+/// single array. Records are serialized straight from their stored slices
+/// as they are found. Returns the number of records written.
+fn write_json_records<W: std::io::Write>(
+    store: &mut GenomicDataStore<BedRecord>,
+    regions: &[(String, u32, u32)],
+    options: &OutputOptions<'_>,
+    array: bool,
+    writer: &mut W,
+) -> Result<usize, HgIndexError> {
+    let mut count = 0;
+    if array {
+        writer.write_all(b"[")?;
+    }
+    for (chrom, start, end) in regions {
+        let (start, end) = options.search_window(*start, *end);
+        store.map_overlapping_sorted(chrom, start, end, |record| {
+            if array && count > 0 {
+                writer.write_all(b",")?;
+            }
+            let json = JsonRecord {
+                chrom,
+                start: record.start,
+                end: record.end,
+                rest: String::from_utf8_lossy(record.rest),
+            };
+            serde_json::to_writer(&mut *writer, &json)
+                .map_err(|e| HgIndexError::SerializationError(e.to_string()))?;
+            if !array {
+                writer.write_all(b"\n")?;
+            }
+            count += 1;
+            Ok(())
+        })?;
+    }
+    if array {
+        writer.write_all(b"]\n")?;
+    }
+    Ok(count)
+}
+
+/// Write the sorted, distinct values of a 1-based column among the records
+/// overlapping any of the regions, returning how many there were.
+fn query_names<W: std::io::Write>(
+    store: &mut GenomicDataStore<BedRecord>,
+    regions: &[(String, u32, u32)],
+    name_col: usize,
+    options: &OutputOptions<'_>,
+    output_writer: &mut W,
+) -> Result<usize, HgIndexError> {
+    let mut names = HashSet::new();
+    for (chrom, start, end) in regions {
+        let (start, end) = options.search_window(*start, *end);
+        names.extend(store.overlapping_names(chrom, start, end, |record| {
+            record_column(chrom, &record, name_col)
+        })?);
+    }
+    let mut names: Vec<_> = names.into_iter().collect();
+    names.sort_unstable();
+    for name in &names {
+        writeln!(output_writer, "{}", name)?;
+    }
+    Ok(names.len())
+}
+
+/// The value of a record's 1-based column (as in --columns), or `None` if the
+/// record has no such column or it is empty.
+fn record_column(chrom: &str, record: &BedRecordSlice<'_>, column: usize) -> Option<String> {
+    let value = match column {
+        1 => chrom.to_string(),
+        2 => record.start.to_string(),
+        3 => record.end.to_string(),
+        _ => String::from_utf8_lossy(record.field(column - 4)?).into_owned(),
+    };
+    (!value.is_empty()).then_some(value)
+}
+
 fn query_single_region<W: std::io::Write>(
     store: &mut GenomicDataStore<BedRecord>,
     region: &str,
     output_writer: &mut W,
+    options: &OutputOptions<'_>,
 ) -> Result<(), HgIndexError> {
     let (seqname, start, end) = parse_region(region)?;
-
-    // Use `map_overlapping` for efficient ZCD
-    let record_count = store.map_overlapping(seqname, start, end, |record_slice| {
-        write_tsv_bytes(seqname, &record_slice, output_writer)?;
-        Ok(())
-    })?;
+    let seqname = seqname.as_str();
+
+    // Stream zero-copy slices straight to the output, in (start, end) order
+    let (search_start, search_end) = options.search_window(start, end);
+    let record_count =
+        store.map_overlapping_sorted(seqname, search_start, search_end, |record_slice| {
+            match options.columns {
+                Some(columns) => write_columns(seqname, &record_slice, columns, output_writer)?,
+                None => {
+                    let chrom = (!options.no_chrom).then_some(seqname);
+                    write_tsv_bytes(chrom, &record_slice, output_writer)?
+                }
+            }
+            write_computed_columns(
+                &record_slice,
+                options.add_columns,
+                (start, end),
+                output_writer,
+            )
+        })?;
 
     eprintln!("{} records processed.", record_count);
     Ok(())
 }
 
-fn query_bed_regions<W: std::io::Write>(
+/// Where batch query output goes: one writer, or one file per chromosome.
+enum RegionOutput<'a> {
+    Single(&'a mut dyn Write),
+    Split {
+        dir: &'a Path,
+        suffix: &'a str,
+        compression: Option<Compression>,
+        writers: HashMap<String, Box<dyn Write>>,
+    },
+}
+
+impl RegionOutput<'_> {
+    /// The writer for records on `chrom`, opening its file on first use.
+    fn writer(&mut self, chrom: &str) -> Result<&mut dyn Write, HgIndexError> {
+        match self {
+            RegionOutput::Single(writer) => Ok(&mut **writer),
+            RegionOutput::Split {
+                dir,
+                suffix,
+                compression,
+                writers,
+            } => {
+                if !writers.contains_key(chrom) {
+                    if chrom.is_empty() || chrom.contains(['/', '\\']) || chrom == ".." {
+                        return Err(format!(
+                            "Cannot use chromosome name '{}' as a --split-output file name.",
+                            chrom
+                        )
+                        .into());
+                    }
+                    let path = dir.join(format!("{}{}", chrom, suffix));
+                    let writer = OutputStream::builder()
+                        .filepath(Some(path))
+                        .buffer_size(256 * 1024)
+                        .compression_level(*compression)
+                        .build()
+                        .writer()?;
+                    writers.insert(chrom.to_string(), writer);
+                }
+                Ok(writers.get_mut(chrom).unwrap().as_mut())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), HgIndexError> {
+        match self {
+            RegionOutput::Single(writer) => writer.flush()?,
+            RegionOutput::Split { writers, .. } => {
+                for writer in writers.values_mut() {
+                    writer.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn query_bed_regions(
     store: &mut GenomicDataStore<BedRecord>,
     regions_file: &PathBuf,
-    output_writer: &mut W,
+    outputs: &mut RegionOutput<'_>,
     comment_char: &char,
+    format: IntervalFormat,
+    coordinates: CoordinateSystem,
+    options: &OutputOptions<'_>,
 ) -> Result<(), HgIndexError> {
     let mut reader = build_tsv_reader(
         regions_file,
@@ -116,78 +631,179 @@ fn query_bed_regions<W: std::io::Write>(
         false, // has_headers
     )?;
 
-    let mut total_records = 0;
-    // Initialize batch with reasonable starting capacity
-    let mut batch = RecordBatch::with_capacity(64 * 1024);
-
+    // The search windows, alongside each region's own interval and key
+    let mut windows = Vec::new();
+    let mut regions = Vec::new();
+    let mut keys = Vec::new();
     for record in reader.records() {
         let record = record?;
-        let chrom = record.get(0).ok_or("Missing chrom")?.to_string();
-        let start: u32 = record
-            .get(1)
-            .ok_or("Missing start")?
-            .parse()
-            .map_err(|_| "Invalid start coordinate")?;
-        let end: u32 = record
-            .get(2)
-            .ok_or("Missing end")?
-            .parse()
-            .map_err(|_| "Invalid end coordinate")?;
-
-        let records = store.get_overlapping_batch(&chrom, start, end)?;
-        for record in records {
-            batch.push_record(&chrom, &record);
-            if batch.should_flush() {
-                batch.write_batch(output_writer)?;
+        let (chrom, start, end) = parse_region_record(&record, format, coordinates)?;
+        let key = if options.write_regions {
+            Some(record.iter().collect::<Vec<_>>().join("\t"))
+        } else {
+            options
+                .region_key
+                .then(|| format!("{}:{}-{}", chrom, start, end))
+        };
+        let (search_start, search_end) = options.search_window(start, end);
+        windows.push((chrom, search_start, search_end));
+        regions.push((start, end));
+        keys.push(key);
+    }
+
+    // Initialize batch with reasonable starting capacity
+    let mut batch = RecordBatch::with_capacity(64 * 1024);
+    // The chromosome of the batched records, whose writer they go to
+    let mut batch_chrom = String::new();
+
+    let mut write_record = |i: usize, record: BedRecordSlice<'_>| -> Result<(), HgIndexError> {
+        let chrom = &windows[i].0;
+        let region = regions[i];
+        let key = &keys[i];
+        if let Some(columns) = options.columns {
+            let writer = outputs.writer(chrom)?;
+            if let Some(key) = key {
+                write!(writer, "{}\t", key)?;
             }
-            total_records += 1;
+            write_columns(chrom, &record, columns, writer)?;
+            return write_computed_columns(&record, options.add_columns, region, writer);
         }
-    }
+        if *chrom != batch_chrom {
+            if batch.records_seen > 0 {
+                batch.write_batch(outputs.writer(&batch_chrom)?)?;
+            }
+            batch_chrom.clone_from(chrom);
+        }
+        if let Some(key) = key {
+            batch.push_key(key);
+        }
+        batch.push_record(chrom, &record, options.add_columns, region);
+        if batch.should_flush() {
+            batch.write_batch(outputs.writer(chrom)?)?;
+        }
+        Ok(())
+    };
+    let total_records = if options.ordered {
+        // Regions in file order, each with its overlaps by start and end
+        let mut total = 0;
+        for (i, (chrom, start, end)) in windows.iter().enumerate() {
+            total += store
+                .map_overlapping_sorted(chrom, *start, *end, |record| write_record(i, record))?;
+        }
+        total
+    } else {
+        // Grouped by chromosome, so each data file is opened once
+        store.map_overlapping_multi(&windows, &mut write_record)?
+    };
 
     // Flush any remaining records
     if batch.records_seen > 0 {
-        batch.write_batch(output_writer)?;
+        batch.write_batch(outputs.writer(&batch_chrom)?)?;
     }
 
     eprintln!("Found {} total records.", total_records);
     Ok(())
 }
 
+/// Parse a regions-file record into a 0-based, half-open region.
+fn parse_region_record(
+    record: &csv::StringRecord,
+    format: IntervalFormat,
+    coordinates: CoordinateSystem,
+) -> Result<(String, u32, u32), HgIndexError> {
+    let fields: Vec<&str> = record.iter().collect();
+    let (chrom, start, end) = format.parse_interval(&fields)?;
+    let chrom = chrom.to_string();
+    // VCF and GFF intervals are already converted; only BED may be 1-based
+    let start = match coordinates {
+        CoordinateSystem::ZeroBased => start,
+        CoordinateSystem::OneBased => start
+            .checked_sub(1)
+            .ok_or("Start coordinate must be greater than 0 for 1-based regions")?,
+    };
+    Ok((chrom, start, end))
+}
+
+/// Read all regions of a regions file.
+fn read_regions(
+    regions_file: &PathBuf,
+    comment_char: &char,
+    format: IntervalFormat,
+    coordinates: CoordinateSystem,
+) -> Result<Vec<(String, u32, u32)>, HgIndexError> {
+    let mut reader = build_tsv_reader(regions_file, Some(*comment_char as u8), true, false)?;
+    reader
+        .records()
+        .map(|record| parse_region_record(&record?, format, coordinates))
+        .collect()
+}
+
 #[inline(always)]
 fn write_tsv_bytes<W: std::io::Write>(
-    chrom: &str,
+    chrom: Option<&str>,
     record: &BedRecordSlice<'_>,
     writer: &mut W,
 ) -> Result<(), HgIndexError> {
     // Directly write to the writer without intermediate buffer
-    write!(writer, "{}\t{}\t{}\t", chrom, record.start, record.end)?;
+    if let Some(chrom) = chrom {
+        write!(writer, "{}\t", chrom)?;
+    }
+    write!(writer, "{}\t{}\t", record.start, record.end)?;
     writer.write_all(record.rest)?; // Raw bytes, no conversion
-    writer.write_all(b"\n")?;
     Ok(())
 }
 
-fn parse_region(region: &str) -> Result<(&str, u32, u32), HgIndexError> {
-    let region_parts: Vec<&str> = region.split(':').collect();
-    if region_parts.len() != 2 {
-        return Err("Invalid region format. Expected seqname:start-end.".into());
-    }
+/// Write only the requested 1-based columns of a record. Columns 1-3 are the
+/// chrom, start, and end; later columns are the tab-delimited fields of `rest`.
+/// Like `write_tsv_bytes`, this leaves the line open for computed columns.
+fn write_columns<W: std::io::Write + ?Sized>(
+    chrom: &str,
+    record: &BedRecordSlice<'_>,
+    columns: &[usize],
+    writer: &mut W,
+) -> Result<(), HgIndexError> {
+    let rest_fields: Vec<&[u8]> = if record.rest.is_empty() {
+        Vec::new()
+    } else {
+        record.rest.split(|&b| b == b'\t').collect()
+    };
 
-    let seqname = region_parts[0];
-    let coords: Vec<&str> = region_parts[1].split('-').collect();
-    if coords.len() != 2 {
-        return Err("Invalid region format. Expected start-end.".into());
+    // Validate before writing so an error never leaves a partial line.
+    let num_columns = 3 + rest_fields.len();
+    if let Some(&column) = columns.iter().find(|&&column| column > num_columns) {
+        return Err(format!(
+            "Column {} requested, but record {}:{}-{} has only {} columns.",
+            column, chrom, record.start, record.end, num_columns
+        )
+        .into());
     }
 
-    let tabix_start: u32 = coords[0].parse().map_err(|_| "Invalid start coordinate.")?;
-    let tabix_end: u32 = coords[1].parse().map_err(|_| "Invalid end coordinate.")?;
-
-    // Convert to 0-based exclusive coordinates
-    let start = tabix_start
-        .checked_sub(1)
-        .ok_or("Start coordinate must be greater than 0")?;
-    let end = tabix_end; // End remains the same as it's exclusive in 0-based
+    for (i, &column) in columns.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b"\t")?;
+        }
+        match column {
+            1 => writer.write_all(chrom.as_bytes())?,
+            2 => write!(writer, "{}", record.start)?,
+            3 => write!(writer, "{}", record.end)?,
+            n => writer.write_all(rest_fields[n - 4])?,
+        }
+    }
+    Ok(())
+}
 
-    Ok((seqname, start, end))
+/// Append any computed columns for a record overlapping `query`, ending the line.
+fn write_computed_columns<W: std::io::Write + ?Sized>(
+    record: &BedRecordSlice<'_>,
+    add_columns: &[ComputedColumn],
+    query: (u32, u32),
+    writer: &mut W,
+) -> Result<(), HgIndexError> {
+    for column in add_columns {
+        write!(writer, "\t{}", column.value(record, query))?;
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
 }
 
 /// Utility function to find a .hgidx file in the current directory
@@ -236,7 +852,13 @@ impl RecordBatch {
     }
 
     #[inline(always)]
-    pub fn push_record(&mut self, chrom: &str, record: &BedRecordSlice<'_>) {
+    pub fn push_record(
+        &mut self,
+        chrom: &str,
+        record: &BedRecordSlice<'_>,
+        add_columns: &[ComputedColumn],
+        query: (u32, u32),
+    ) {
         // Extend chrom bytes
         self.buffer.extend_from_slice(chrom.as_bytes());
         self.buffer.push(b'\t');
@@ -250,13 +872,25 @@ impl RecordBatch {
         self.buffer.extend_from_slice(end_str.as_bytes());
         self.buffer.push(b'\t');
 
-        // Rest of record and newline
+        // Rest of record, computed columns, and newline
         self.buffer.extend_from_slice(record.rest);
+        for column in add_columns {
+            self.buffer.push(b'\t');
+            let value = self.start_buffer.format(column.value(record, query));
+            self.buffer.extend_from_slice(value.as_bytes());
+        }
         self.buffer.push(b'\n');
 
         self.records_seen += 1;
     }
 
+    /// Prefix the next record with a key column.
+    #[inline(always)]
+    pub fn push_key(&mut self, key: &str) {
+        self.buffer.extend_from_slice(key.as_bytes());
+        self.buffer.push(b'\t');
+    }
+
     // Flush when batch is large enough
     #[inline(always)]
     pub fn should_flush(&self) -> bool {
@@ -264,7 +898,10 @@ impl RecordBatch {
     }
 
     #[inline(always)]
-    pub fn write_batch<W: std::io::Write>(&mut self, writer: &mut W) -> Result<(), HgIndexError> {
+    pub fn write_batch<W: std::io::Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+    ) -> Result<(), HgIndexError> {
         writer.write_all(&self.buffer)?;
         self.clear();
         Ok(())
@@ -132,22 +132,6 @@ fn generate_extra_field<R: Rng>(rng: &mut R, feature_types: &[&str]) -> String {
     }
 }
 
-#[allow(dead_code)]
-fn compare_chromosomes(a: &str, b: &str) -> std::cmp::Ordering {
-    fn chrom_value(chrom: &str) -> u32 {
-        chrom
-            .strip_prefix("chr")
-            .and_then(|s| match s {
-                "X" => Some(u32::MAX - 1),
-                "Y" => Some(u32::MAX),
-                num => num.parse().ok(),
-            })
-            .unwrap_or(0)
-    }
-
-    chrom_value(a).cmp(&chrom_value(b))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,14 +146,6 @@ mod tests {
         assert_eq!(records1, records2);
     }
 
-    #[test]
-    fn test_chromosome_ordering() {
-        assert!(compare_chromosomes("chr1", "chr2").is_lt());
-        assert!(compare_chromosomes("chr2", "chr1").is_gt());
-        assert!(compare_chromosomes("chrX", "chrY").is_lt());
-        assert!(compare_chromosomes("chr1", "chrX").is_lt());
-    }
-
     #[test]
     fn test_output_file_creation() -> Result<(), HgIndexError> {
         let test_file = NamedTempFile::new().unwrap();
@@ -0,0 +1,49 @@
+// bin/commands/reindex.rs
+
+use clap::Args;
+use hgindex::error::HgIndexError;
+use hgindex::store::GenomicDataStore;
+use hgindex::{BedRecord, BinningSchema};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Args)]
+pub struct ReindexArgs {
+    /// Input .hgidx directory whose index to rebuild
+    #[arg(value_name = "data.hgidx")]
+    pub input: PathBuf,
+
+    /// Hierarchical binning schema for the rebuilt index
+    #[arg(long, value_enum)]
+    pub schema: BinningSchema,
+
+    /// Recover a store whose pack crashed before writing its index, from the
+    /// data files alone. Pack metadata is not recovered.
+    #[arg(long)]
+    pub recover: bool,
+}
+
+pub fn run(args: ReindexArgs) -> Result<(), HgIndexError> {
+    let start = Instant::now();
+
+    if !args.input.exists() {
+        return Err(format!("Input file {} does not exist.", args.input.display()).into());
+    }
+    eprintln!(
+        "{} {} with the {} schema",
+        if args.recover {
+            "Recovering"
+        } else {
+            "Reindexing"
+        },
+        args.input.display(),
+        args.schema
+    );
+    let count = if args.recover {
+        GenomicDataStore::<BedRecord>::recover(&args.input, None, &args.schema)?
+    } else {
+        GenomicDataStore::<BedRecord>::reindex(&args.input, None, &args.schema)?
+    };
+    eprintln!("Indexed {} records in {:?}", count, start.elapsed());
+    Ok(())
+}
@@ -2,6 +2,7 @@ use clap::Args;
 use hgindex::error::HgIndexError;
 use hgindex::index::BinningIndex;
 use hgindex::stats::BinningStats;
+use std::io::Write;
 use std::path::PathBuf;
 use std::time::Instant;
 
@@ -14,6 +15,16 @@ pub struct StatsArgs {
     /// Print bin indices for debugging purposes
     #[arg(long)]
     pub show_bins: bool,
+
+    /// Print each chromosome's record count and extent, as a table of
+    /// chrom, count, min start, and max end
+    #[arg(long)]
+    pub chrom_summary: bool,
+
+    /// Print the full statistics as JSON on stdout, instead of the summary
+    /// and performance report, e.g. to track index quality over time
+    #[arg(long, conflicts_with_all = ["show_bins", "chrom_summary"])]
+    pub json: bool,
 }
 
 pub fn run(args: StatsArgs) -> Result<(), HgIndexError> {
@@ -28,6 +39,15 @@ pub fn run(args: StatsArgs) -> Result<(), HgIndexError> {
     eprintln!("Analyzing index structure and performance...");
     let stats = BinningStats::analyze(&index);
 
+    if args.json {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &stats)
+            .map_err(|e| HgIndexError::SerializationError(e.to_string()))?;
+        writeln!(stdout)?;
+        eprintln!("Analysis completed in {:?}", start.elapsed());
+        return Ok(());
+    }
+
     // Print statistics summary
     eprintln!("\nIndex Analysis Summary:");
     stats.print_summary();
@@ -41,11 +61,21 @@ pub fn run(args: StatsArgs) -> Result<(), HgIndexError> {
         eprintln!("\nBin Indices:");
         for (chrom, sequence_index) in &index.sequences {
             println!("Chromosome: {}", chrom);
-            let bins: Vec<_> = sequence_index.bins.keys().cloned().collect(); // Collect bin IDs
+            let bins: Vec<_> = sequence_index
+                .iter_bins()
+                .map(|(bin_id, _)| bin_id)
+                .collect(); // Collect bin IDs
             println!("  Bins: {:?}", bins);
         }
     }
 
+    if args.chrom_summary {
+        println!("chrom\tcount\tmin_start\tmax_end");
+        for (chrom, count, min_start, max_end) in index.chrom_summary() {
+            println!("{}\t{}\t{}\t{}", chrom, count, min_start, max_end);
+        }
+    }
+
     let duration = start.elapsed();
     eprintln!("Analysis completed in {:?}", duration);
 
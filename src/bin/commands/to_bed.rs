@@ -0,0 +1,96 @@
+// bin/commands/to_bed.rs
+
+use clap::Args;
+use flate2::Compression;
+use hgindex::error::HgIndexError;
+use hgindex::store::GenomicDataStore;
+use hgindex::{BedRecord, BgzfWriter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Args)]
+pub struct ToBedArgs {
+    /// Input .hgidx directory to export
+    #[arg(value_name = "data.hgidx")]
+    pub input: PathBuf,
+
+    /// Output BED file; a .gz suffix writes BGZF, ready for `tabix -p bed`.
+    /// If not specified, uncompressed BED is written to stdout.
+    #[arg(short, long, value_name = "out.bed.gz")]
+    pub output: Option<PathBuf>,
+
+    /// Write 1-based, fully-closed start coordinates (e.g. to restore a
+    /// source packed with `pack --one-based`) instead of 0-based BED starts
+    #[arg(long)]
+    pub one_based: bool,
+}
+
+pub fn run(args: ToBedArgs) -> Result<(), HgIndexError> {
+    let duration_start = Instant::now();
+
+    if !args.input.exists() {
+        return Err(format!("Input file {} does not exist.", args.input.display()).into());
+    }
+    let mut store = GenomicDataStore::<BedRecord>::open(&args.input, None)?;
+
+    let start_offset = u32::from(args.one_based);
+    let total_records = match &args.output {
+        Some(path) if path.extension().is_some_and(|ext| ext == "gz") => {
+            let file = BufWriter::new(File::create(path)?);
+            let mut writer = BgzfWriter::new(file, Compression::default());
+            let total_records = write_bed(&mut store, &mut writer, start_offset)?;
+            // Write the final block and EOF marker, surfacing any error
+            writer.finish()?.flush()?;
+            total_records
+        }
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            let total_records = write_bed(&mut store, &mut writer, start_offset)?;
+            writer.flush()?;
+            total_records
+        }
+        None => {
+            let mut writer = BufWriter::new(io::stdout());
+            let total_records = write_bed(&mut store, &mut writer, start_offset)?;
+            writer.flush()?;
+            total_records
+        }
+    };
+
+    eprintln!(
+        "Exported {} records in {:?}",
+        total_records,
+        duration_start.elapsed()
+    );
+    Ok(())
+}
+
+/// Write every record, with sequences in natural order and each streamed in
+/// coordinate order, so the output is sorted as tabix requires.
+fn write_bed<W: Write>(
+    store: &mut GenomicDataStore<BedRecord>,
+    writer: &mut W,
+    start_offset: u32,
+) -> Result<usize, HgIndexError> {
+    let mut total_records = 0;
+    for chrom in store.sequence_names() {
+        total_records += store.map_sequence(&chrom, |record| {
+            write!(
+                writer,
+                "{}\t{}\t{}",
+                chrom,
+                record.start + start_offset,
+                record.end
+            )?;
+            if !record.rest.is_empty() {
+                writer.write_all(b"\t")?;
+                writer.write_all(record.rest)?;
+            }
+            writer.write_all(b"\n")?;
+            Ok(())
+        })?;
+    }
+    Ok(total_records)
+}
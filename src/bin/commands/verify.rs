@@ -0,0 +1,48 @@
+// bin/commands/verify.rs
+
+use clap::Args;
+use hgindex::error::HgIndexError;
+use hgindex::store::GenomicDataStore;
+use hgindex::BedRecord;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Input .hgidx directory to check
+    #[arg(value_name = "data.hgidx")]
+    pub input: PathBuf,
+}
+
+pub fn run(args: VerifyArgs) -> Result<(), HgIndexError> {
+    if !args.input.exists() {
+        return Err(format!("Input file {} does not exist.", args.input.display()).into());
+    }
+    let store = GenomicDataStore::<BedRecord>::open(&args.input, None)?;
+    let report = store.verify()?;
+
+    for chrom in &report.missing_files {
+        eprintln!("{}: data file is missing", chrom);
+    }
+    for (chrom, reason) in &report.bad_headers {
+        eprintln!("{}: {}", chrom, reason);
+    }
+    for (chrom, offset, length) in &report.bad_records {
+        eprintln!(
+            "{}: record of length {} at offset {} is truncated or corrupt",
+            chrom, length, offset
+        );
+    }
+    for (chrom, bytes) in &report.orphaned {
+        eprintln!("{}: {} bytes not covered by the index", chrom, bytes);
+    }
+
+    if !report.is_ok() {
+        return Err(format!(
+            "{} is damaged; see the problems above",
+            args.input.display()
+        )
+        .into());
+    }
+    eprintln!("Checked {} records: OK", report.records);
+    Ok(())
+}
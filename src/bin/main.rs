@@ -5,9 +5,13 @@ mod commands;
 use crate::commands::random_bed;
 //#[cfg(all(feature = "dev"))]
 //use crate::commands::analyze;
+use crate::commands::diff;
 use crate::commands::pack;
 use crate::commands::query;
+use crate::commands::reindex;
 use crate::commands::stats;
+use crate::commands::to_bed;
+use crate::commands::verify;
 use clap::Parser;
 use hgindex::error::HgIndexError;
 
@@ -16,6 +20,10 @@ use hgindex::error::HgIndexError;
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Log library progress events to stderr (-v for info, -vv for debug).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
 }
 
 #[derive(clap::Subcommand)]
@@ -23,28 +31,57 @@ enum Commands {
     //#[cfg(feature = "dev")]
     ///// Analyze index structure and performance metrics
     //Analyze(analyze::AnalyzeArgs),
+    /// Report features present in one store but not another.
+    Diff(diff::DiffArgs),
     /// Block-compress and index a file.
     Pack(pack::PackArgs),
     Query(query::QueryArgs),
     #[cfg(all(feature = "cli", feature = "dev"))]
     /// Generate a random BED file for benchmarking (only with dev feature)
     RandomBed(random_bed::RandomBedArgs),
+    /// Rebuild a store's index under another schema, from its data files.
+    Reindex(reindex::ReindexArgs),
     Stats(stats::StatsArgs),
+    /// Export a store as sorted BED (BGZF-compressed for .gz output).
+    ToBed(to_bed::ToBedArgs),
+    /// Check a store's data files against its index for truncation or corruption.
+    Verify(verify::VerifyArgs),
 }
 
 pub fn run() -> Result<(), HgIndexError> {
     let cli = Cli::parse();
+    init_tracing(cli.verbose);
     match cli.command {
         //#[cfg(feature = "dev")]
         //Commands::Analyze(args) => analyze::run(args),
+        Commands::Diff(args) => diff::run(args),
         Commands::Pack(args) => pack::run(args),
         Commands::Query(args) => query::run(args),
         #[cfg(feature = "dev")]
         Commands::RandomBed(args) => random_bed::run(args),
+        Commands::Reindex(args) => reindex::run(args),
         Commands::Stats(args) => stats::run(args),
+        Commands::ToBed(args) => to_bed::run(args),
+        Commands::Verify(args) => verify::run(args),
     }
 }
 
+/// Subscribe a stderr logger for the library's `tracing` events; warnings
+/// only unless raised with `-v`.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => tracing::Level::WARN,
+        1 => tracing::Level::INFO,
+        _ => tracing::Level::DEBUG,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 fn main() {
     #[cfg(feature = "cli")]
     if let Err(e) = run() {
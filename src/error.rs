@@ -21,6 +21,23 @@ pub enum HgIndexError {
         current: u32,
     },
 
+    #[error(
+        "Sequence {0} appeared again after other sequences; input must be grouped by sequence"
+    )]
+    UngroupedSequence(String),
+
+    #[error("Feature {chrom}:{start}-{end} ends beyond the maximum coordinate {max} addressable by the {schema} binning schema; use a schema with more levels (e.g. Dense)")]
+    UnaddressableFeature {
+        chrom: String,
+        start: u32,
+        end: u32,
+        schema: String,
+        max: u64,
+    },
+
+    #[error("Query matched more than {limit} records, the store's result limit")]
+    ResultLimitExceeded { limit: usize },
+
     #[error("IO error: {0}")]
     IOError(#[from] std::io::Error),
 
@@ -36,6 +53,9 @@ pub enum HgIndexError {
     #[error("Deserialization error: {0}")]
     DeserializationError(String),
 
+    #[error("Corrupt record at offset {offset}: {reason}")]
+    CorruptRecord { offset: u64, reason: String },
+
     #[error("Invalid offset error: {0}")]
     InvalidOffset(String),
 
@@ -43,7 +63,7 @@ pub enum HgIndexError {
     ParseIntError(#[from] ParseIntError),
 
     #[error("Internal error: {0}")]
-    BoxError(#[from] Box<dyn std::error::Error>),
+    BoxError(#[from] Box<dyn std::error::Error + Send + Sync>),
 
     #[error("{0}")]
     StringError(String),
@@ -57,6 +77,14 @@ pub enum HgIndexError {
     TemplateError(#[from] TemplateError),
 }
 
+// Keep HgIndexError Send + Sync (e.g. to cross spawn_blocking) by
+// flattening errors that aren't to their message
+impl From<Box<dyn std::error::Error>> for HgIndexError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        HgIndexError::BoxError(error.to_string().into())
+    }
+}
+
 // Add a convenience implementation for &str errors
 impl From<&str> for HgIndexError {
     fn from(error: &str) -> Self {
@@ -3,6 +3,8 @@ use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::error::HgIndexError;
+
 /// index/binning.rs
 ///
 /// # Hierarchical Binning
@@ -70,6 +72,15 @@ use std::fmt;
 /// 4. Optimize `linear_shift`:
 ///    - Decrease for clustered dense queries (e.g., `linear_shift = 6`).
 ///    - Increase for sparse datasets (e.g., `linear_shift = 10`).
+///
+/// `base_shift`, `level_shift`, and `num_levels` together fix the largest coordinate a
+/// schema can index, `1 << (base_shift + (num_levels - 1) * level_shift)` (see
+/// `HierarchicalBins::max_coordinate`): e.g. 512Mb for `Tabix`, versus the full `u32` range
+/// for `Dense` and `Sparse`. Check it against your longest sequence when tuning.
+///
+/// Tuned parameters are used through `HierarchicalBins::try_new` (a `BinningSchema::Custom`),
+/// or from the CLI with `pack --base-shift`, `--level-shift`, `--num-levels`, and
+/// `--linear-shift`.
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct HierarchicalBins {
@@ -137,6 +148,17 @@ pub enum BinningSchema {
     UcscNoLinear,
     Dense,
     Sparse,
+    Flat,
+    /// Bins with user-chosen parameters (see `HierarchicalBins::try_new`),
+    /// e.g. tuned to a dataset's feature sizes. Not a CLI schema name; `pack`
+    /// builds one from `--base-shift` and related options.
+    #[cfg_attr(feature = "cli", value(skip))]
+    Custom {
+        base_shift: u32,
+        level_shift: u32,
+        num_levels: usize,
+        linear_shift: Option<u32>,
+    },
 }
 
 impl fmt::Display for BinningSchema {
@@ -148,6 +170,23 @@ impl fmt::Display for BinningSchema {
             BinningSchema::UcscNoLinear => write!(f, "UCSC (No Linear Index)"),
             BinningSchema::Dense => write!(f, "Dense"),
             BinningSchema::Sparse => write!(f, "Sparse"),
+            BinningSchema::Flat => write!(f, "Flat"),
+            BinningSchema::Custom {
+                base_shift,
+                level_shift,
+                num_levels,
+                linear_shift,
+            } => {
+                write!(
+                    f,
+                    "Custom (base shift {}, level shift {}, {} levels, ",
+                    base_shift, level_shift, num_levels
+                )?;
+                match linear_shift {
+                    Some(shift) => write!(f, "linear shift {})", shift),
+                    None => write!(f, "no linear index)"),
+                }
+            }
         }
     }
 }
@@ -161,6 +200,19 @@ impl HierarchicalBins {
             BinningSchema::UcscNoLinear => Self::ucsc_no_linear(),
             BinningSchema::Dense => Self::dense(),
             BinningSchema::Sparse => Self::sparse(),
+            BinningSchema::Flat => Self::flat(),
+            BinningSchema::Custom {
+                base_shift,
+                level_shift,
+                num_levels,
+                linear_shift,
+            } => Self::new(
+                schema.clone(),
+                *base_shift,
+                *level_shift,
+                *num_levels,
+                *linear_shift,
+            ),
         }
     }
 
@@ -171,12 +223,10 @@ impl HierarchicalBins {
         num_levels: usize,
         linear_shift: Option<u32>,
     ) -> Self {
+        Self::check_params(base_shift, level_shift, num_levels, linear_shift)
+            .unwrap_or_else(|e| panic!("{}", e));
         let levels = calc_level_sizes(level_shift, num_levels);
         let bin_offsets = calc_offsets_from_levels(&levels);
-        // TODO: check
-        if base_shift + (num_levels as u32 - 1) * level_shift > 63 {
-            panic!("Shift exceeds maximum allowable bit width!");
-        }
 
         Self {
             schema: schema_type,
@@ -189,6 +239,111 @@ impl HierarchicalBins {
         }
     }
 
+    /// Bins with custom parameters, as a `BinningSchema::Custom`: the finest
+    /// bins span `2^base_shift` bp, each coarser level's bins are
+    /// `2^level_shift` times wider, and `linear_shift` sets the linear
+    /// index's window size (`None` for no linear index). Unlike `new`,
+    /// invalid parameters are an error rather than a panic.
+    pub fn try_new(
+        base_shift: u32,
+        level_shift: u32,
+        num_levels: usize,
+        linear_shift: Option<u32>,
+    ) -> Result<Self, HgIndexError> {
+        Self::check_params(base_shift, level_shift, num_levels, linear_shift)?;
+        let schema = BinningSchema::Custom {
+            base_shift,
+            level_shift,
+            num_levels,
+            linear_shift,
+        };
+        Ok(Self::new(
+            schema,
+            base_shift,
+            level_shift,
+            num_levels,
+            linear_shift,
+        ))
+    }
+
+    // Bin parameters must give a top-level shift within 63 bits, and few
+    // enough bins that bin IDs fit in a u32
+    fn check_params(
+        base_shift: u32,
+        level_shift: u32,
+        num_levels: usize,
+        linear_shift: Option<u32>,
+    ) -> Result<(), HgIndexError> {
+        if num_levels == 0 {
+            return Err("Binning needs at least one level".into());
+        }
+        // Coordinates are u32, shifted right by these
+        if base_shift >= u32::BITS || level_shift >= u32::BITS {
+            return Err(format!(
+                "Base shift {} and level shift {} must each be below {}",
+                base_shift,
+                level_shift,
+                u32::BITS
+            )
+            .into());
+        }
+        let top_shift = (num_levels as u64 - 1) * level_shift as u64 + base_shift as u64;
+        if top_shift > 63 {
+            return Err(format!(
+                "Base shift {} plus {} levels of shift {} is {} bits, more than the maximum 63",
+                base_shift,
+                num_levels - 1,
+                level_shift,
+                top_shift
+            )
+            .into());
+        }
+        let total_bins = (0..num_levels as u64)
+            .try_fold(0u64, |sum, level| {
+                let bins = 1u64.checked_shl((level * level_shift as u64) as u32)?;
+                sum.checked_add(bins)
+            })
+            .filter(|&total| total <= u32::MAX as u64);
+        if total_bins.is_none() {
+            return Err(format!(
+                "{} levels of shift {} need more bins than a u32 bin ID can number",
+                num_levels, level_shift
+            )
+            .into());
+        }
+        if let Some(shift) = linear_shift.filter(|&shift| shift >= u32::BITS) {
+            return Err(format!(
+                "Linear shift {} leaves no windows for u32 coordinates",
+                shift
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Custom bins suited to features of these widths (e.g. a sample of the
+    /// input): the finest bins are the smallest power of two at least the
+    /// median width (within 4kb to 16Mb), levels are 8x wider as in tabix,
+    /// and there are enough levels for the top-level bin to span every `u32`
+    /// coordinate, so any feature fits. Without sizes, returns the `Dense`
+    /// schema's bins.
+    pub fn tune_for_sizes(sizes: &[u32]) -> Self {
+        const LEVEL_SHIFT: u32 = 3;
+        if sizes.is_empty() {
+            return Self::dense();
+        }
+        let mut sorted = sizes.to_vec();
+        sorted.sort_unstable();
+        let median = sorted[sorted.len() / 2];
+
+        // The smallest shift with 1 << shift >= size
+        let ceil_log2 = |size: u32| u32::BITS - size.saturating_sub(1).leading_zeros();
+        let base_shift = ceil_log2(median).clamp(12, 24);
+        let num_levels = (u32::BITS - base_shift).div_ceil(LEVEL_SHIFT) as usize + 1;
+        Self::try_new(base_shift, LEVEL_SHIFT, num_levels, Some(base_shift))
+            .expect("tuned parameters are valid")
+    }
+
     pub fn tabix() -> Self {
         Self::new(BinningSchema::Tabix, 14, 3, 6, Some(14))
     }
@@ -213,27 +368,72 @@ impl HierarchicalBins {
         Self::new(BinningSchema::Sparse, 20, 4, 4, Some(16))
     }
 
+    /// A single-level schema with one bin covering coordinates up to 2^31, and
+    /// no linear index. Used for short sequences (e.g. unplaced or alt
+    /// contigs), where a hierarchy of bins only adds overhead.
+    pub fn flat() -> Self {
+        Self::new(BinningSchema::Flat, 31, 3, 1, None)
+    }
+
+    /// One past the largest coordinate this binning can index: the span of its
+    /// single top-level bin, `1 << (base_shift + (num_levels - 1) * level_shift)`.
+    /// Use it to validate inputs or choose a schema for long sequences.
+    pub fn max_coordinate(&self) -> u64 {
+        let shift = self.base_shift + (self.num_levels as u32 - 1) * self.level_shift;
+        1u64 << shift
+    }
+
     pub fn uses_linear_index(&self) -> bool {
         self.linear_shift.is_some()
     }
 
     /// Compute the smallest bin fully containing the range `[start, end)`.
-    pub fn region_to_bin(&self, start: u32, end: u32) -> u32 {
+    ///
+    /// Returns `UnaddressableFeature` if `end` lies beyond
+    /// [`max_coordinate`](Self::max_coordinate), i.e. no bin contains the
+    /// range. The error's `chrom` is left empty for the caller to fill in.
+    pub fn region_to_bin(&self, start: u32, end: u32) -> Result<u32, HgIndexError> {
+        // A range entirely past the maximum would otherwise shift down into a
+        // shared top-level bin, since the bin ids wrap at every level
+        if end as u64 > self.max_coordinate() {
+            return Err(self.unaddressable(start, end));
+        }
+
         let mut start_bin = start >> self.base_shift;
         let mut end_bin = (end - 1) >> self.base_shift;
 
         for &offset in &self.bin_offsets {
             if start_bin == end_bin {
-                return offset + start_bin;
+                return Ok(offset + start_bin);
             }
             start_bin >>= self.level_shift;
             end_bin >>= self.level_shift;
         }
 
-        panic!(
-            "start {}, end {} out of range for region_to_bin",
-            start, end
-        );
+        Err(self.unaddressable(start, end))
+    }
+
+    fn unaddressable(&self, start: u32, end: u32) -> HgIndexError {
+        HgIndexError::UnaddressableFeature {
+            chrom: String::new(),
+            start,
+            end,
+            schema: self.schema.to_string(),
+            max: self.max_coordinate(),
+        }
+    }
+
+    /// The level of `bin_id`, where level 0 is the single coarsest bin
+    /// spanning every coordinate and the last level has the finest bins
+    /// (as in `BinningStats`' per-level statistics).
+    pub fn bin_level(&self, bin_id: u32) -> usize {
+        // bin_offsets run from the finest level's (largest) offset down to 0
+        let finest_first = self
+            .bin_offsets
+            .iter()
+            .position(|&offset| offset <= bin_id)
+            .unwrap_or(self.bin_offsets.len() - 1);
+        self.bin_offsets.len() - 1 - finest_first
     }
 
     /// Compute all bins potentially overlapping the range `[start, end)`.
@@ -251,13 +451,14 @@ impl HierarchicalBins {
         bins
     }
 
-    pub fn region_to_bins_iter(&self, start: u32, end: u32) -> RegionToBins {
+    pub fn region_to_bins_iter(&self, start: u32, end: u32) -> RegionToBins<'_> {
         let start_bin = start >> self.base_shift;
         let end_bin = (end - 1) >> self.base_shift;
 
         RegionToBins {
             current_level: 0,
             start_bin,
+            current_bin: start_bin,
             end_bin,
             bin_offsets: &self.bin_offsets,
             level_shift: self.level_shift,
@@ -267,7 +468,9 @@ impl HierarchicalBins {
 
 pub struct RegionToBins<'a> {
     current_level: usize,
+    // The first and last bins of the region at the current level
     start_bin: u32,
+    current_bin: u32,
     end_bin: u32,
     bin_offsets: &'a [u32],
     level_shift: u32,
@@ -282,19 +485,20 @@ impl Iterator for RegionToBins<'_> {
         }
 
         // Return the current bin
-        let current_bin = self.bin_offsets[self.current_level] + self.start_bin;
+        let current_bin = self.bin_offsets[self.current_level] + self.current_bin;
 
-        if self.start_bin < self.end_bin {
+        if self.current_bin < self.end_bin {
             // Move to the next bin within the current level
-            self.start_bin += 1;
+            self.current_bin += 1;
         } else {
             // Move to the next level
             self.current_level += 1;
 
             if self.current_level < self.bin_offsets.len() {
-                // Reset bins for the next level
+                // Reset bins for the next level, from the region's first bin
                 self.start_bin >>= self.level_shift;
                 self.end_bin >>= self.level_shift;
+                self.current_bin = self.start_bin;
             }
         }
 
@@ -376,30 +580,49 @@ mod tests {
         assert_eq!(offsets[4] + bin_counts[4] - 1, 4095); // 4096 bins
     }
 
+    #[test]
+    fn test_bin_level() {
+        let bins = HierarchicalBins::ucsc();
+        assert_eq!(bins.bin_level(0), 0);
+        assert_eq!(
+            bins.bin_level(bins.region_to_bin(0, 500_000_000).unwrap()),
+            0
+        );
+        assert_eq!(
+            bins.bin_level(bins.region_to_bin(0, 60_000_000).unwrap()),
+            1
+        );
+        let finest = bins.region_to_bin(1000, 2000).unwrap();
+        assert_eq!(bins.bin_level(finest), bins.num_levels - 1);
+    }
+
     #[test]
     fn test_region_to_bin() {
         let index = HierarchicalBins::ucsc();
 
         // Test cases from UCSC example in documentation:
         // "100_000_000 >> 17" gives bin 762
-        assert_eq!(index.region_to_bin(100_000_000, 100_000_100), 762 + 585);
+        assert_eq!(
+            index.region_to_bin(100_000_000, 100_000_100).unwrap(),
+            762 + 585
+        );
 
         // Test different size ranges that should go into different bin levels
 
         // Small range (fits in level 4 - 128kb bins)
-        assert_eq!(index.region_to_bin(0, 1000), 585); // Should be first bin at finest level
+        assert_eq!(index.region_to_bin(0, 1000).unwrap(), 585); // Should be first bin at finest level
 
         // 1MB range (should go to level 3)
-        assert_eq!(index.region_to_bin(1_000_000, 2_000_000), 9); // Level 2 offset + bin 0
+        assert_eq!(index.region_to_bin(1_000_000, 2_000_000).unwrap(), 9); // Level 2 offset + bin 0
 
         // 10MB range (should go to level 1 - 64MB bins)
-        assert_eq!(index.region_to_bin(10_000_000, 20_000_000), 1); // Level 1 offset + bin 0
+        assert_eq!(index.region_to_bin(10_000_000, 20_000_000).unwrap(), 1); // Level 1 offset + bin 0
 
         // 100MB range (goes to level 0 - 512MB bins)
-        assert_eq!(index.region_to_bin(100_000_000, 200_000_000), 0); // Level 0 offset + bin 0
+        assert_eq!(index.region_to_bin(100_000_000, 200_000_000).unwrap(), 0); // Level 0 offset + bin 0
 
         // 500MB range (should go to level 0)
-        assert_eq!(index.region_to_bin(0, 500_000_000), 0); // Level 0 offset + bin number
+        assert_eq!(index.region_to_bin(0, 500_000_000).unwrap(), 0); // Level 0 offset + bin number
 
         // Test edge cases
 
@@ -407,12 +630,12 @@ mod tests {
         const KiB: u32 = 1024;
 
         // Test exact bin boundaries
-        assert_eq!(index.region_to_bin(0, 128 * KiB), 585); // Exactly one 128kb bin
-        assert_eq!(index.region_to_bin(128 * KiB, 256 * KiB), 586); // Second 128kb bin
+        assert_eq!(index.region_to_bin(0, 128 * KiB).unwrap(), 585); // Exactly one 128kb bin
+        assert_eq!(index.region_to_bin(128 * KiB, 256 * KiB).unwrap(), 586); // Second 128kb bin
 
         // Test adjacent regions get different bins
-        let bin1 = index.region_to_bin(0, 128_000);
-        let bin2 = index.region_to_bin(128_000, 256_000);
+        let bin1 = index.region_to_bin(0, 128_000).unwrap();
+        let bin2 = index.region_to_bin(128_000, 256_000).unwrap();
         assert_ne!(bin1, bin2);
     }
 
@@ -457,6 +680,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_max_coordinate() {
+        assert_eq!(HierarchicalBins::tabix().max_coordinate(), 1 << 29);
+        assert_eq!(
+            HierarchicalBins::tabix_no_linear().max_coordinate(),
+            1 << 29
+        );
+        assert_eq!(HierarchicalBins::ucsc().max_coordinate(), 1 << 29);
+        assert_eq!(HierarchicalBins::ucsc_no_linear().max_coordinate(), 1 << 29);
+        assert_eq!(HierarchicalBins::dense().max_coordinate(), 1 << 41);
+        assert_eq!(HierarchicalBins::sparse().max_coordinate(), 1 << 32);
+        assert_eq!(HierarchicalBins::flat().max_coordinate(), 1 << 31);
+
+        // The last coordinate below the maximum lands in the top-level bin
+        let bins = HierarchicalBins::tabix();
+        let max = bins.max_coordinate() as u32;
+        assert_eq!(bins.region_to_bin(0, max).unwrap(), 0);
+
+        // Past it, no bin contains the range
+        match bins.region_to_bin(1000, max + 1) {
+            Err(HgIndexError::UnaddressableFeature {
+                start, end, max: m, ..
+            }) => assert_eq!((start, end, m), (1000, max + 1, max as u64)),
+            other => panic!("expected UnaddressableFeature, got {:?}", other),
+        }
+
+        // So does a range entirely past it, which fits no bin at any level
+        for bins in [HierarchicalBins::tabix(), HierarchicalBins::flat()] {
+            let max = bins.max_coordinate() as u32;
+            assert!(matches!(
+                bins.region_to_bin(max + 10_000_000, max + 20_000_000),
+                Err(HgIndexError::UnaddressableFeature { .. })
+            ));
+            assert!(bins.region_to_bin(max, max + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_custom_bins() {
+        let bins = HierarchicalBins::try_new(12, 3, 7, Some(12)).unwrap();
+        assert_eq!(bins.max_coordinate(), 1 << 30);
+        assert_eq!(
+            bins.schema,
+            BinningSchema::Custom {
+                base_shift: 12,
+                level_shift: 3,
+                num_levels: 7,
+                linear_shift: Some(12)
+            }
+        );
+        assert_eq!(HierarchicalBins::from_schema(&bins.schema), bins);
+        assert_eq!(
+            bins.region_to_bin(0, 4096).unwrap(),
+            *bins.bin_offsets.first().unwrap()
+        );
+
+        // Invalid parameters are errors, not panics
+        assert!(HierarchicalBins::try_new(14, 3, 0, None).is_err());
+        assert!(HierarchicalBins::try_new(32, 3, 1, None).is_err());
+        assert!(HierarchicalBins::try_new(40, 8, 4, None).is_err());
+        assert!(HierarchicalBins::try_new(10, 4, 10, None).is_err());
+        assert!(HierarchicalBins::try_new(14, 3, 6, Some(32)).is_err());
+    }
+
+    #[test]
+    fn test_tune_for_sizes() {
+        // Median 1kb: the 4kb floor, with levels up to 2^33
+        let bins = HierarchicalBins::tune_for_sizes(&[200, 1000, 1000, 5000, 2_000_000]);
+        assert_eq!((bins.base_shift, bins.level_shift), (12, 3));
+        assert_eq!(bins.num_levels, 8);
+        assert_eq!(bins.linear_shift, Some(12));
+        assert!(bins.max_coordinate() >= 1 << 32);
+
+        // Median 100kb: 128kb bins
+        let bins = HierarchicalBins::tune_for_sizes(&[50_000, 100_000, 300_000]);
+        assert_eq!(bins.base_shift, 17);
+        assert_eq!(bins.num_levels, 6);
+        assert!(bins.region_to_bin(0, u32::MAX).is_ok());
+
+        assert_eq!(
+            HierarchicalBins::tune_for_sizes(&[]),
+            HierarchicalBins::dense()
+        );
+    }
+
     fn test_with_all_configs<F>(test_fn: F)
     where
         F: Fn(&HierarchicalBins),
@@ -475,12 +783,30 @@ mod tests {
     fn test_bin_boundaries_all_configs() {
         test_with_all_configs(|index| {
             let bin_size = 1 << index.base_shift;
-            let bin1 = index.region_to_bin(0, bin_size);
-            let bin2 = index.region_to_bin(bin_size, 2 * bin_size);
+            let bin1 = index.region_to_bin(0, bin_size).unwrap();
+            let bin2 = index.region_to_bin(bin_size, 2 * bin_size).unwrap();
             assert_ne!(bin1, bin2);
         });
     }
 
+    #[test]
+    fn test_region_to_bins_iter() {
+        // A region spanning several bins at the finer levels, so each coarser
+        // level must restart from the region's first bin, not its last
+        test_with_all_configs(|index| {
+            let start = 7 << index.base_shift;
+            let end = (17 << (index.base_shift + index.level_shift)) + 1;
+            let bins: Vec<u32> = index.region_to_bins_iter(start, end).collect();
+            assert_eq!(bins, index.region_to_bins(start, end));
+
+            let mut first_bin = start >> index.base_shift;
+            for &offset in &index.bin_offsets {
+                assert!(bins.contains(&(offset + first_bin)));
+                first_bin >>= index.level_shift;
+            }
+        });
+    }
+
     proptest! {
         #[test]
         fn test_region_to_bins_properties(start in 0u32..1_000_000, len in 1u32..1_000_000) {
@@ -499,6 +825,10 @@ mod tests {
                 let max_valid_bin = index.bin_offsets[0] + index.levels[index.num_levels - 1];
                 assert!(bins.iter().all(|&bin| bin < max_valid_bin),
                     "Found bin larger than max valid bin {} in {:?}", max_valid_bin, bins);
+
+                // The lazy iterator yields the same bins
+                let iter_bins: Vec<_> = index.region_to_bins_iter(start, end).collect();
+                assert_eq!(iter_bins, bins);
             });
         }
     }
@@ -1,16 +1,69 @@
 // binning_index.rs
 
 use std::{
-    fs::File,
-    io::{BufWriter, Write},
-    path::Path,
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap},
+    fs::{self, File},
+    hash::{BuildHasher, Hasher},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
 };
 
 use super::binning::{BinningSchema, HierarchicalBins};
+use crate::aliases::ChromAliases;
 use crate::error::HgIndexError;
+use crate::region::natural_chrom_cmp;
+use crate::stats::RunningStats;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
+/// Map keyed by sequence name. Names come from input files (and, for a
+/// server, possibly from untrusted requests), so the `siphash` feature swaps
+/// the fast but predictable `FxHash` for std's DoS-resistant SipHash. Bin maps
+/// are keyed by integer bin ids and always use `FxHash`. The map type and the
+/// serialized format are the same either way; only [`SequenceHasher`] differs.
+pub type SequenceMap<V> = HashMap<String, V, SequenceBuildHasher>;
+
+/// Builds the [`SequenceHasher`]s of a [`SequenceMap`].
+#[derive(Clone, Debug, Default)]
+pub struct SequenceBuildHasher {
+    #[cfg(not(feature = "siphash"))]
+    inner: std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    #[cfg(feature = "siphash")]
+    inner: std::collections::hash_map::RandomState,
+}
+
+impl BuildHasher for SequenceBuildHasher {
+    type Hasher = SequenceHasher;
+
+    fn build_hasher(&self) -> SequenceHasher {
+        SequenceHasher(self.inner.build_hasher())
+    }
+}
+
+/// The hasher of sequence names: `FxHash`, or SipHash with the `siphash`
+/// feature.
+pub struct SequenceHasher(
+    #[cfg(not(feature = "siphash"))] rustc_hash::FxHasher,
+    #[cfg(feature = "siphash")] std::collections::hash_map::DefaultHasher,
+);
+
+impl Hasher for SequenceHasher {
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+
+    // `str` hashing ends with a `write_u8`, which FxHash does in one step
+    fn write_u8(&mut self, i: u8) {
+        self.0.write_u8(i)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct LinearIndex {
     entries: Vec<u64>,
@@ -50,9 +103,17 @@ impl LinearIndex {
         }
     }
 
+    /// The smallest offset of any feature overlapping `start`'s window or a
+    /// later one. Empty windows (no overlapping feature) defer to the next
+    /// non-empty window, as tabix backfills them, rather than ruling out
+    /// every feature; `None` if no window at or after `start` is filled.
     pub fn get_min_offset(&self, start: u32) -> Option<u64> {
         let window = (start >> self.shift) as usize;
-        self.entries.get(window).copied()
+        self.entries
+            .get(window..)?
+            .iter()
+            .copied()
+            .find(|&offset| offset != u64::MAX)
     }
 
     pub fn len(&self) -> usize {
@@ -66,21 +127,239 @@ impl LinearIndex {
 pub struct BinningIndex {
     /// Initialized binning
     pub bins: HierarchicalBins,
-    pub sequences: FxHashMap<String, SequenceIndex>,
+    pub sequences: SequenceMap<SequenceIndex>,
     last_chrom: Option<String>,
     last_start: Option<u32>,
     // Store metadata as raw bytes
     metadata_bytes: Option<Vec<u8>>,
+    /// Statistics maintained as features are added
+    running_stats: RunningStats,
+    /// Alternative names resolving to the sequence names in `sequences`
+    chrom_aliases: ChromAliases,
+    /// Known sequence lengths, used to pick a flat binning for short sequences
+    sequence_lengths: SequenceMap<u32>,
+    /// Sequences with a known length at most this use `HierarchicalBins::flat`
+    flat_max_length: u32,
+    /// Lowercase sequence names (before alias lookup) when adding and querying
+    lowercase_chroms: bool,
+    /// If set, each completed sequence index is written to this directory
+    /// when the next sequence starts, rather than held in memory until finalize.
+    #[serde(skip)]
+    spill_dir: Option<PathBuf>,
+    /// Sequences already spilled to `spill_dir`, in the order they were added.
+    #[serde(skip)]
+    spilled: Vec<String>,
+    /// Skip the per-feature sort order check, for input known to be sorted.
+    #[serde(skip)]
+    unchecked_insertion: bool,
+    /// Match zero-width features at either edge of a query window.
+    #[serde(skip)]
+    zero_width_inclusive: bool,
+}
+
+// Index files start with a magic, a layout version, and flags, like data
+// files, so an index written with a different layout (e.g. other per-bin
+// fields) is rejected rather than misread.
+const INDEX_MAGIC: [u8; 4] = *b"GIDI";
+const INDEX_VERSION: u8 = 1;
+// No flags are defined for this version
+const INDEX_FLAGS: u8 = 0;
+const INDEX_HEADER_LEN: usize = INDEX_MAGIC.len() + 2;
+
+fn write_index_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&INDEX_MAGIC)?;
+    writer.write_all(&[INDEX_VERSION, INDEX_FLAGS])
+}
+
+// Validate an index file's header: its magic, and a version and flags this
+// build can read
+fn check_index_header(header: &[u8]) -> Result<(), HgIndexError> {
+    let invalid = |message: String| Err(HgIndexError::DeserializationError(message));
+    match header.get(..INDEX_HEADER_LEN) {
+        Some(header) if header[..4] == INDEX_MAGIC => {
+            let (version, flags) = (header[4], header[5]);
+            if version != INDEX_VERSION {
+                return invalid(format!(
+                    "Unsupported index file version {} (expected {}); repack the store",
+                    version, INDEX_VERSION
+                ));
+            }
+            if flags & !INDEX_FLAGS != 0 {
+                return invalid(format!("Unsupported index file flags {:#04x}", flags));
+            }
+            Ok(())
+        }
+        _ => invalid(
+            "Missing index file header (an unversioned index from before index \
+             headers, or not an index); repack the store"
+                .to_string(),
+        ),
+    }
+}
+
+// The serialized fields of `BinningIndex` after `sequences`, in field order.
+// Indices written or read a sequence at a time (`finalize_spilled` and
+// `open_sequences`) encode these through this one struct, so they share the
+// whole index's bincode layout; new serialized fields go here too.
+#[derive(Serialize, Deserialize)]
+struct IndexTail {
+    last_chrom: Option<String>,
+    last_start: Option<u32>,
+    metadata_bytes: Option<Vec<u8>>,
+    running_stats: RunningStats,
+    chrom_aliases: ChromAliases,
+    sequence_lengths: SequenceMap<u32>,
+    flat_max_length: u32,
+    lowercase_chroms: bool,
+}
+
+/// The range spanned by a bin's features, so queries can skip whole bins
+/// whose features can't overlap.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BinBounds {
+    /// The smallest feature start in the bin.
+    pub min_start: u32,
+    /// The largest feature end in the bin.
+    pub max_end: u32,
+}
+
+impl BinBounds {
+    fn new(start: u32, end: u32) -> Self {
+        BinBounds {
+            min_start: start,
+            max_end: end,
+        }
+    }
+
+    fn extend(&mut self, start: u32, end: u32) {
+        self.min_start = self.min_start.min(start);
+        self.max_end = self.max_end.max(end);
+    }
+
+    fn of(features: &[Feature]) -> Option<Self> {
+        let (first, rest) = features.split_first()?;
+        let mut bounds = BinBounds::new(first.start, first.index_end());
+        rest.iter()
+            .for_each(|f| bounds.extend(f.start, f.index_end()));
+        Some(bounds)
+    }
+
+    /// Whether any feature in the bin could overlap `[start, end)`.
+    #[inline]
+    pub fn may_overlap(&self, start: u32, end: u32) -> bool {
+        self.min_start < end && self.max_end > start
+    }
+}
+
+/// A read-optimized layout of a sequence's bins: bin IDs in sorted order,
+/// each owning a contiguous range of one flat feature array. Lookups binary
+/// search `bin_ids`, and scanning adjacent bins walks contiguous memory,
+/// unlike chasing one heap allocation per bin in a `HashMap`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct FrozenBins {
+    bin_ids: Vec<u32>,
+    /// `features[offsets[i]..offsets[i + 1]]` are the features of `bin_ids[i]`.
+    offsets: Vec<usize>,
+    features: Vec<Feature>,
+    /// The range spanned by each bin's features.
+    bounds: Vec<BinBounds>,
+}
+
+impl FrozenBins {
+    fn from_map(bins: FxHashMap<u32, Vec<Feature>>) -> Self {
+        let mut bins: Vec<(u32, Vec<Feature>)> = bins.into_iter().collect();
+        bins.sort_unstable_by_key(|(bin_id, _)| *bin_id);
+
+        let mut frozen = FrozenBins {
+            bin_ids: Vec::with_capacity(bins.len()),
+            offsets: Vec::with_capacity(bins.len() + 1),
+            features: Vec::with_capacity(bins.iter().map(|(_, f)| f.len()).sum()),
+            bounds: Vec::with_capacity(bins.len()),
+        };
+        frozen.offsets.push(0);
+        for (bin_id, features) in bins {
+            let Some(bounds) = BinBounds::of(&features) else {
+                continue;
+            };
+            frozen.bin_ids.push(bin_id);
+            frozen.bounds.push(bounds);
+            frozen.features.extend(features);
+            frozen.offsets.push(frozen.features.len());
+        }
+        frozen
+    }
+
+    fn into_map(self) -> FxHashMap<u32, Vec<Feature>> {
+        self.iter()
+            .map(|(bin_id, features)| (bin_id, features.to_vec()))
+            .collect()
+    }
+
+    /// The features of a bin, by binary search over the sorted bin IDs.
+    pub fn get(&self, bin_id: u32) -> Option<&[Feature]> {
+        let i = self.bin_ids.binary_search(&bin_id).ok()?;
+        Some(&self.features[self.offsets[i]..self.offsets[i + 1]])
+    }
+
+    /// The features of a bin that may overlap `[start, end)`: `None` if the
+    /// bin is absent or its [`BinBounds`] rule out any overlap.
+    pub fn get_candidates(&self, bin_id: u32, start: u32, end: u32) -> Option<&[Feature]> {
+        let i = self.bin_ids.binary_search(&bin_id).ok()?;
+        if !self.bounds[i].may_overlap(start, end) {
+            return None;
+        }
+        Some(&self.features[self.offsets[i]..self.offsets[i + 1]])
+    }
+
+    /// The range spanned by a bin's features.
+    pub fn bounds(&self, bin_id: u32) -> Option<BinBounds> {
+        let i = self.bin_ids.binary_search(&bin_id).ok()?;
+        Some(self.bounds[i])
+    }
+
+    /// Iterate over `(bin_id, features)` in bin ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &[Feature])> {
+        self.bin_ids
+            .iter()
+            .zip(self.offsets.windows(2))
+            .map(|(&bin_id, range)| (bin_id, &self.features[range[0]..range[1]]))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bin_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bin_ids.is_empty()
+    }
 }
 
 /// SequenceIndex stores the bin indices to the features they
 /// contain fully.
+///
+/// Features are added to the `bins` map; `freeze` (called by
+/// `BinningIndex::finalize`) moves them into a sorted [`FrozenBins`] layout
+/// for reading. Use `bin`, `iter_bins`, and `num_bins` to read either layout.
 #[derive(Debug, Serialize)]
 pub struct SequenceIndex {
-    // Map from bin ID to u64, which can be used as a VirtualOffset.
+    // Map from bin ID to u64, which can be used as a VirtualOffset. Empty
+    // once the index is frozen.
     pub bins: FxHashMap<u32, Vec<Feature>>,
     // Optional linear index for quick region queries
     pub linear_index: Option<LinearIndex>,
+    // Binning used for this sequence instead of the index-wide binning, if set
+    pub binning: Option<HierarchicalBins>,
+    // Read-optimized layout of the bins, if frozen
+    frozen: Option<FrozenBins>,
+    // The range spanned by each bin in `bins`, maintained as features are added
+    bin_bounds: FxHashMap<u32, BinBounds>,
+    // The range spanned by all features; derived from the bin bounds on load
+    #[serde(skip)]
+    bounds: Option<BinBounds>,
+    // The largest start added, for the sort order check; found from the
+    // features on the first check after loading
+    #[serde(skip)]
+    last_start: Option<u32>,
 }
 
 impl Clone for SequenceIndex {
@@ -88,14 +367,23 @@ impl Clone for SequenceIndex {
         Self {
             bins: self.bins.clone(),
             linear_index: self.linear_index.clone(),
+            binning: self.binning.clone(),
+            frozen: self.frozen.clone(),
+            bin_bounds: self.bin_bounds.clone(),
+            bounds: self.bounds,
+            last_start: self.last_start,
         }
     }
 }
 
 impl PartialEq for SequenceIndex {
     fn eq(&self, other: &Self) -> bool {
-        // Compare only bins and linear_index
-        self.bins == other.bins && self.linear_index == other.linear_index
+        // Compare only bins, linear_index, and binning
+        self.bins == other.bins
+            && self.linear_index == other.linear_index
+            && self.binning == other.binning
+            && self.frozen == other.frozen
+            && self.bin_bounds == other.bin_bounds
     }
 }
 
@@ -109,16 +397,26 @@ impl<'de> Deserialize<'de> for SequenceIndex {
         struct Helper {
             bins: FxHashMap<u32, Vec<Feature>>,
             linear_index: Option<LinearIndex>,
+            binning: Option<HierarchicalBins>,
+            frozen: Option<FrozenBins>,
+            bin_bounds: FxHashMap<u32, BinBounds>,
         }
 
         // Deserialize into helper
         let helper = Helper::deserialize(deserializer)?;
 
         // Construct full SequenceIndex with new buffer
-        Ok(SequenceIndex {
+        let mut sequence = SequenceIndex {
             bins: helper.bins,
             linear_index: helper.linear_index,
-        })
+            binning: helper.binning,
+            frozen: helper.frozen,
+            bin_bounds: helper.bin_bounds,
+            bounds: None,
+            last_start: None,
+        };
+        sequence.bounds = sequence.compute_bounds();
+        Ok(sequence)
     }
 }
 
@@ -129,15 +427,161 @@ impl SequenceIndex {
         SequenceIndex {
             bins: FxHashMap::default(),
             linear_index,
+            binning: None,
+            frozen: None,
+            bin_bounds: FxHashMap::default(),
+            bounds: None,
+            last_start: None,
+        }
+    }
+
+    /// Create a new SequenceIndex that uses its own binning rather than the
+    /// index-wide binning passed to its methods.
+    pub fn with_binning(binning: HierarchicalBins) -> Self {
+        let linear_index = LinearIndex::from_schema(&binning);
+        SequenceIndex {
+            bins: FxHashMap::default(),
+            linear_index,
+            binning: Some(binning),
+            frozen: None,
+            bin_bounds: FxHashMap::default(),
+            bounds: None,
+            last_start: None,
+        }
+    }
+
+    /// The smallest start and largest end of the indexed features, or
+    /// `None` if there are none. Kept up to date as features are added.
+    pub fn bounds(&self) -> Option<BinBounds> {
+        self.bounds
+    }
+
+    // Union the per-bin bounds of either layout
+    fn compute_bounds(&self) -> Option<BinBounds> {
+        let mut bin_bounds: Box<dyn Iterator<Item = &BinBounds>> = match &self.frozen {
+            Some(frozen) => Box::new(frozen.bounds.iter()),
+            None => Box::new(self.bin_bounds.values()),
+        };
+        let mut bounds = *bin_bounds.next()?;
+        bin_bounds.for_each(|b| bounds.extend(b.min_start, b.max_end));
+        Some(bounds)
+    }
+
+    /// Whether this sequence has no indexed features.
+    pub fn is_empty(&self) -> bool {
+        self.iter_bins().all(|(_, features)| features.is_empty())
+    }
+
+    /// Move the bins into the read-optimized [`FrozenBins`] layout. Adding a
+    /// feature afterwards thaws them back into the map.
+    pub fn freeze(&mut self) {
+        if !self.bins.is_empty() || self.frozen.is_none() {
+            self.thaw();
+            self.bin_bounds.clear();
+            self.frozen = Some(FrozenBins::from_map(std::mem::take(&mut self.bins)));
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Move frozen bins back into the `bins` map, e.g. to modify them.
+    pub fn thaw(&mut self) {
+        if let Some(frozen) = self.frozen.take() {
+            self.bin_bounds = frozen
+                .bin_ids
+                .iter()
+                .copied()
+                .zip(frozen.bounds.iter().copied())
+                .collect();
+            self.bins = frozen.into_map();
         }
     }
 
+    /// The features of a bin, in either layout.
+    pub fn bin(&self, bin_id: u32) -> Option<&[Feature]> {
+        match &self.frozen {
+            Some(frozen) => frozen.get(bin_id),
+            None => self.bins.get(&bin_id).map(Vec::as_slice),
+        }
+    }
+
+    /// The features of a bin that may overlap `[start, end)`, skipping the
+    /// bin whole if its [`BinBounds`] rule out any overlap.
+    #[inline]
+    fn candidate_bin(&self, bin_id: u32, start: u32, end: u32) -> Option<&[Feature]> {
+        match &self.frozen {
+            Some(frozen) => frozen.get_candidates(bin_id, start, end),
+            None => {
+                let bounds = self.bin_bounds.get(&bin_id)?;
+                if !bounds.may_overlap(start, end) {
+                    return None;
+                }
+                self.bins.get(&bin_id).map(Vec::as_slice)
+            }
+        }
+    }
+
+    /// The range spanned by a bin's features, in either layout.
+    pub fn bin_bounds(&self, bin_id: u32) -> Option<BinBounds> {
+        match &self.frozen {
+            Some(frozen) => frozen.bounds(bin_id),
+            None => self.bin_bounds.get(&bin_id).copied(),
+        }
+    }
+
+    /// The frozen bin layout, if frozen.
+    pub fn frozen(&self) -> Option<&FrozenBins> {
+        self.frozen.as_ref()
+    }
+
+    /// Iterate over `(bin_id, features)`, in bin ID order if frozen and
+    /// arbitrary order otherwise.
+    pub fn iter_bins(&self) -> Box<dyn Iterator<Item = (u32, &[Feature])> + '_> {
+        match &self.frozen {
+            Some(frozen) => Box::new(frozen.iter()),
+            None => Box::new(
+                self.bins
+                    .iter()
+                    .map(|(&bin_id, features)| (bin_id, features.as_slice())),
+            ),
+        }
+    }
+
+    /// The number of non-empty bins.
+    pub fn num_bins(&self) -> usize {
+        match &self.frozen {
+            Some(frozen) => frozen.len(),
+            None => self.bins.len(),
+        }
+    }
+
+    /// The number of indexed features.
+    pub fn num_features(&self) -> usize {
+        self.iter_bins().map(|(_, features)| features.len()).sum()
+    }
+
     pub fn find_overlapping(
         &self,
         bins: &HierarchicalBins,
         start: u32,
         end: u32,
     ) -> Vec<(u64, u64)> {
+        self.find_overlapping_at_most(bins, start, end, usize::MAX)
+    }
+
+    /// Like `find_overlapping`, but stopping once `max` features are found,
+    /// e.g. to tell that a query exceeds a result limit without collecting
+    /// every overlap.
+    pub fn find_overlapping_at_most(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+        max: usize,
+    ) -> Vec<(u64, u64)> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
         let min_offset = self
             .linear_index
             .as_ref()
@@ -145,26 +589,326 @@ impl SequenceIndex {
             .unwrap_or(0);
 
         // Pre-allocate results with an estimate based on bin count
-        let estimated_capacity = bins.region_to_bins(start, end).len() * 10; // Assume ~10 features per bin
+        let candidate_bins = bins.region_to_bins(start, end);
+        let estimated_capacity = (candidate_bins.len() * 10).min(max); // Assume ~10 features per bin
         let mut results = Vec::with_capacity(estimated_capacity);
 
-        for &bin_id in bins.region_to_bins(start, end).iter() {
-            if let Some(features) = self.bins.get(&bin_id) {
+        for &bin_id in candidate_bins.iter() {
+            if results.len() >= max {
+                break;
+            }
+            if let Some(features) = self.candidate_bin(bin_id, start, end) {
                 // SIMD?
                 // Filter features within the bin
-                results.extend(features.iter().filter_map(|feature| {
-                    if feature.index >= min_offset && feature.start < end && feature.end > start {
-                        Some((feature.index, feature.length))
-                    } else {
-                        None
-                    }
-                }));
+                let remaining = max - results.len();
+                results.extend(
+                    features
+                        .iter()
+                        .filter_map(|feature| {
+                            if feature.index >= min_offset
+                                && feature.start < end
+                                && feature.end > start
+                            {
+                                Some((feature.index, feature.length))
+                            } else {
+                                None
+                            }
+                        })
+                        .take(remaining),
+                );
             }
         }
 
         results
     }
 
+    /// Like `find_overlapping`, but ordered by feature start, then end (ties
+    /// broken by index, i.e. insertion order). Each bin's features are already
+    /// start-sorted, so this k-way merges the candidate bins with a heap of
+    /// one entry per bin rather than sorting all results; only runs sharing a
+    /// start are sorted by end.
+    pub fn find_overlapping_sorted(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> Vec<(u64, u64)> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        let min_offset = self
+            .linear_index
+            .as_ref()
+            .and_then(|index| index.get_min_offset(start))
+            .unwrap_or(0);
+
+        // The position of the next overlapping feature in a bin at or after `from`
+        let next_match = |features: &[Feature], from: usize| {
+            features[from..]
+                .iter()
+                .take_while(|feature| feature.start < end)
+                .position(|feature| feature.index >= min_offset && feature.end > start)
+                .map(|i| from + i)
+        };
+
+        let lists: Vec<&[Feature]> = bins
+            .region_to_bins_iter(start, end)
+            .filter_map(|bin_id| self.candidate_bin(bin_id, start, end))
+            .collect();
+        let mut heap = BinaryHeap::with_capacity(lists.len());
+        for (list, features) in lists.iter().enumerate() {
+            if let Some(i) = next_match(features, 0) {
+                heap.push(Reverse((features[i].start, features[i].index, list, i)));
+            }
+        }
+
+        let mut merged: Vec<&Feature> = Vec::new();
+        while let Some(Reverse((_, _, list, i))) = heap.pop() {
+            let features = lists[list];
+            merged.push(&features[i]);
+            if let Some(i) = next_match(features, i + 1) {
+                heap.push(Reverse((features[i].start, features[i].index, list, i)));
+            }
+        }
+        for run in merged.chunk_by_mut(|a, b| a.start == b.start) {
+            if run.len() > 1 {
+                run.sort_unstable_by_key(|feature| (feature.end, feature.index));
+            }
+        }
+        merged
+            .into_iter()
+            .map(|feature| (feature.index, feature.length))
+            .collect()
+    }
+
+    /// Like `find_overlapping`, but return the overlapping features themselves.
+    pub fn overlapping_features(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> Vec<&Feature> {
+        self.candidate_features(bins, start, end)
+            .filter(|feature| feature.start < end && feature.end > start)
+            .collect()
+    }
+
+    /// The features overlapping `[start, end)`, grouped by the level of the
+    /// bin they were assigned to (see [`HierarchicalBins::bin_level`]), in
+    /// level order from coarsest; levels without overlaps are left out.
+    pub fn overlapping_features_by_level(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> Vec<(usize, Vec<&Feature>)> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        let mut levels: Vec<Vec<&Feature>> = vec![Vec::new(); bins.num_levels];
+        for bin_id in bins.region_to_bins_iter(start, end) {
+            if let Some(features) = self.candidate_bin(bin_id, start, end) {
+                levels[bins.bin_level(bin_id)].extend(
+                    features
+                        .iter()
+                        .filter(|feature| feature.start < end && feature.end > start),
+                );
+            }
+        }
+        levels
+            .into_iter()
+            .enumerate()
+            .filter(|(_, features)| !features.is_empty())
+            .collect()
+    }
+
+    /// Like `overlapping_features`, but also matching zero-width features
+    /// at a point `start..=end`, i.e. touching either edge of the window.
+    pub fn touching_features(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> Vec<&Feature> {
+        self.touching_iter(bins, start, end).collect()
+    }
+
+    // The features `touching_features` returns, lazily
+    fn touching_iter<'a, 'b>(
+        &'a self,
+        bins: &'b HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> impl Iterator<Item = &'a Feature> + 'b
+    where
+        'a: 'b,
+    {
+        // Zero-width features are binned as [point, point + 1)
+        self.candidate_features(bins, start, end.saturating_add(1))
+            .filter(move |feature| {
+                if feature.start == feature.end {
+                    start <= feature.start && feature.start <= end
+                } else {
+                    feature.start < end && feature.end > start
+                }
+            })
+    }
+
+    // The features of the bins that may overlap `[start, end)`, less those the
+    // linear index rules out, without checking their coordinates
+    fn candidate_features<'a, 'b>(
+        &'a self,
+        bins: &'b HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> impl Iterator<Item = &'a Feature> + 'b
+    where
+        'a: 'b,
+    {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        let min_offset = self
+            .linear_index
+            .as_ref()
+            .and_then(|index| index.get_min_offset(start))
+            .unwrap_or(0);
+
+        bins.region_to_bins_iter(start, end)
+            .filter_map(move |bin_id| self.candidate_bin(bin_id, start, end))
+            .flatten()
+            .filter(move |feature| feature.index >= min_offset)
+    }
+
+    /// Return the `(index, length)` of the overlapping feature with the smallest
+    /// start, breaking ties by the smallest index (i.e. the earliest added).
+    ///
+    /// Features within a bin are start-sorted, so each bin's scan stops at its
+    /// first overlap or at the first feature starting at or after `end`.
+    pub fn first_overlapping(
+        &self,
+        bins: &HierarchicalBins,
+        start: u32,
+        end: u32,
+    ) -> Option<(u64, u64)> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        let min_offset = self
+            .linear_index
+            .as_ref()
+            .and_then(|index| index.get_min_offset(start))
+            .unwrap_or(0);
+
+        let mut best: Option<&Feature> = None;
+        for bin_id in bins.region_to_bins_iter(start, end) {
+            let Some(features) = self.candidate_bin(bin_id, start, end) else {
+                continue;
+            };
+            for feature in features {
+                if feature.start >= end {
+                    break;
+                }
+                if let Some(best) = best {
+                    if (feature.start, feature.index) >= (best.start, best.index) {
+                        break;
+                    }
+                }
+                if feature.index >= min_offset && feature.end > start {
+                    best = Some(feature);
+                    break;
+                }
+            }
+        }
+
+        best.map(|feature| (feature.index, feature.length))
+    }
+
+    /// Return the `k` features closest to `pos`, by ascending distance with
+    /// ties broken by start (then index). The distance is 0 for features
+    /// containing `pos`, `start - pos` for features to its right, and
+    /// `pos - end` for those to its left.
+    ///
+    /// This queries windows around `pos` through the bins, doubling their
+    /// width (from the base bin size) until `k` features lie within the
+    /// window's reach or it covers every feature.
+    pub fn nearest<'a>(&'a self, bins: &HierarchicalBins, pos: u32, k: usize) -> Vec<&'a Feature> {
+        let Some(bounds) = self.bounds() else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+        let distance = |feature: &Feature| {
+            if pos < feature.start {
+                feature.start - pos
+            } else {
+                pos.saturating_sub(feature.end)
+            }
+        };
+
+        let base_shift = self.binning.as_ref().unwrap_or(bins).base_shift;
+        let mut width = 1u32.checked_shl(base_shift).unwrap_or(u32::MAX);
+        loop {
+            // Every feature within `width` of `pos` overlaps this window
+            let start = pos.saturating_sub(width.saturating_add(1));
+            let end = pos.saturating_add(width).saturating_add(1);
+            let covers_all = start <= bounds.min_start && end >= bounds.max_end;
+
+            let mut candidates: Vec<(u32, &Feature)> = self
+                .overlapping_features(bins, start, end)
+                .into_iter()
+                .map(|feature| (distance(feature), feature))
+                .filter(|(distance, _)| covers_all || *distance <= width)
+                .collect();
+            if covers_all || candidates.len() >= k {
+                candidates.sort_unstable_by_key(|(distance, feature)| {
+                    (*distance, feature.start, feature.index)
+                });
+                return candidates
+                    .into_iter()
+                    .take(k)
+                    .map(|(_, feature)| feature)
+                    .collect();
+            }
+            width = width.saturating_mul(2);
+        }
+    }
+
+    /// Return up to `limit` features at or after `cursor`, a `(start, index)`
+    /// position, ordered by start then index. `(pos, 0)` begins at the first
+    /// feature starting at or after `pos`; a page's last feature `f` gives
+    /// the next page's cursor, `(f.start, f.index + 1)`, so features sharing
+    /// a start are split across pages without being skipped.
+    ///
+    /// Like `nearest`, this queries windows starting at the cursor's start of
+    /// doubling width, until `limit` features start within the window or it
+    /// reaches past every feature.
+    pub fn features_after<'a>(
+        &'a self,
+        bins: &HierarchicalBins,
+        cursor: (u32, u64),
+        limit: usize,
+    ) -> Vec<&'a Feature> {
+        let pos = cursor.0;
+        let Some(bounds) = self.bounds() else {
+            return Vec::new();
+        };
+        if limit == 0 || pos >= bounds.max_end {
+            return Vec::new();
+        }
+
+        let base_shift = self.binning.as_ref().unwrap_or(bins).base_shift;
+        let mut width = 1u32.checked_shl(base_shift).unwrap_or(u32::MAX);
+        loop {
+            // Every feature starting in [pos, end) overlaps the window
+            let end = pos.saturating_add(width);
+            let mut features: Vec<&Feature> = self
+                .overlapping_features(bins, pos, end)
+                .into_iter()
+                .filter(|feature| (feature.start, feature.index) >= cursor)
+                .collect();
+            if end >= bounds.max_end || features.len() >= limit {
+                features.sort_unstable_by_key(|feature| (feature.start, feature.index));
+                features.truncate(limit);
+                return features;
+            }
+            width = width.saturating_mul(2);
+        }
+    }
+
     /// Add a feature to the sequence index, ensuring it is in sorted order and updating bins and linear index.
     pub fn add_feature(
         &mut self,
@@ -174,128 +918,890 @@ impl SequenceIndex {
         bins: &HierarchicalBins,
         length: u64,
     ) -> Result<(), HgIndexError> {
-        // Validate feature ordering
-        if let Some(last_feature) = self.bins.values().flat_map(|f| f.iter()).last() {
-            if start < last_feature.start {
+        self.add_feature_with_extra(start, end, index, bins, length, 0)
+    }
+
+    /// Like `add_feature`, also storing `extra` (see [`Feature::extra`]).
+    pub fn add_feature_with_extra(
+        &mut self,
+        start: u32,
+        end: u32,
+        index: u64,
+        bins: &HierarchicalBins,
+        length: u64,
+        extra: u32,
+    ) -> Result<(), HgIndexError> {
+        self.check_feature(start, end, bins)?;
+        self.add_feature_unchecked(start, end, index, bins, length, extra)
+    }
+
+    /// Check that `add_feature_with_extra` would accept a feature at
+    /// `start..end`, without adding it: that it is in start order and
+    /// addressable by the binning.
+    pub fn check_feature(
+        &self,
+        start: u32,
+        end: u32,
+        bins: &HierarchicalBins,
+    ) -> Result<(), HgIndexError> {
+        // Validate feature ordering, against features loaded from disk too
+        let last_start = self.last_start.or_else(|| {
+            self.iter_bins()
+                .flat_map(|(_, features)| features)
+                .map(|feature| feature.start)
+                .max()
+        });
+        if let Some(last_start) = last_start {
+            if start < last_start {
                 return Err(HgIndexError::UnsortedFeatures {
                     chrom: String::new(), // Chromosome validation occurs in BinningIndex
                     bin_id: 0,            // We could also calculate the bin ID here if helpful
-                    previous: last_feature.start,
+                    previous: last_start,
                     current: start,
                 });
             }
         }
 
-        // Determine the bin for the feature
-        let bin_id = bins.region_to_bin(start, end);
+        self.check_addressable(start, end, bins)
+    }
 
-        // Add the feature to the appropriate bin
-        self.bins.entry(bin_id).or_default().push(Feature {
+    // Check that the binning has a bin for a feature at `start..end`
+    fn check_addressable(
+        &self,
+        start: u32,
+        end: u32,
+        bins: &HierarchicalBins,
+    ) -> Result<(), HgIndexError> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        bins.region_to_bin(start, end.max(start.saturating_add(1)))
+            .map(|_| ())
+    }
+
+    /// Merge another sequence index built with the same binning into this
+    /// one, interleaving each bin's features by start (this index's first
+    /// on ties). The merged index has no linear index, as the batches'
+    /// offsets needn't grow with start. Returns the number of bins both
+    /// indices used.
+    pub fn merge_sorted(&mut self, other: SequenceIndex) -> Result<u64, HgIndexError> {
+        if self.binning != other.binning {
+            return Err("Cannot merge sequence indices with different binnings".into());
+        }
+        let was_frozen = self.is_frozen();
+        let mut other = other;
+        self.thaw();
+        other.thaw();
+
+        let mut shared_bins = 0;
+        for (bin_id, features) in other.bins {
+            match self.bins.entry(bin_id) {
+                Entry::Occupied(mut entry) => {
+                    shared_bins += 1;
+                    let existing = std::mem::take(entry.get_mut());
+                    let mut merged = Vec::with_capacity(existing.len() + features.len());
+                    let mut left = existing.into_iter().peekable();
+                    let mut right = features.into_iter().peekable();
+                    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+                        if b.start < a.start {
+                            merged.extend(right.next());
+                        } else {
+                            merged.extend(left.next());
+                        }
+                    }
+                    merged.extend(left);
+                    merged.extend(right);
+                    *entry.get_mut() = merged;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(features);
+                }
+            }
+        }
+        for (bin_id, bounds) in other.bin_bounds {
+            self.bin_bounds
+                .entry(bin_id)
+                .and_modify(|existing| existing.extend(bounds.min_start, bounds.max_end))
+                .or_insert(bounds);
+        }
+        self.bounds = self.compute_bounds();
+        self.last_start = None;
+
+        // Linear index pruning assumes offsets grow with start, which the
+        // merged batches' interleaved offsets needn't, so drop it
+        self.linear_index = None;
+
+        if was_frozen {
+            self.freeze();
+        }
+        Ok(shared_bins)
+    }
+
+    /// Add a feature without validating sort order. Adding features out of
+    /// order produces an index that silently misses overlaps.
+    pub fn add_feature_unchecked(
+        &mut self,
+        start: u32,
+        end: u32,
+        index: u64,
+        bins: &HierarchicalBins,
+        length: u64,
+        extra: u32,
+    ) -> Result<(), HgIndexError> {
+        let bins = self.binning.as_ref().unwrap_or(bins);
+        let feature = Feature {
             start,
             end,
             index,
             length,
-        });
+            extra,
+        };
+        let index_end = feature.index_end();
+
+        // Determine the bin for the feature, before modifying anything
+        let bin_id = bins.region_to_bin(start, index_end)?;
+        self.thaw();
+
+        // Add the feature to the appropriate bin
+        self.bin_bounds
+            .entry(bin_id)
+            .and_modify(|bounds| bounds.extend(start, index_end))
+            .or_insert_with(|| BinBounds::new(start, index_end));
+        match &mut self.bounds {
+            Some(bounds) => bounds.extend(start, index_end),
+            None => self.bounds = Some(BinBounds::new(start, index_end)),
+        }
+        self.bins.entry(bin_id).or_default().push(feature);
+        self.last_start = self.last_start.max(Some(start));
 
         // Update the linear index
         if let Some(linear_index) = &mut self.linear_index {
-            linear_index.update(start, end, index);
+            linear_index.update(start, index_end, index);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Feature {
+    /// Start position.
+    pub start: u32,
+    /// End position.
+    pub end: u32,
+    /// The feature index (e.g. a file offset).
+    pub index: u64,
+    /// The length of data in bytes.
+    pub length: u64,
+    /// A small caller-defined value stored in the index itself (e.g. a
+    /// category ID or strand bit), so filters needing only it can skip the
+    /// data file. Zero unless set when the feature was added.
+    pub extra: u32,
+}
+
+impl Feature {
+    /// The end used to bin the feature: `end`, or for a zero-width feature
+    /// (e.g. an insertion point), one past `start`, so it is binned as the
+    /// base following the point.
+    #[inline]
+    pub fn index_end(&self) -> u32 {
+        self.end.max(self.start.saturating_add(1))
+    }
+}
+
+// Fill in the sequence name that `SequenceIndex` leaves empty in an
+// `UnaddressableFeature` error
+fn with_chrom(error: HgIndexError, chrom: &str) -> HgIndexError {
+    match error {
+        HgIndexError::UnaddressableFeature {
+            start,
+            end,
+            schema,
+            max,
+            ..
+        } => HgIndexError::UnaddressableFeature {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            schema,
+            max,
+        },
+        error => error,
+    }
+}
+
+impl Default for BinningIndex {
+    fn default() -> Self {
+        let schema = BinningSchema::default();
+        Self::new(&schema)
+    }
+}
+
+impl BinningIndex {
+    /// Default length at or below which a sequence gets a flat index.
+    pub const DEFAULT_FLAT_MAX_LENGTH: u32 = 1_000_000;
+
+    pub fn new(schema: &BinningSchema) -> Self {
+        let bins = HierarchicalBins::from_schema(schema);
+        BinningIndex {
+            bins,
+            sequences: SequenceMap::default(),
+            last_chrom: None,
+            last_start: None,
+            metadata_bytes: None,
+            running_stats: RunningStats::default(),
+            chrom_aliases: ChromAliases::default(),
+            sequence_lengths: SequenceMap::default(),
+            flat_max_length: Self::DEFAULT_FLAT_MAX_LENGTH,
+            lowercase_chroms: false,
+            spill_dir: None,
+            spilled: Vec::new(),
+            unchecked_insertion: false,
+            zero_width_inclusive: false,
+        }
+    }
+
+    /// An empty index under `schema` with this index's settings and metadata
+    /// (aliases, sequence lengths, case folding), for rebuilding it under a
+    /// different schema.
+    pub fn empty_like(&self, schema: &BinningSchema) -> Self {
+        BinningIndex {
+            metadata_bytes: self.metadata_bytes.clone(),
+            chrom_aliases: self.chrom_aliases.clone(),
+            sequence_lengths: self.sequence_lengths.clone(),
+            flat_max_length: self.flat_max_length,
+            lowercase_chroms: self.lowercase_chroms,
+            ..Self::new(schema)
+        }
+    }
+
+    /// Spill completed sequence indices to `dir` as input moves on to the next
+    /// sequence, so peak memory is a single sequence's index. This requires
+    /// input grouped by sequence; the full index is reassembled at finalize.
+    pub fn enable_spilling(&mut self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        self.spill_dir = Some(dir.to_path_buf());
+        Ok(())
+    }
+
+    pub fn is_spilling(&self) -> bool {
+        self.spill_dir.is_some()
+    }
+
+    fn spill_part_path(dir: &Path, part: usize) -> PathBuf {
+        dir.join(format!("{}.part", part))
+    }
+
+    /// Write the in-memory index for `chrom` to the spill directory and drop it.
+    fn spill_sequence(&mut self, chrom: &str) -> Result<(), HgIndexError> {
+        let Some(dir) = &self.spill_dir else {
+            return Ok(());
+        };
+        let Some(mut sequence_index) = self.sequences.remove(chrom) else {
+            return Ok(());
+        };
+        sequence_index.freeze();
+        let path = Self::spill_part_path(dir, self.spilled.len());
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, &sequence_index)
+            .map_err(|e| HgIndexError::SerializationError(e.to_string()))?;
+        writer.flush()?;
+        trace_event!(debug, chrom, "sequence index spilled");
+        self.spilled.push(chrom.to_string());
+        Ok(())
+    }
+
+    /// Handle a change of sequence in the input stream.
+    fn start_sequence(&mut self, chrom: &str) -> Result<(), HgIndexError> {
+        if self.spill_dir.is_some() {
+            if self.spilled.iter().any(|c| c == chrom) {
+                return Err(HgIndexError::UngroupedSequence(chrom.to_string()));
+            }
+            if let Some(previous) = self.last_chrom.take() {
+                self.spill_sequence(&previous)?;
+            }
+        }
+        self.last_chrom = Some(chrom.to_string());
+        Ok(())
+    }
+
+    /// The smallest start and largest end of the features indexed on
+    /// `chrom`, e.g. to clamp queries to where features are without knowing
+    /// the sequence length. `None` if `chrom` has no features.
+    pub fn sequence_bounds(&self, chrom: &str) -> Option<(u32, u32)> {
+        let bounds = self.sequences.get(chrom)?.bounds()?;
+        Some((bounds.min_start, bounds.max_end))
+    }
+
+    /// Remove `chrom`'s sequence index, e.g. to replace the sequence, and
+    /// recompute the running statistics from the remaining features. Returns
+    /// false if `chrom` isn't indexed. Sequences already spilled to disk
+    /// can't be removed.
+    pub fn remove_sequence(&mut self, chrom: &str) -> bool {
+        if self.sequences.remove(chrom).is_none() {
+            return false;
+        }
+        if self.last_chrom.as_deref() == Some(chrom) {
+            self.last_chrom = None;
+            self.last_start = None;
+        }
+
+        let mut stats = RunningStats::default();
+        for sequence in self.sequences.values() {
+            for (_, features) in sequence.iter_bins() {
+                for (i, feature) in features.iter().enumerate() {
+                    stats.record(feature.end - feature.start, i == 0);
+                }
+            }
+        }
+        self.running_stats = stats;
+        true
+    }
+
+    /// The names of the indexed sequences, in karyotype order (see
+    /// [`natural_chrom_cmp`](crate::natural_chrom_cmp)) rather than hash map
+    /// order.
+    pub fn chromosomes(&self) -> Vec<&str> {
+        let mut chroms: Vec<&str> = self.sequences.keys().map(String::as_str).collect();
+        chroms.sort_by(|a, b| natural_chrom_cmp(a, b));
+        chroms
+    }
+
+    /// Each sequence with features as `(chrom, count, min_start, max_end)`:
+    /// its number of features, and its [`bounds`](SequenceIndex::bounds),
+    /// in karyotype order of name (as `chromosomes`). Read from the index
+    /// alone.
+    pub fn chrom_summary(&self) -> Vec<(String, u64, u32, u32)> {
+        let mut summary: Vec<(String, u64, u32, u32)> = self
+            .sequences
+            .iter()
+            .filter_map(|(chrom, sequence)| {
+                let bounds = sequence.bounds()?;
+                let count = sequence.num_features() as u64;
+                Some((chrom.clone(), count, bounds.min_start, bounds.max_end))
+            })
+            .collect();
+        summary.sort_by(|a, b| natural_chrom_cmp(&a.0, &b.0));
+        summary
+    }
+
+    /// The number of features indexed across all sequences.
+    pub fn num_features(&self) -> usize {
+        self.sequences
+            .values()
+            .map(|sequence| sequence.num_features())
+            .sum()
+    }
+
+    /// Whether `chrom` is indexed and has at least one feature.
+    pub fn has_features(&self, chrom: &str) -> bool {
+        self.sequences
+            .get(chrom)
+            .is_some_and(|sequence| !sequence.is_empty())
+    }
+
+    /// Skip validating that features are added in sorted order, for input
+    /// already guaranteed sorted (e.g. by an upstream `sort`). If the input is
+    /// in fact unsorted, the resulting index is broken: queries, which rely on
+    /// order for linear index pruning, will silently miss overlaps.
+    pub fn set_unchecked_insertion(&mut self, unchecked: bool) {
+        self.unchecked_insertion = unchecked;
+    }
+
+    /// Whether overlap queries match zero-width features (`start == end`,
+    /// e.g. insertion points) at the edges of the query window.
+    ///
+    /// Intervals are half-open, so by default a zero-width feature at `p`
+    /// overlaps `[start, end)` only if `start < p < end`: a query for the
+    /// base at `p`, `[p, p + 1)`, misses it, as does the base before it.
+    /// With this set, it is matched when `start <= p <= end`, by windows
+    /// touching the point on either side. Applies to `find_overlapping`,
+    /// `find_overlapping_sorted`, and `overlapping_features`.
+    pub fn set_zero_width_inclusive(&mut self, inclusive: bool) {
+        self.zero_width_inclusive = inclusive;
+    }
+
+    pub fn get_sequence_index(&self, chrom: &str) -> Option<&SequenceIndex> {
+        self.sequences.get(chrom)
+    }
+
+    pub fn disable_linear_index(&mut self) {
+        // Clear out old linear indices.
+        self.sequences
+            .values_mut()
+            .for_each(|f| f.linear_index = None);
+        // Set the linear shift to None.
+        self.bins.linear_shift = None;
+    }
+
+    /// Check that every bin's features are in non-decreasing start order,
+    /// the invariant queries rely on. Returns the first violation found,
+    /// scanning sequences by name and bins by ID.
+    pub fn check_sorted(&self) -> Result<(), HgIndexError> {
+        let mut chroms: Vec<&String> = self.sequences.keys().collect();
+        chroms.sort();
+        for chrom in chroms {
+            let sequence = &self.sequences[chrom];
+            let mut bins: Vec<(u32, &[Feature])> = sequence.iter_bins().collect();
+            bins.sort_unstable_by_key(|(bin_id, _)| *bin_id);
+            for (bin_id, features) in bins {
+                for pair in features.windows(2) {
+                    if pair[1].start < pair[0].start {
+                        return Err(HgIndexError::UnsortedFeatures {
+                            chrom: chrom.clone(),
+                            bin_id,
+                            previous: pair[0].start,
+                            current: pair[1].start,
+                        });
+                    }
+                }
+            }
         }
+        Ok(())
+    }
+
+    pub fn has_linear_index(&self) -> bool {
+        self.bins.linear_shift.is_some()
+    }
+
+    /// Create a new index object by reading a binary serialized version of disk.
+    pub fn open(path: &Path) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self::from_bytes(&mmap[..])?)
+    }
+
+    /// Decode an index from its serialized bytes (the contents of an index
+    /// file), e.g. one embedded in another file or fetched from object storage.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HgIndexError> {
+        check_index_header(bytes)?;
+        bincode::deserialize(&bytes[INDEX_HEADER_LEN..])
+            .map_err(|e| HgIndexError::DeserializationError(e.to_string()))
+    }
+
+    /// Decode an index from a reader positioned at its serialized bytes. The
+    /// reader is read in many small pieces, so wrap unbuffered sources (like a
+    /// `File`) in a `BufReader`.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, HgIndexError> {
+        let mut header = [0; INDEX_HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| HgIndexError::DeserializationError(e.to_string()))?;
+        check_index_header(&header)?;
+        bincode::deserialize_from(reader)
+            .map_err(|e| HgIndexError::DeserializationError(e.to_string()))
+    }
+
+    /// Open an index keeping only the named sequences' indices (by canonical
+    /// name), for single-chromosome workloads. Other sequences are decoded
+    /// one at a time and dropped, so memory is that of the kept sequences.
+    /// Reads the same layout `finalize_spilled` writes.
+    pub fn open_sequences(
+        path: &Path,
+        chroms: &[&str],
+    ) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        check_index_header(&mmap)?;
+        let mut reader = &mmap[INDEX_HEADER_LEN..];
+
+        let bins: HierarchicalBins = bincode::deserialize_from(&mut reader)?;
+        let num_sequences: u64 = bincode::deserialize_from(&mut reader)?;
+        let mut sequences = SequenceMap::default();
+        for _ in 0..num_sequences {
+            let chrom: String = bincode::deserialize_from(&mut reader)?;
+            let sequence: SequenceIndex = bincode::deserialize_from(&mut reader)?;
+            if chroms.contains(&chrom.as_str()) {
+                sequences.insert(chrom, sequence);
+            }
+        }
+
+        let IndexTail {
+            last_chrom,
+            last_start,
+            metadata_bytes,
+            running_stats,
+            chrom_aliases,
+            sequence_lengths,
+            flat_max_length,
+            lowercase_chroms,
+        } = bincode::deserialize_from(&mut reader)?;
+        Ok(BinningIndex {
+            bins,
+            sequences,
+            last_chrom,
+            last_start,
+            metadata_bytes,
+            running_stats,
+            chrom_aliases,
+            sequence_lengths,
+            flat_max_length,
+            lowercase_chroms,
+            spill_dir: None,
+            spilled: Vec::new(),
+            unchecked_insertion: false,
+            zero_width_inclusive: false,
+        })
+    }
+
+    /// Add a feature, a range with a file
+    pub fn add_feature(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        index: u64,
+        length: u64,
+    ) -> Result<(), HgIndexError> {
+        self.add_feature_with_extra(chrom, start, end, index, length, 0)
+    }
+
+    /// Like `add_feature`, also storing `extra` (see [`Feature::extra`]).
+    pub fn add_feature_with_extra(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        index: u64,
+        length: u64,
+        extra: u32,
+    ) -> Result<(), HgIndexError> {
+        if self.last_chrom.as_deref() != Some(chrom) {
+            self.start_sequence(chrom)?;
+        }
+
+        // Get or create the sequence index for the chromosome
+        let is_short = self.is_short_sequence(chrom);
+        let sequence_index = match self.sequences.entry(chrom.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(if is_short {
+                SequenceIndex::with_binning(HierarchicalBins::flat())
+            } else {
+                SequenceIndex::new(&self.bins)
+            }),
+        };
+
+        // Delegate the feature addition to SequenceIndex
+        let bins_before = sequence_index.num_bins();
+        let added = if self.unchecked_insertion {
+            sequence_index.add_feature_unchecked(start, end, index, &self.bins, length, extra)
+        } else {
+            sequence_index.add_feature_with_extra(start, end, index, &self.bins, length, extra)
+        };
+        added.map_err(|error| with_chrom(error, chrom))?;
+        let new_bin = sequence_index.num_bins() > bins_before;
+        self.running_stats.record(end - start, new_bin);
+
+        Ok(())
+    }
+
+    /// Check that `add_feature` would accept a feature on `chrom` at
+    /// `start..end`, without adding it, e.g. to validate a record before
+    /// writing its data: that its sequence isn't ungrouped, and (unless
+    /// insertion is unchecked) that it is in start order and addressable.
+    pub fn check_feature(&self, chrom: &str, start: u32, end: u32) -> Result<(), HgIndexError> {
+        if self.last_chrom.as_deref() != Some(chrom)
+            && self.spill_dir.is_some()
+            && self.spilled.iter().any(|c| c == chrom)
+        {
+            return Err(HgIndexError::UngroupedSequence(chrom.to_string()));
+        }
+        let checked = match self.sequences.get(chrom) {
+            Some(sequence) if self.unchecked_insertion => {
+                sequence.check_addressable(start, end, &self.bins)
+            }
+            Some(sequence) => sequence.check_feature(start, end, &self.bins),
+            // A new sequence, binned as `add_feature` would create it
+            None => {
+                let flat;
+                let bins = if self.is_short_sequence(chrom) {
+                    flat = HierarchicalBins::flat();
+                    &flat
+                } else {
+                    &self.bins
+                };
+                bins.region_to_bin(start, end.max(start.saturating_add(1)))
+                    .map(|_| ())
+            }
+        };
+        checked.map_err(|error| with_chrom(error, chrom))
+    }
+
+    // Whether `chrom` is short enough to get a flat binning of its own
+    fn is_short_sequence(&self, chrom: &str) -> bool {
+        self.sequence_lengths
+            .get(chrom)
+            .is_some_and(|&length| length <= self.flat_max_length)
+    }
+
+    /// Merge the features of another index, built with the same schema, into
+    /// this one. Sequences in both are merged bin by bin, preserving start
+    /// order, so the batches' starts may interleave. Feature indices are kept
+    /// as is, so both must refer to the same data (e.g. offsets already
+    /// rebased into a shared data file). Merged sequences lose their linear
+    /// index, since its pruning assumes offsets grow with start.
+    pub fn merge_sorted(&mut self, other: BinningIndex) -> Result<(), HgIndexError> {
+        if self.bins != other.bins {
+            return Err(format!(
+                "Cannot merge an index with the {} binning schema into one with the {} schema",
+                other.bins.schema, self.bins.schema
+            )
+            .into());
+        }
+        if self.is_spilling() {
+            return Err("Cannot merge into an index that spills sequences to disk".into());
+        }
+
+        let mut shared_bins = 0;
+        for (chrom, sequence) in other.sequences {
+            match self.sequences.entry(chrom) {
+                Entry::Occupied(mut entry) => {
+                    shared_bins += entry.get_mut().merge_sorted(sequence).map_err(|error| {
+                        format!("Cannot merge sequence {}: {}", entry.key(), error)
+                    })?;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(sequence);
+                }
+            }
+        }
+        self.running_stats.merge(&other.running_stats);
+        self.running_stats.total_bins_used -= shared_bins;
+        for (chrom, length) in other.sequence_lengths {
+            self.sequence_lengths.entry(chrom).or_insert(length);
+        }
+        Ok(())
+    }
+
+    /// Return the indices (e.g. file offsets) of all ranges that overlap with the supplied range.
+    /// Bins are always computed with the binning stored in this index (or a
+    /// sequence's own flat binning), never a default schema.
+    pub fn find_overlapping(&self, chrom: &str, start: u32, end: u32) -> Vec<(u64, u64)> {
+        self.find_overlapping_at_most(chrom, start, end, usize::MAX)
+    }
+
+    /// Like `find_overlapping`, but stopping once `max` features are found;
+    /// see [`SequenceIndex::find_overlapping_at_most`].
+    pub fn find_overlapping_at_most(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        max: usize,
+    ) -> Vec<(u64, u64)> {
+        let Some(chrom_index) = self.sequences.get(chrom) else {
+            return Vec::new();
+        };
+        if self.zero_width_inclusive {
+            return chrom_index
+                .touching_iter(&self.bins, start, end)
+                .take(max)
+                .map(|feature| (feature.index, feature.length))
+                .collect();
+        }
+        chrom_index.find_overlapping_at_most(&self.bins, start, end, max)
+    }
+
+    /// Like `find_overlapping`, but ordered by feature start, then end; see
+    /// [`SequenceIndex::find_overlapping_sorted`].
+    pub fn find_overlapping_sorted(&self, chrom: &str, start: u32, end: u32) -> Vec<(u64, u64)> {
+        if self.zero_width_inclusive {
+            let mut features = self.overlapping_features(chrom, start, end);
+            features.sort_unstable_by_key(|feature| (feature.start, feature.end, feature.index));
+            return features
+                .into_iter()
+                .map(|feature| (feature.index, feature.length))
+                .collect();
+        }
+        match self.sequences.get(chrom) {
+            Some(chrom_index) => chrom_index.find_overlapping_sorted(&self.bins, start, end),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the features overlapping `[start, end)` on `chrom`.
+    pub fn overlapping_features(&self, chrom: &str, start: u32, end: u32) -> Vec<&Feature> {
+        match self.sequences.get(chrom) {
+            Some(chrom_index) if self.zero_width_inclusive => {
+                chrom_index.touching_features(&self.bins, start, end)
+            }
+            Some(chrom_index) => chrom_index.overlapping_features(&self.bins, start, end),
+            None => Vec::new(),
+        }
+    }
+
+    /// Record the lengths of sequences. Sequences at most `flat_max_length`
+    /// long get a single-bin flat index when their first feature is added,
+    /// which avoids per-sequence overhead for many short contigs.
+    pub fn set_sequence_lengths<I>(&mut self, lengths: I)
+    where
+        I: IntoIterator<Item = (String, u32)>,
+    {
+        for (chrom, length) in lengths {
+            let chrom = self.canonical_chrom(&chrom).into_owned();
+            self.sequence_lengths.insert(chrom, length);
+        }
+    }
 
-        Ok(())
+    pub fn sequence_length(&self, chrom: &str) -> Option<u32> {
+        self.sequence_lengths.get(chrom).copied()
     }
-}
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct Feature {
-    /// Start position.
-    pub start: u32,
-    /// End position.
-    pub end: u32,
-    /// The feature index (e.g. a file offset).
-    pub index: u64,
-    /// The length of data in bytes.
-    pub length: u64,
-}
+    /// Set the length at or below which a sequence gets a flat index.
+    pub fn set_flat_max_length(&mut self, length: u32) {
+        self.flat_max_length = length;
+    }
 
-impl Default for BinningIndex {
-    fn default() -> Self {
-        let schema = BinningSchema::default();
-        Self::new(&schema)
+    pub fn chrom_aliases(&self) -> &ChromAliases {
+        &self.chrom_aliases
     }
-}
 
-impl BinningIndex {
-    pub fn new(schema: &BinningSchema) -> Self {
-        let bins = HierarchicalBins::from_schema(schema);
-        BinningIndex {
-            bins,
-            sequences: FxHashMap::default(),
-            last_chrom: None,
-            last_start: None,
-            metadata_bytes: None,
-        }
+    /// Add chromosome aliases; these are persisted with the index.
+    pub fn add_chrom_aliases(&mut self, aliases: ChromAliases) {
+        self.chrom_aliases.extend(aliases);
     }
 
-    pub fn get_sequence_index(&self, chrom: &str) -> Option<&SequenceIndex> {
-        self.sequences.get(chrom)
+    /// Match sequence names case-insensitively, by lowercasing them (before
+    /// alias lookup) wherever they are canonicalized, i.e. in store records
+    /// and queries. The choice is persisted with the index, so readers apply
+    /// the same rule. Set this before adding features.
+    pub fn set_lowercase_chroms(&mut self, lowercase: bool) {
+        self.lowercase_chroms = lowercase;
     }
 
-    pub fn disable_linear_index(&mut self) {
-        // Clear out old linear indices.
-        self.sequences
-            .values_mut()
-            .for_each(|f| f.linear_index = None);
-        // Set the linear shift to None.
-        self.bins.linear_shift = None;
+    pub fn lowercase_chroms(&self) -> bool {
+        self.lowercase_chroms
     }
 
-    pub fn has_linear_index(&self) -> bool {
-        self.bins.linear_shift.is_some()
+    /// The canonical sequence name for `chrom`: the name it is an alias of
+    /// (after lowercasing, with `set_lowercase_chroms`), or else `chrom`
+    /// itself. Only a lowercased name that isn't an alias is allocated.
+    pub fn canonical_chrom<'a>(&'a self, chrom: &'a str) -> Cow<'a, str> {
+        if self.lowercase_chroms && chrom.bytes().any(|b| b.is_ascii_uppercase()) {
+            let lowercase = chrom.to_ascii_lowercase();
+            return match self.chrom_aliases.canonical(&lowercase) {
+                Some(canonical) => Cow::Borrowed(canonical),
+                None => Cow::Owned(lowercase),
+            };
+        }
+        Cow::Borrowed(self.chrom_aliases.resolve(chrom))
     }
 
-    /// Create a new index object by reading a binary serialized version of disk.
-    pub fn open(path: &Path) -> std::result::Result<Self, Box<dyn std::error::Error>> {
-        let file = File::open(path)?;
-        let mmap = unsafe { memmap2::Mmap::map(&file)? };
-        let index: BinningIndex = bincode::deserialize(&mmap[..])?;
-        Ok(index)
+    /// Statistics maintained incrementally as features were added.
+    pub fn running_stats(&self) -> &RunningStats {
+        &self.running_stats
     }
 
-    /// Add a feature, a range with a file
-    pub fn add_feature(
-        &mut self,
+    /// Return the features overlapping `[start, end)` on `chrom`, grouped by
+    /// bin level; see [`SequenceIndex::overlapping_features_by_level`].
+    pub fn overlapping_features_by_level(
+        &self,
         chrom: &str,
         start: u32,
         end: u32,
-        index: u64,
-        length: u64,
-    ) -> Result<(), HgIndexError> {
-        // Get or create the sequence index for the chromosome
-        let sequence_index = self
-            .sequences
-            .entry(chrom.to_string())
-            .or_insert_with(|| SequenceIndex::new(&self.bins));
+    ) -> Vec<(usize, Vec<&Feature>)> {
+        match self.sequences.get(chrom) {
+            Some(chrom_index) => chrom_index.overlapping_features_by_level(&self.bins, start, end),
+            None => Vec::new(),
+        }
+    }
 
-        // Delegate the feature addition to SequenceIndex
-        sequence_index.add_feature(start, end, index, &self.bins, length)?;
+    /// Return all features stored in the given bins of `chrom`, without any
+    /// overlap filtering, in the order of `bins`. Unknown bins are ignored.
+    pub fn features_in_bins(&self, chrom: &str, bins: &[u32]) -> Vec<&Feature> {
+        let Some(chrom_index) = self.sequences.get(chrom) else {
+            return Vec::new();
+        };
+        bins.iter()
+            .filter_map(|&bin_id| chrom_index.bin(bin_id))
+            .flatten()
+            .collect()
+    }
 
-        Ok(())
+    /// Return the index of the leftmost feature overlapping the supplied range;
+    /// see [`SequenceIndex::first_overlapping`] for the tie-break.
+    pub fn first_overlapping(&self, chrom: &str, start: u32, end: u32) -> Option<(u64, u64)> {
+        self.sequences
+            .get(chrom)
+            .and_then(|chrom_index| chrom_index.first_overlapping(&self.bins, start, end))
     }
 
-    /// Return the indices (e.g. file offsets) of all ranges that overlap with the supplied range.
-    pub fn find_overlapping(&mut self, chrom: &str, start: u32, end: u32) -> Vec<(u64, u64)> {
-        if let Some(chrom_index) = self.sequences.get_mut(chrom) {
-            chrom_index.find_overlapping(&self.bins, start, end)
-        } else {
-            vec![]
+    /// Return the `k` features on `chrom` closest to `pos`; see
+    /// [`SequenceIndex::nearest`] for the distance and ordering.
+    pub fn nearest_features(&self, chrom: &str, pos: u32, k: usize) -> Vec<&Feature> {
+        match self.sequences.get(chrom) {
+            Some(chrom_index) => chrom_index.nearest(&self.bins, pos, k),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return up to `limit` features on `chrom` at or after the `(start,
+    /// index)` `cursor`; see [`SequenceIndex::features_after`].
+    pub fn features_after(&self, chrom: &str, cursor: (u32, u64), limit: usize) -> Vec<&Feature> {
+        match self.sequences.get(chrom) {
+            Some(chrom_index) => chrom_index.features_after(&self.bins, cursor, limit),
+            None => Vec::new(),
         }
     }
 
-    /// Write the BinningIndex to a path by binary serialization.
+    /// Freeze every sequence index into the read-optimized bin layout.
+    pub fn freeze(&mut self) {
+        self.sequences.values_mut().for_each(SequenceIndex::freeze);
+    }
+
+    /// Write the BinningIndex to a path by binary serialization, freezing
+    /// the sequence indices first.
     pub fn finalize(&mut self, path: &Path) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.is_spilling() {
+            return self.finalize_spilled(path);
+        }
+        self.freeze();
         let mut file = BufWriter::new(File::create(path)?);
+        write_index_header(&mut file)?;
         bincode::serialize_into(&mut file, &self)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reassemble the spilled sequence indices into a single index file.
+    ///
+    /// This writes the same header and bincode layout as serializing the whole
+    /// `BinningIndex`, but copies each sequence's bytes from its spill part so
+    /// no more than one sequence index is ever held in memory. The fields
+    /// after the sequences are written as an `IndexTail`.
+    fn finalize_spilled(
+        &mut self,
+        path: &Path,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if let Some(chrom) = self.last_chrom.clone() {
+            self.spill_sequence(&chrom)?;
+        }
+        let dir = self
+            .spill_dir
+            .take()
+            .expect("finalize_spilled requires a spill directory");
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_index_header(&mut writer)?;
+        bincode::serialize_into(&mut writer, &self.bins)?;
+        bincode::serialize_into(&mut writer, &(self.spilled.len() as u64))?;
+        for (part, chrom) in self.spilled.iter().enumerate() {
+            bincode::serialize_into(&mut writer, chrom)?;
+            let mut part_file = File::open(Self::spill_part_path(&dir, part))?;
+            io::copy(&mut part_file, &mut writer)?;
+        }
+        let tail = IndexTail {
+            last_chrom: self.last_chrom.clone(),
+            last_start: self.last_start,
+            metadata_bytes: self.metadata_bytes.clone(),
+            running_stats: self.running_stats.clone(),
+            chrom_aliases: self.chrom_aliases.clone(),
+            sequence_lengths: self.sequence_lengths.clone(),
+            flat_max_length: self.flat_max_length,
+            lowercase_chroms: self.lowercase_chroms,
+        };
+        bincode::serialize_into(&mut writer, &tail)?;
+        writer.flush()?;
+
+        fs::remove_dir_all(&dir)?;
+        self.spilled.clear();
         Ok(())
     }
 
@@ -307,9 +1813,15 @@ impl BinningIndex {
         // Serialize metadata
         self.metadata_bytes = Some(bincode::serialize(metadata)?);
 
+        if self.is_spilling() {
+            return self.finalize_spilled(path);
+        }
+        self.freeze();
+
         // Write to file
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
+        write_index_header(&mut writer)?;
         bincode::serialize_into(&mut writer, self)?;
         writer.flush()?;
         Ok(())
@@ -325,6 +1837,7 @@ impl BinningIndex {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::test_utils::TestDir;
 
     #[test]
     fn test_feature_ordering() {
@@ -388,6 +1901,25 @@ mod tests {
         assert!(index.find_overlapping("chr1", 0, 500).is_empty());
     }
 
+    #[test]
+    fn test_linear_index_gaps() {
+        let mut index = BinningIndex::default();
+        index.add_feature("chr1", 0, 100, 0, 0).unwrap();
+        // Covers 16kb windows 6 to 12, leaving windows 1 to 5 empty
+        index.add_feature("chr1", 100_000, 200_000, 1, 0).unwrap();
+
+        // A query starting in an empty window still finds later features
+        assert_eq!(index.find_overlapping("chr1", 20_000, 150_000), [(1, 0)]);
+        assert_eq!(
+            index.find_overlapping_sorted("chr1", 20_000, 150_000),
+            [(1, 0)]
+        );
+        assert!(index.find_overlapping("chr1", 20_000, 30_000).is_empty());
+
+        // And past the end of the linear index
+        assert!(index.find_overlapping("chr1", 300_000, 400_000).is_empty());
+    }
+
     #[test]
     fn test_spanning_features() {
         let mut index = BinningIndex::default();
@@ -416,6 +1948,542 @@ mod tests {
         assert_eq!(results.len(), 10); // Should find 10 features
     }
 
+    #[test]
+    fn test_flat_index_for_short_sequences() {
+        let mut index = BinningIndex::default();
+        index.set_sequence_lengths([
+            ("chr1".to_string(), 248_956_422),
+            ("chrUn_KI270302v1".to_string(), 2_274),
+        ]);
+
+        index.add_feature("chr1", 1000, 2000, 0, 0).unwrap();
+        index
+            .add_feature("chrUn_KI270302v1", 100, 200, 0, 0)
+            .unwrap();
+        index
+            .add_feature("chrUn_KI270302v1", 150, 2000, 1, 0)
+            .unwrap();
+
+        let main = &index.sequences["chr1"];
+        assert!(main.binning.is_none());
+        assert!(main.linear_index.is_some());
+
+        let short = &index.sequences["chrUn_KI270302v1"];
+        assert_eq!(short.binning, Some(HierarchicalBins::flat()));
+        assert!(short.linear_index.is_none());
+        assert_eq!(short.num_bins(), 1);
+
+        assert_eq!(
+            index.find_overlapping("chrUn_KI270302v1", 180, 190).len(),
+            2
+        );
+        assert_eq!(
+            index.find_overlapping("chrUn_KI270302v1", 1000, 1001).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_features_in_bins() {
+        let mut index = BinningIndex::default();
+        index.add_feature("chr1", 1000, 2000, 0, 0).unwrap();
+        index.add_feature("chr1", 1500, 1600, 1, 0).unwrap();
+        index.add_feature("chr1", 0, 10_000_000, 2, 0).unwrap_err();
+        index.add_feature("chr1", 3000, 10_000_000, 2, 0).unwrap();
+
+        let small_bin = index.bins.region_to_bin(1000, 2000).unwrap();
+        let large_bin = index.bins.region_to_bin(3000, 10_000_000).unwrap();
+        assert_ne!(small_bin, large_bin);
+
+        let features = index.features_in_bins("chr1", &[small_bin]);
+        let indices: Vec<u64> = features.iter().map(|f| f.index).collect();
+        assert_eq!(indices, vec![0, 1]);
+
+        // No overlap filtering: every feature in the bin comes back
+        let features = index.features_in_bins("chr1", &[large_bin, small_bin, 12345]);
+        let indices: Vec<u64> = features.iter().map(|f| f.index).collect();
+        assert_eq!(indices, vec![2, 0, 1]);
+
+        assert!(index.features_in_bins("chr2", &[small_bin]).is_empty());
+    }
+
+    #[test]
+    fn test_unaddressable_feature() {
+        let mut index = BinningIndex::new(&BinningSchema::Tabix);
+        let max = index.bins.max_coordinate();
+        assert_eq!(max, 1 << 29);
+        index.add_feature("chr1", 0, max as u32, 0, 0).unwrap();
+        assert!(matches!(
+            index.add_feature("chr1", 1000, max as u32 + 1, 1, 0),
+            Err(HgIndexError::UnaddressableFeature { chrom, start: 1000, .. }) if chrom == "chr1"
+        ));
+
+        // Sparse addresses the full u32 range
+        let mut index = BinningIndex::new(&BinningSchema::Sparse);
+        index.add_feature("chr1", 1000, u32::MAX, 0, 0).unwrap();
+    }
+
+    #[test]
+    fn test_unchecked_insertion() {
+        let mut index = BinningIndex::default();
+        index.set_unchecked_insertion(true);
+        index.add_feature("chr1", 5000, 6000, 0, 0).unwrap();
+        index.add_feature("chr1", 1000, 2000, 1, 0).unwrap();
+        assert!(index.check_sorted().is_err());
+
+        index.set_unchecked_insertion(false);
+        assert!(index.add_feature("chr1", 500, 600, 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_check_feature() {
+        let mut index = BinningIndex::new(&BinningSchema::Tabix);
+        index.add_feature("chr1", 5000, 6000, 0, 0).unwrap();
+        assert!(index.check_feature("chr1", 5000, 7000).is_ok());
+        assert!(matches!(
+            index.check_feature("chr1", 1000, 2000),
+            Err(HgIndexError::UnsortedFeatures { .. })
+        ));
+        let max = index.bins.max_coordinate() as u32;
+        match index.check_feature("chr2", max, max + 1) {
+            Err(HgIndexError::UnaddressableFeature { chrom, .. }) => assert_eq!(chrom, "chr2"),
+            other => panic!("expected UnaddressableFeature, got {:?}", other),
+        }
+        // Checking adds nothing
+        assert_eq!(index.sequences["chr1"].num_features(), 1);
+        assert!(!index.sequences.contains_key("chr2"));
+    }
+
+    #[test]
+    fn test_open_sequences() {
+        let test_dir = TestDir::new("open_sequences").expect("Failed to create test dir");
+        let path = test_dir.path().join("index.bin");
+        let mut index = BinningIndex::default();
+        for chrom in ["chr1", "chr2", "chr3"] {
+            for i in 0..10u32 {
+                index
+                    .add_feature(chrom, i * 1000, i * 1000 + 500, i as u64, 1)
+                    .unwrap();
+            }
+        }
+        index.finalize_with_metadata(&path, &"meta").unwrap();
+
+        let full = BinningIndex::open(&path).unwrap();
+        let partial = BinningIndex::open_sequences(&path, &["chr2"]).unwrap();
+        assert_eq!(partial.sequences.len(), 1);
+        assert_eq!(partial.sequences["chr2"], full.sequences["chr2"]);
+        assert_eq!(
+            partial.find_overlapping("chr2", 2000, 5000),
+            full.find_overlapping("chr2", 2000, 5000)
+        );
+        assert!(partial.find_overlapping("chr1", 0, 5000).is_empty());
+        assert_eq!(partial.metadata::<String>().unwrap(), "meta");
+        assert_eq!(partial.running_stats(), full.running_stats());
+    }
+
+    #[test]
+    fn test_from_bytes_and_reader() {
+        let test_dir = TestDir::new("index_from_bytes").expect("Failed to create test dir");
+        let path = test_dir.path().join("index.bin");
+        let mut index = BinningIndex::default();
+        for chrom in ["chr1", "chr2"] {
+            for i in 0..10u32 {
+                index
+                    .add_feature(chrom, i * 1000, i * 1000 + 500, i as u64, 1)
+                    .unwrap();
+            }
+        }
+        index.finalize_with_metadata(&path, &"meta").unwrap();
+
+        let opened = BinningIndex::open(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(BinningIndex::from_bytes(&bytes).unwrap(), opened);
+        assert_eq!(BinningIndex::from_reader(&bytes[..]).unwrap(), opened);
+        assert!(matches!(
+            BinningIndex::from_bytes(&bytes[..bytes.len() / 2]),
+            Err(HgIndexError::DeserializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_index_header() {
+        let test_dir = TestDir::new("index_header").expect("Failed to create test dir");
+        let path = test_dir.path().join("index.bin");
+        let mut index = BinningIndex::default();
+        index.add_feature("chr1", 0, 500, 0, 1).unwrap();
+        index.finalize(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[..INDEX_HEADER_LEN], *b"GIDI\x01\x00");
+
+        let message = |bytes: &[u8]| match BinningIndex::from_bytes(bytes) {
+            Err(HgIndexError::DeserializationError(message)) => message,
+            other => panic!("expected a header error, got {:?}", other),
+        };
+        // An index from before index headers starts directly with the bins
+        let legacy = &bytes[INDEX_HEADER_LEN..];
+        assert!(message(legacy).contains("repack"));
+        assert!(BinningIndex::from_reader(legacy).is_err());
+
+        let mut newer = bytes.clone();
+        newer[4] = INDEX_VERSION + 1;
+        assert!(message(&newer).contains("version 2"));
+        std::fs::write(&path, &newer).unwrap();
+        assert!(BinningIndex::open_sequences(&path, &["chr1"]).is_err());
+    }
+
+    #[test]
+    fn test_sequence_bounds() {
+        let test_dir = TestDir::new("sequence_bounds").expect("Failed to create test dir");
+        let path = test_dir.path().join("index.bin");
+        let mut index = BinningIndex::default();
+        index.add_feature("chr1", 5_000, 6_000, 0, 1).unwrap();
+        index.add_feature("chr1", 5_500, 2_000_000, 1, 1).unwrap();
+        index.add_feature("chr1", 900_000, 900_100, 2, 1).unwrap();
+        index.add_feature("chr2", 10, 20, 0, 1).unwrap();
+        assert_eq!(index.sequence_bounds("chr1"), Some((5_000, 2_000_000)));
+        assert_eq!(index.sequence_bounds("chr3"), None);
+        index.finalize(&path).unwrap();
+
+        // Recomputed from the frozen bins on load
+        let mut opened = BinningIndex::open(&path).unwrap();
+        assert_eq!(opened.sequence_bounds("chr1"), Some((5_000, 2_000_000)));
+        assert_eq!(opened.sequence_bounds("chr2"), Some((10, 20)));
+
+        // and extended by features added after thawing
+        opened.add_feature("chr2", 15, 3_000, 1, 1).unwrap();
+        assert_eq!(opened.sequence_bounds("chr2"), Some((10, 3_000)));
+    }
+
+    #[test]
+    fn test_check_sorted() {
+        let mut index = BinningIndex::default();
+        index.add_feature("chr1", 1000, 2000, 0, 0).unwrap();
+        index.add_feature("chr1", 1500, 1600, 1, 0).unwrap();
+        index.add_feature("chr2", 100, 200, 0, 0).unwrap();
+        assert!(index.check_sorted().is_ok());
+
+        // Simulate a bad merge by swapping features within a bin
+        let bin_id = index.bins.region_to_bin(1000, 2000).unwrap();
+        let features = index
+            .sequences
+            .get_mut("chr1")
+            .unwrap()
+            .bins
+            .get_mut(&bin_id)
+            .unwrap();
+        features.swap(0, 1);
+
+        match index.check_sorted() {
+            Err(HgIndexError::UnsortedFeatures {
+                chrom,
+                bin_id: found_bin,
+                previous,
+                current,
+            }) => {
+                assert_eq!(chrom, "chr1");
+                assert_eq!(found_bin, bin_id);
+                assert_eq!((previous, current), (1500, 1000));
+            }
+            other => panic!("expected UnsortedFeatures, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_frozen_bins() {
+        let test_dir = TestDir::new("frozen_bins").expect("Failed to create test dir");
+        let path = test_dir.path().join("index.bin");
+
+        let mut index = BinningIndex::default();
+        for i in 0..2000u32 {
+            let start = i * 500;
+            let end = start + 100 + (i % 7) * 10_000;
+            index.add_feature("chr1", start, end, i as u64, 1).unwrap();
+        }
+        let queries: Vec<(u32, u32)> = (0..1_100_000)
+            .step_by(7919)
+            .map(|s| (s, s + 3000))
+            .collect();
+        let expected: Vec<_> = queries
+            .iter()
+            .map(|&(start, end)| {
+                let mut hits = index.find_overlapping("chr1", start, end);
+                hits.sort();
+                hits
+            })
+            .collect();
+
+        let mut map_bounds: Vec<_> = index.sequences["chr1"]
+            .iter_bins()
+            .map(|(bin_id, _)| (bin_id, index.sequences["chr1"].bin_bounds(bin_id)))
+            .collect();
+        map_bounds.sort_unstable_by_key(|(bin_id, _)| *bin_id);
+
+        index.finalize(&path).unwrap();
+        let mut frozen = BinningIndex::open(&path).unwrap();
+        let frozen_bounds: Vec<_> = frozen.sequences["chr1"]
+            .iter_bins()
+            .map(|(bin_id, _)| (bin_id, frozen.sequences["chr1"].bin_bounds(bin_id)))
+            .collect();
+        assert_eq!(frozen_bounds, map_bounds);
+        let sequence = &frozen.sequences["chr1"];
+        assert!(sequence.is_frozen());
+        assert!(sequence.bins.is_empty());
+        let frozen_bins = sequence.frozen().unwrap();
+        for (bin_id, features) in frozen_bins.iter() {
+            let min_start = features.iter().map(|f| f.start).min().unwrap();
+            let max_end = features.iter().map(|f| f.end).max().unwrap();
+            assert_eq!(
+                frozen_bins.bounds(bin_id),
+                Some(BinBounds { min_start, max_end })
+            );
+            assert!(frozen_bins
+                .get_candidates(bin_id, max_end, u32::MAX)
+                .is_none());
+            assert!(frozen_bins.get_candidates(bin_id, 0, min_start).is_none());
+            assert!(frozen_bins
+                .get_candidates(bin_id, max_end - 1, max_end)
+                .is_some());
+        }
+        for (&(start, end), expected) in queries.iter().zip(&expected) {
+            let mut hits = frozen.find_overlapping("chr1", start, end);
+            hits.sort();
+            assert_eq!(&hits, expected);
+        }
+
+        // Adding after freezing thaws back to the map
+        frozen
+            .add_feature("chr1", 1_100_000, 1_100_100, 2000, 1)
+            .unwrap();
+        assert!(!frozen.sequences["chr1"].is_frozen());
+        assert_eq!(
+            frozen.find_overlapping("chr1", 1_100_050, 1_100_060).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_first_overlapping_matches_find_overlapping() {
+        let mut index = BinningIndex::default();
+        let mut spans = Vec::new();
+        for i in 0..3000u32 {
+            let start = (i / 2) * 300;
+            let end = start + 50 + (i * 7919) % 200_000;
+            index.add_feature("chr1", start, end, i as u64, 1).unwrap();
+            spans.push((start, end));
+        }
+
+        // The leftmost of `find_overlapping`'s results, which gathers the
+        // candidate bins with `region_to_bins` rather than the lazy iterator
+        for query_start in (0..1_000_000).step_by(33_331) {
+            for width in [5_000, 40_000, 300_000] {
+                let query_end = query_start + width;
+                let expected = index
+                    .find_overlapping("chr1", query_start, query_end)
+                    .into_iter()
+                    .min_by_key(|&(i, _)| (spans[i as usize].0, i));
+                assert_eq!(
+                    index.first_overlapping("chr1", query_start, query_end),
+                    expected,
+                    "query {}-{}",
+                    query_start,
+                    query_end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_overlapping_at_most() {
+        let mut index = BinningIndex::default();
+        for i in 0..3000u32 {
+            let start = (i / 2) * 300;
+            let end = start + 50 + (i * 7919) % 200_000;
+            index.add_feature("chr1", start, end, i as u64, 1).unwrap();
+        }
+
+        for zero_width_inclusive in [false, true] {
+            index.set_zero_width_inclusive(zero_width_inclusive);
+            let all = index.find_overlapping("chr1", 100_000, 400_000);
+            assert!(all.len() > 100);
+            for max in [0, 1, 100, all.len(), all.len() + 1] {
+                let found = index.find_overlapping_at_most("chr1", 100_000, 400_000, max);
+                assert_eq!(found, all[..max.min(all.len())]);
+            }
+        }
+        assert!(index
+            .find_overlapping_at_most("chrX", 0, 1000, 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_overlapping_sorted() {
+        let mut index = BinningIndex::default();
+        let mut spans = Vec::new();
+        for i in 0..3000u32 {
+            // Same-start ties and widths spanning several bin levels
+            let start = (i / 2) * 300;
+            let end = start + 50 + (i * 7919) % 200_000;
+            index.add_feature("chr1", start, end, i as u64, 1).unwrap();
+            spans.push((start, end));
+        }
+
+        // Check against `find_overlapping`, which gathers the candidate bins
+        // eagerly with `region_to_bins` rather than the lazy iterator
+        let check = |index: &BinningIndex| {
+            for query_start in (0..500_000).step_by(33_331) {
+                for width in [5_000, 40_000, 300_000] {
+                    let query_end = query_start + width;
+                    let mut expected = index.find_overlapping("chr1", query_start, query_end);
+                    expected.sort_by_key(|&(i, _)| (spans[i as usize], i));
+                    assert!(!expected.is_empty());
+                    assert_eq!(
+                        index.find_overlapping_sorted("chr1", query_start, query_end),
+                        expected
+                    );
+                }
+            }
+        };
+        check(&index);
+        index.freeze();
+        check(&index);
+        assert!(index.find_overlapping_sorted("chrX", 0, 100).is_empty());
+    }
+
+    #[test]
+    fn test_running_stats() {
+        let mut index = BinningIndex::default();
+        assert_eq!(index.running_stats().total_features, 0);
+
+        index.add_feature("chr1", 1000, 1100, 0, 0).unwrap();
+        index.add_feature("chr1", 1200, 1250, 1, 0).unwrap();
+        index.add_feature("chr2", 0, 1_000_000, 2, 0).unwrap();
+
+        let stats = index.running_stats();
+        assert_eq!(stats.total_features, 3);
+        assert_eq!(stats.total_bins_used, 2);
+        assert_eq!(stats.min_size, 50);
+        assert_eq!(stats.max_size, 1_000_000);
+        assert_eq!(stats.total_size, 1_000_150);
+    }
+
+    #[test]
+    fn test_chrom_summary() {
+        let mut index = BinningIndex::default();
+        index.add_feature("chr10", 50, 60, 0, 0).unwrap();
+        index.add_feature("chr2", 1000, 1100, 1, 0).unwrap();
+        index.add_feature("chr2", 1050, 900_000, 2, 0).unwrap();
+        index.add_feature("chr2", 2000, 2100, 3, 0).unwrap();
+        index.freeze();
+
+        assert_eq!(
+            index.chrom_summary(),
+            [
+                ("chr2".to_string(), 3, 1000, 900_000),
+                ("chr10".to_string(), 1, 50, 60),
+            ]
+        );
+        assert!(BinningIndex::default().chrom_summary().is_empty());
+    }
+
+    #[test]
+    fn test_chromosomes() {
+        let mut index = BinningIndex::default();
+        for chrom in ["chrX", "chr10", "chr2", "chr1"] {
+            index.add_feature(chrom, 0, 10, 0, 0).unwrap();
+        }
+        assert_eq!(index.chromosomes(), ["chr1", "chr2", "chr10", "chrX"]);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        // Interleaved batches, including a sequence only in the second
+        let features = [
+            ("chr1", 1000, 1100),
+            ("chr1", 1050, 90_000),
+            ("chr1", 1200, 1300),
+            ("chr1", 5000, 6000),
+            ("chr1", 5500, 5600),
+            ("chr1", 2_000_000, 2_000_100),
+            ("chr2", 100, 200),
+            ("chr3", 300, 400),
+        ];
+        let mut combined = BinningIndex::default();
+        let mut first = BinningIndex::default();
+        let mut second = BinningIndex::default();
+        for (i, &(chrom, start, end)) in features.iter().enumerate() {
+            combined
+                .add_feature(chrom, start, end, i as u64, 1)
+                .unwrap();
+            let batch = if i % 2 == 0 { &mut first } else { &mut second };
+            batch.add_feature(chrom, start, end, i as u64, 1).unwrap();
+        }
+        // Frozen indices are merged too, e.g. ones read back from disk
+        first.freeze();
+        second.freeze();
+        combined.freeze();
+
+        first.merge_sorted(second).unwrap();
+        // Only merged sequences lose their linear index
+        assert!(first.sequences["chr1"].linear_index.is_none());
+        assert!(first.sequences["chr3"].linear_index.is_some());
+        combined.sequences.get_mut("chr1").unwrap().linear_index = None;
+        assert_eq!(first.sequences, combined.sequences);
+        assert_eq!(first.running_stats(), combined.running_stats());
+        assert!(first.check_sorted().is_ok());
+        assert_eq!(
+            first.find_overlapping_sorted("chr1", 1000, 6000),
+            combined.find_overlapping_sorted("chr1", 1000, 6000)
+        );
+
+        let mut dense = BinningIndex::new(&BinningSchema::Dense);
+        assert!(dense.merge_sorted(BinningIndex::default()).is_err());
+    }
+
+    #[test]
+    fn test_merge_sorted_interleaved_offsets() {
+        // Each batch's offsets grow with start, but the second batch's data
+        // comes first, so the merged offsets don't
+        let first_batch = [(40_000, 40_100, 10), (60_000, 60_100, 20)];
+        let second_batch = [(20_000, 20_100, 1000), (45_000, 45_100, 1010)];
+        let mut first = BinningIndex::default();
+        let mut second = BinningIndex::default();
+        for &(start, end, offset) in &first_batch {
+            first.add_feature("chr1", start, end, offset, 1).unwrap();
+        }
+        for &(start, end, offset) in &second_batch {
+            second.add_feature("chr1", start, end, offset, 1).unwrap();
+        }
+        first.merge_sorted(second).unwrap();
+
+        // A single-pass build of the same features, without the linear index
+        // its offsets would also break
+        let mut features: Vec<_> = first_batch.iter().chain(&second_batch).collect();
+        features.sort();
+        let mut single = BinningIndex::default();
+        for &&(start, end, offset) in &features {
+            single.add_feature("chr1", start, end, offset, 1).unwrap();
+        }
+        single.sequences.get_mut("chr1").unwrap().linear_index = None;
+        assert_eq!(first.sequences, single.sequences);
+
+        for (start, end) in [(0, 100_000), (30_000, 50_000), (42_000, 61_000)] {
+            let mut expected: Vec<(u64, u64)> = features
+                .iter()
+                .filter(|&&&(s, e, _)| s < end && e > start)
+                .map(|&&(_, _, offset)| (offset, 1))
+                .collect();
+            let mut found = first.find_overlapping("chr1", start, end);
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected, "query {}-{}", start, end);
+            assert_eq!(found, {
+                let mut single_found = single.find_overlapping("chr1", start, end);
+                single_found.sort();
+                single_found
+            });
+        }
+    }
+
     #[test]
     fn test_disable_linear_index_consistency() {
         let mut index = BinningIndex::default();
@@ -490,13 +2558,152 @@ mod tests {
         let path = Path::new("test_index.hgidx");
 
         // Serialize
-        index.finalize(&path).unwrap();
+        index.finalize(path).unwrap();
 
         // Deserialize
-        let deserialized_index = BinningIndex::open(&path).unwrap();
+        let deserialized_index = BinningIndex::open(path).unwrap();
         assert_eq!(deserialized_index.bins.schema, schema);
 
         // Clean up
         std::fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn test_find_overlapping_uses_stored_schema() {
+        // Queries must use the binning the index was built with (restored
+        // from disk), never a default binning, or features are silently missed.
+        let test_dir = TestDir::new("stored_schema").expect("Failed to create test dir");
+        let features: Vec<(u32, u32)> = (0..2000u32)
+            .map(|i| (i * 1000, i * 1000 + 10 + (i % 7) * 30_000))
+            .collect();
+        let queries = [
+            (0, 1),
+            (5_500, 5_600),
+            (123_456, 200_000),
+            (1_999_000, 2_100_000),
+        ];
+
+        for schema in [
+            BinningSchema::Tabix,
+            BinningSchema::TabixNoLinear,
+            BinningSchema::Ucsc,
+            BinningSchema::UcscNoLinear,
+            BinningSchema::Dense,
+            BinningSchema::Sparse,
+            BinningSchema::Flat,
+        ] {
+            let mut index = BinningIndex::new(&schema);
+            for (i, &(start, end)) in features.iter().enumerate() {
+                index.add_feature("chr1", start, end, i as u64, 1).unwrap();
+            }
+            let path = test_dir.path().join(format!("{}.bin", schema));
+            index.finalize(&path).unwrap();
+
+            let index = BinningIndex::open(&path).unwrap();
+            assert_eq!(index.bins.schema, schema);
+            for &(start, end) in &queries {
+                let mut found: Vec<u64> = index
+                    .find_overlapping("chr1", start, end)
+                    .into_iter()
+                    .map(|(offset, _)| offset)
+                    .collect();
+                found.sort_unstable();
+                let expected: Vec<u64> = features
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &(s, e))| s < end && e > start)
+                    .map(|(i, _)| i as u64)
+                    .collect();
+                assert_eq!(found, expected, "{} query {}-{}", schema, start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_spilled_index_matches_in_memory() {
+        let test_dir = TestDir::new("spilled_index").expect("Failed to create test dir");
+        let dir = test_dir.path();
+        let mut in_memory = BinningIndex::default();
+        let mut spilled = BinningIndex::default();
+        spilled.enable_spilling(&dir.join("parts")).unwrap();
+
+        for chrom in ["chr1", "chr2", "chr3"] {
+            for i in (0..100_000).step_by(1_000) {
+                in_memory
+                    .add_feature(chrom, i, i + 500, i as u64, 10)
+                    .unwrap();
+                spilled
+                    .add_feature(chrom, i, i + 500, i as u64, 10)
+                    .unwrap();
+            }
+        }
+        // Only the current sequence is held in memory.
+        assert_eq!(spilled.sequences.len(), 1);
+
+        let in_memory_path = dir.join("in_memory.bin");
+        let spilled_path = dir.join("spilled.bin");
+        in_memory.finalize(&in_memory_path).unwrap();
+        spilled.finalize(&spilled_path).unwrap();
+        assert!(!dir.join("parts").exists());
+
+        let in_memory = BinningIndex::open(&in_memory_path).unwrap();
+        let spilled = BinningIndex::open(&spilled_path).unwrap();
+        assert_eq!(in_memory, spilled);
+        assert_eq!(
+            in_memory.find_overlapping("chr2", 10_000, 20_000),
+            spilled.find_overlapping("chr2", 10_000, 20_000)
+        );
+    }
+
+    #[test]
+    fn test_spilled_index_bytes_match_serialize() {
+        // Every serialized field set, with one entry per map so their
+        // encoding doesn't depend on hash order
+        let test_dir = TestDir::new("spilled_index_bytes").expect("Failed to create test dir");
+        let dir = test_dir.path();
+        let build = |spill: bool| {
+            let mut index = BinningIndex::default();
+            if spill {
+                index.enable_spilling(&dir.join("parts")).unwrap();
+            }
+            let mut aliases = ChromAliases::new();
+            aliases.insert("1", "chr1");
+            index.add_chrom_aliases(aliases);
+            index.set_sequence_lengths([("chr1".to_string(), 5_000_000)]);
+            index.set_flat_max_length(1_000);
+            index.set_lowercase_chroms(true);
+            for i in (0..100_000).step_by(1_000) {
+                index.add_feature("chr1", i, i + 500, i as u64, 10).unwrap();
+            }
+            index
+        };
+
+        let in_memory_path = dir.join("in_memory.bin");
+        let spilled_path = dir.join("spilled.bin");
+        build(false)
+            .finalize_with_metadata(&in_memory_path, &"meta")
+            .unwrap();
+        build(true)
+            .finalize_with_metadata(&spilled_path, &"meta")
+            .unwrap();
+        let in_memory = std::fs::read(&in_memory_path).unwrap();
+        assert_eq!(std::fs::read(&spilled_path).unwrap(), in_memory);
+
+        let partial = BinningIndex::open_sequences(&spilled_path, &["chr1"]).unwrap();
+        assert_eq!(partial, BinningIndex::from_bytes(&in_memory).unwrap());
+    }
+
+    #[test]
+    fn test_spilled_index_rejects_ungrouped_sequences() {
+        let test_dir = TestDir::new("spilled_ungrouped").expect("Failed to create test dir");
+        let mut index = BinningIndex::default();
+        index.enable_spilling(test_dir.path()).unwrap();
+
+        index.add_feature("chr1", 1000, 2000, 0, 0).unwrap();
+        index.add_feature("chr2", 1000, 2000, 0, 0).unwrap();
+        assert!(matches!(
+            index.add_feature("chr1", 3000, 4000, 0, 0),
+            Err(HgIndexError::UngroupedSequence(chrom)) if chrom == "chr1"
+        ));
+    }
 }
@@ -3,4 +3,7 @@ pub mod binning;
 mod binning_index;
 
 pub use binning::{BinningSchema, HierarchicalBins};
-pub use binning_index::{BinningIndex, Feature, SequenceIndex};
+pub use binning_index::{
+    BinBounds, BinningIndex, Feature, FrozenBins, SequenceBuildHasher, SequenceHasher,
+    SequenceIndex, SequenceMap,
+};
@@ -1,16 +1,28 @@
 // io.rs
 
 use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
-use flate2::Compression;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::{Compression, Crc};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, BufWriter, Error, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use crate::error::HgIndexError;
+
 const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 const DEFAULT_BUFFER_SIZE: usize = 128 * 1024;
 
+/// Uncompressed bytes per BGZF block, as in htslib (leaving room for
+/// incompressible data to fit the 64 KiB block limit).
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+/// The empty block marking the end of a BGZF file.
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
 #[derive(Error, Debug)]
 pub enum IoError {
     #[error("IO error: {0}")]
@@ -76,6 +88,30 @@ impl InputStream {
     }
 }
 
+/// The distinct sequence names in the first column of a tab-delimited file
+/// (e.g. a BED file, optionally gzipped), in order of first appearance.
+/// Blank lines and `#` comment lines are skipped.
+pub fn scan_chromosomes(path: &Path) -> Result<Vec<String>, HgIndexError> {
+    let mut reader =
+        BufReader::with_capacity(DEFAULT_BUFFER_SIZE, InputStream::new(path).reader()?);
+    let mut seen = HashSet::new();
+    let mut chroms = Vec::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let chrom = line
+            .split(|&b| b == b'\t')
+            .next()
+            .unwrap_or_default()
+            .trim_ascii_end();
+        if !chrom.is_empty() && !chrom.starts_with(b"#") && !seen.contains(chrom) {
+            seen.insert(chrom.to_vec());
+            chroms.push(String::from_utf8_lossy(chrom).into_owned());
+        }
+        line.clear();
+    }
+    Ok(chroms)
+}
+
 #[derive(Clone)]
 pub struct OutputStreamBuilder {
     filepath: Option<PathBuf>,
@@ -108,6 +144,9 @@ impl OutputStreamBuilder {
         self
     }
 
+    /// Set the gzip level for `.gz` outputs; `None` selects
+    /// `Compression::best()`. Level 0 (`Compression::none()`) still writes a
+    /// gzip file, with stored (uncompressed) blocks.
     pub fn compression_level(mut self, level: Option<Compression>) -> Self {
         if let Some(level) = level {
             self.compression_level = level;
@@ -144,7 +183,7 @@ impl OutputStream {
     fn should_compress(&self) -> bool {
         self.filepath
             .as_ref()
-            .map_or(false, |p| p.extension().map_or(false, |ext| ext == "gz"))
+            .is_some_and(|p| p.extension().is_some_and(|ext| ext == "gz"))
     }
 
     pub fn writer(&self) -> Result<Box<dyn Write>, Error> {
@@ -168,3 +207,154 @@ impl OutputStream {
         }
     }
 }
+
+/// A writer producing BGZF (blocked gzip), the format `bgzip` writes and
+/// `tabix` indexes: a series of gzip members of at most 64 KiB each, whose
+/// headers record the block size. Any gzip reader can decompress it.
+///
+/// Call `finish` to write the final block and EOF marker; dropping the writer
+/// does so too, ignoring errors.
+pub struct BgzfWriter<W: Write> {
+    inner: Option<W>,
+    buffer: Vec<u8>,
+    compressed: Vec<u8>,
+    level: Compression,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W, level: Compression) -> Self {
+        Self {
+            inner: Some(inner),
+            buffer: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            compressed: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            level,
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let inner = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| io::Error::other("BgzfWriter already finished"))?;
+
+        self.compressed.clear();
+        let mut encoder = DeflateEncoder::new(&mut self.compressed, self.level);
+        encoder.write_all(&self.buffer)?;
+        encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(&self.buffer);
+
+        // Header (18 bytes) + compressed data + CRC32 and ISIZE (8 bytes)
+        let block_size = 18 + self.compressed.len() + 8;
+        let bsize = u16::try_from(block_size - 1)
+            .map_err(|_| io::Error::other("BGZF block exceeds 64 KiB"))?;
+        inner.write_all(&[
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, b'B', b'C',
+            0x02, 0x00,
+        ])?;
+        inner.write_all(&bsize.to_le_bytes())?;
+        inner.write_all(&self.compressed)?;
+        inner.write_all(&crc.sum().to_le_bytes())?;
+        inner.write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Write any buffered data and the EOF marker, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self
+            .inner
+            .take()
+            .expect("inner writer present until finish"))
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        if let Some(inner) = self.inner.as_mut() {
+            inner.write_all(&BGZF_EOF)?;
+            inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(BGZF_BLOCK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    /// Flushes complete blocks only; a partial block stays buffered so blocks
+    /// aren't cut short, which would bloat the output.
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for BgzfWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_utils::TestDir;
+    use flate2::read::MultiGzDecoder;
+
+    #[test]
+    fn test_scan_chromosomes() {
+        let test_dir = TestDir::new("scan_chromosomes").expect("Failed to create test dir");
+        let path = test_dir.path().join("regions.bed.gz");
+        let mut writer = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        writer
+            .write_all(b"#chrom\tstart\tend\nchr2\t1\t2\nchr1\t1\t2\n\nchr2\t5\t6\nchrX\t0\t1\r\nchr1\t9\t10")
+            .unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(scan_chromosomes(&path).unwrap(), ["chr2", "chr1", "chrX"]);
+        assert!(scan_chromosomes(&test_dir.path().join("missing.bed")).is_err());
+    }
+
+    #[test]
+    fn test_bgzf_writer() {
+        // Several blocks' worth of compressible and incompressible data
+        let mut data: Vec<u8> = (0..200_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        data.extend((0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8));
+
+        let mut writer = BgzfWriter::new(Vec::new(), Compression::default());
+        writer.write_all(&data).unwrap();
+        let output = writer.finish().unwrap();
+        assert!(output.ends_with(&BGZF_EOF));
+
+        // Every block header records its own size
+        let mut offset = 0;
+        while offset < output.len() {
+            assert_eq!(&output[offset..offset + 4], &[0x1f, 0x8b, 0x08, 0x04]);
+            assert_eq!(&output[offset + 12..offset + 14], b"BC");
+            let bsize = u16::from_le_bytes([output[offset + 16], output[offset + 17]]);
+            offset += bsize as usize + 1;
+        }
+        assert_eq!(offset, output.len());
+
+        let mut decompressed = Vec::new();
+        MultiGzDecoder::new(output.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+}
@@ -1,16 +1,29 @@
+// Emit a `tracing` event when the `tracing` feature is enabled; otherwise
+// expands to nothing.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+pub mod aliases;
 pub mod error;
 pub mod index;
 #[cfg(feature = "cli")]
 pub mod io;
 pub mod records;
+pub mod region;
 pub mod stats;
 pub mod store;
 
+pub use aliases::ChromAliases;
 pub use index::{BinningIndex, BinningSchema, Feature, HierarchicalBins, SequenceIndex};
 #[cfg(feature = "cli")]
 pub use io::*;
 pub use records::*;
-pub use store::GenomicDataStore;
+pub use region::{natural_chrom_cmp, natural_cmp, parse_region, touching_window};
+pub use store::{GenomicDataStore, IndexOnlyStore, MultiStore};
 
 #[cfg(test)]
 pub(crate) mod test_utils;
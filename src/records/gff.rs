@@ -0,0 +1,199 @@
+// records/gff.rs
+//
+// GFF3/GTF features as store records, so annotation files can be packed and
+// queried like BED files.
+
+use std::fmt;
+
+use super::interval::IntervalFormat;
+use super::{Record, RecordSlice, Strand};
+use crate::error::HgIndexError;
+
+/// A GFF3 or GTF line, less its seqid (implied by the sequence it is stored
+/// under). `start` and `end` are 0-based half-open, converted from the
+/// 1-based, fully closed start and end columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GffRecord {
+    pub start: u32,
+    pub end: u32,
+    /// The columns after seqid (source, type, start, end, score, strand,
+    /// phase, attributes) as written, tab-delimited.
+    pub rest: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GffRecordSlice<'a> {
+    pub start: u32,
+    pub end: u32,
+    pub rest: &'a [u8],
+}
+
+impl GffRecord {
+    /// Parse a GFF3 or GTF line into its seqid and record.
+    pub fn parse(line: &str) -> Result<(&str, Self), HgIndexError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (chrom, start, end) = IntervalFormat::Gff.parse_interval(&fields)?;
+        let rest = match line.split_once('\t') {
+            Some((_, rest)) => rest.as_bytes().to_vec(),
+            None => Vec::new(),
+        };
+        Ok((chrom, GffRecord { start, end, rest }))
+    }
+}
+
+impl Record for GffRecord {
+    type Slice<'a> = GffRecordSlice<'a>;
+
+    fn start(&self) -> u32 {
+        self.start
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.rest.len());
+        bytes.extend_from_slice(&self.start.to_le_bytes());
+        bytes.extend_from_slice(&self.end.to_le_bytes());
+        bytes.extend_from_slice(&self.rest);
+        bytes
+    }
+
+    /// The strand column, parsed from `rest` on demand.
+    fn strand(&self) -> Option<Strand> {
+        Strand::from_bytes(self.rest.split(|&b| b == b'\t').nth(5)?)
+    }
+}
+
+impl<'a> GffRecordSlice<'a> {
+    /// The `n`-th tab-delimited field of `rest`, where 0 is source, 1 is
+    /// type, and 7 is attributes. Returns `None` if the record has fewer
+    /// fields.
+    pub fn field(&self, n: usize) -> Option<&'a [u8]> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        self.rest.split(|&b| b == b'\t').nth(n)
+    }
+
+    /// The feature type column (e.g. `gene` or `exon`).
+    pub fn feature_type(&self) -> Option<&'a [u8]> {
+        self.field(1)
+    }
+
+    /// The value of the attribute `name` in the attributes column, in either
+    /// GFF3 (`gene_id=ABC`) or GTF (`gene_id "ABC"`) syntax, without quotes.
+    pub fn attribute(&self, name: &str) -> Option<&'a [u8]> {
+        let name = name.as_bytes();
+        self.field(7)?.split(|&b| b == b';').find_map(|entry| {
+            let entry = entry.trim_ascii();
+            let value = entry.strip_prefix(name)?;
+            let value = match value.first()? {
+                b'=' => &value[1..],
+                b' ' => value[1..].trim_ascii_start(),
+                _ => return None,
+            };
+            Some(
+                value
+                    .strip_prefix(b"\"")
+                    .and_then(|value| value.strip_suffix(b"\""))
+                    .unwrap_or(value),
+            )
+        })
+    }
+}
+
+impl<'a> RecordSlice<'a> for GffRecordSlice<'a> {
+    type Owned = GffRecord;
+
+    fn start(&self) -> u32 {
+        self.start
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    /// The strand column, if `+` or `-`.
+    fn strand(&self) -> Option<Strand> {
+        Strand::from_bytes(self.field(5)?)
+    }
+
+    fn try_from_bytes(bytes: &'a [u8]) -> Result<Self, HgIndexError> {
+        if bytes.len() < 8 {
+            return Err(format!("GffRecord needs at least 8 bytes, found {}", bytes.len()).into());
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        if bytes.len() < 8 {
+            panic!("Internal error: invalid byte record, bytes length too small.")
+        }
+        let start = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let end = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        Self {
+            start,
+            end,
+            rest: &bytes[8..],
+        }
+    }
+
+    fn to_owned(self) -> Self::Owned {
+        self.into()
+    }
+}
+
+impl From<GffRecordSlice<'_>> for GffRecord {
+    fn from(slice: GffRecordSlice<'_>) -> Self {
+        Self {
+            start: slice.start,
+            end: slice.end,
+            rest: slice.rest.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for GffRecordSlice<'_> {
+    /// The line as written, less its seqid column.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(self.rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gff_record() {
+        let line = "chr1\tHAVANA\tgene\t11869\t14409\t.\t+\t.\tID=ENSG1;gene_name=DDX11L1\n";
+        let (chrom, record) = GffRecord::parse(line).unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!((record.start, record.end), (11868, 14409));
+
+        let bytes = record.to_bytes();
+        let slice = GffRecordSlice::from_bytes(&bytes);
+        assert_eq!(slice.feature_type(), Some(&b"gene"[..]));
+        assert_eq!(slice.attribute("gene_name"), Some(&b"DDX11L1"[..]));
+        assert_eq!(slice.attribute("gene"), None);
+        assert_eq!(
+            slice.to_string(),
+            "HAVANA\tgene\t11869\t14409\t.\t+\t.\tID=ENSG1;gene_name=DDX11L1"
+        );
+        assert_eq!(GffRecord::from(slice), record);
+        assert_eq!(record.strand(), Some(Strand::Plus));
+
+        let gtf = "chr1\tsrc\texon\t1\t100\t.\t-\t.\tgene_id \"g1\"; transcript_id \"t1\";";
+        let (_, record) = GffRecord::parse(gtf).unwrap();
+        let bytes = record.to_bytes();
+        let slice = GffRecordSlice::from_bytes(&bytes);
+        assert_eq!((slice.start, slice.end), (0, 100));
+        assert_eq!(slice.attribute("gene_id"), Some(&b"g1"[..]));
+        assert_eq!(slice.attribute("transcript_id"), Some(&b"t1"[..]));
+        assert_eq!(slice.strand(), Some(Strand::Minus));
+
+        assert!(GffRecord::parse("chr1\tsrc\tgene\t0\t10").is_err());
+        assert!(GffRecordSlice::try_from_bytes(&[0; 4]).is_err());
+    }
+}
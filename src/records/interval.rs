@@ -0,0 +1,131 @@
+// records/interval.rs
+//
+// Parsing query intervals from the coordinate columns of common text formats,
+// so e.g. VCF variants or GFF features can be used directly as query regions.
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::error::HgIndexError;
+
+/// A tab-delimited text format whose records each describe an interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum IntervalFormat {
+    /// BED: chrom, start, end, 0-based half-open.
+    #[default]
+    Bed,
+    /// VCF: CHROM, POS, ID, REF, ...; the interval is the 1-based `POS`
+    /// spanning the length of the `REF` allele, or through `INFO`'s `END`
+    /// if that lies further (e.g. for symbolic alleles), as tabix does.
+    Vcf,
+    /// GFF/GTF: seqid, source, type, start, end, ...; 1-based, fully closed.
+    Gff,
+}
+
+impl IntervalFormat {
+    /// Parse the sequence name and 0-based, half-open interval of a record,
+    /// given its tab-delimited fields.
+    pub fn parse_interval<'a>(
+        self,
+        fields: &[&'a str],
+    ) -> Result<(&'a str, u32, u32), HgIndexError> {
+        let field = |index: usize, name: &str| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or_else(|| HgIndexError::StringError(format!("Missing {} column", name)))
+        };
+        let coordinate = |index: usize, name: &str| -> Result<u32, HgIndexError> {
+            field(index, name)?
+                .parse()
+                .map_err(|_| HgIndexError::StringError(format!("Invalid {} coordinate", name)))
+        };
+        let one_based = |position: u32, name: &str| {
+            position.checked_sub(1).ok_or_else(|| {
+                HgIndexError::StringError(format!("{} must be greater than 0", name))
+            })
+        };
+
+        let chrom = field(0, "chrom")?;
+        let (start, end) = match self {
+            IntervalFormat::Bed => (coordinate(1, "start")?, coordinate(2, "end")?),
+            IntervalFormat::Vcf => {
+                let start = one_based(coordinate(1, "POS")?, "POS")?;
+                let ref_length = match fields.get(3) {
+                    Some(allele) if !allele.is_empty() && *allele != "." => allele.len() as u32,
+                    _ => 1,
+                };
+                let end = start.saturating_add(ref_length);
+                let info_end = fields.get(7).and_then(|info| {
+                    info.split(';')
+                        .find_map(|entry| entry.strip_prefix("END="))
+                        .and_then(|value| value.parse::<u32>().ok())
+                });
+                (start, info_end.map_or(end, |info_end| end.max(info_end)))
+            }
+            IntervalFormat::Gff => (
+                one_based(coordinate(3, "start")?, "start")?,
+                coordinate(4, "end")?,
+            ),
+        };
+        Ok((chrom, start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        let bed = ["chr1", "100", "200", "name"];
+        assert_eq!(
+            IntervalFormat::Bed.parse_interval(&bed).unwrap(),
+            ("chr1", 100, 200)
+        );
+
+        // A SNV at POS 101 and a deletion spanning its 3 REF bases
+        let snv = ["chr1", "101", "rs1", "A", "G", ".", "PASS", "."];
+        assert_eq!(
+            IntervalFormat::Vcf.parse_interval(&snv).unwrap(),
+            ("chr1", 100, 101)
+        );
+        let deletion = ["chr1", "101", ".", "ACG", "A"];
+        assert_eq!(
+            IntervalFormat::Vcf.parse_interval(&deletion).unwrap(),
+            ("chr1", 100, 103)
+        );
+
+        // A symbolic deletion spanning to its INFO END
+        let sv = [
+            "chr1",
+            "1000",
+            ".",
+            "N",
+            "<DEL>",
+            ".",
+            ".",
+            "SVTYPE=DEL;END=1500",
+        ];
+        assert_eq!(
+            IntervalFormat::Vcf.parse_interval(&sv).unwrap(),
+            ("chr1", 999, 1500)
+        );
+
+        let gff = [
+            "chr2", "src", "gene", "1001", "2000", ".", "+", ".", "ID=g1",
+        ];
+        assert_eq!(
+            IntervalFormat::Gff.parse_interval(&gff).unwrap(),
+            ("chr2", 1000, 2000)
+        );
+
+        assert!(IntervalFormat::Vcf
+            .parse_interval(&["chr1", "0", ".", "A"])
+            .is_err());
+        assert!(IntervalFormat::Gff
+            .parse_interval(&["chr1", "src"])
+            .is_err());
+    }
+}
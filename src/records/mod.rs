@@ -1,18 +1,91 @@
 // records/mod.rs
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::error::HgIndexError;
+
+pub mod gff;
+pub mod interval;
+pub mod stream;
+pub mod vcf;
+pub use gff::{GffRecord, GffRecordSlice};
+pub use interval::IntervalFormat;
+pub use stream::{RecordStreamReader, RecordStreamWriter};
+pub use vcf::{VcfRecord, VcfRecordSlice};
+
+/// The coordinate convention of an input source. Stores always hold 0-based,
+/// half-open coordinates internally; this records how the source was written.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CoordinateSystem {
+    /// 0-based, half-open `[start, end)`, as in BED.
+    #[default]
+    ZeroBased,
+    /// 1-based, fully closed `[start, end]`, as in GFF, VCF, and tabix regions.
+    OneBased,
+}
+
+impl fmt::Display for CoordinateSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinateSystem::ZeroBased => write!(f, "0-based"),
+            CoordinateSystem::OneBased => write!(f, "1-based"),
+        }
+    }
+}
+
+/// The strand of a stranded record, or `Both` to match either strand in
+/// queries such as `GenomicDataStore::get_overlapping_stranded`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strand {
+    Plus,
+    Minus,
+    Both,
+}
+
+impl Strand {
+    /// Parse a strand column: `+` or `-`. Anything else (e.g. `.`) is an
+    /// unknown strand.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Strand> {
+        match bytes {
+            b"+" => Some(Strand::Plus),
+            b"-" => Some(Strand::Minus),
+            _ => None,
+        }
+    }
+
+    /// True if a record on `strand` passes this strand filter. Records of
+    /// unknown strand match only `Both`.
+    pub fn matches(self, strand: Option<Strand>) -> bool {
+        self == Strand::Both || strand == Some(self)
+    }
+}
+
 pub trait Record: Sized + for<'a> From<Self::Slice<'a>> {
     type Slice<'a>: RecordSlice<'a, Owned = Self>;
     fn start(&self) -> u32;
     fn end(&self) -> u32;
     fn to_bytes(&self) -> Vec<u8>;
+    /// The record's strand, if it has one.
+    fn strand(&self) -> Option<Strand> {
+        None
+    }
 }
 
 pub trait RecordSlice<'a>: Sized {
     type Owned: Record + From<Self>;
     fn from_bytes(bytes: &'a [u8]) -> Self;
+    /// Parse a record, returning an error rather than panicking on malformed
+    /// bytes. Types whose `from_bytes` can panic should override this.
+    fn try_from_bytes(bytes: &'a [u8]) -> Result<Self, HgIndexError> {
+        Ok(Self::from_bytes(bytes))
+    }
     fn start(&self) -> u32;
     fn end(&self) -> u32;
+    /// The record's strand, if it has one, as for `Record::strand`, but
+    /// without converting to an owned record.
+    fn strand(&self) -> Option<Strand> {
+        None
+    }
     fn to_owned(self) -> Self::Owned;
 }
 
@@ -48,6 +121,23 @@ impl Record for BedRecord {
         bytes.extend_from_slice(self.rest.as_bytes());
         bytes
     }
+
+    /// The strand column (BED column 6), parsed from `rest` on demand.
+    fn strand(&self) -> Option<Strand> {
+        Strand::from_bytes(self.rest.split('\t').nth(2)?.as_bytes())
+    }
+}
+
+impl<'a> BedRecordSlice<'a> {
+    /// The `n`-th tab-delimited field of `rest`, where 0 is the first field
+    /// after `end` (BED column 4). Returns `None` if the record has fewer
+    /// fields; a trailing tab yields an empty last field.
+    pub fn field(&self, n: usize) -> Option<&'a [u8]> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        self.rest.split(|&b| b == b'\t').nth(n)
+    }
 }
 
 impl<'a> RecordSlice<'a> for BedRecordSlice<'a> {
@@ -60,6 +150,18 @@ impl<'a> RecordSlice<'a> for BedRecordSlice<'a> {
         self.end
     }
 
+    /// The strand column (BED column 6), if present and `+` or `-`.
+    fn strand(&self) -> Option<Strand> {
+        Strand::from_bytes(self.field(2)?)
+    }
+
+    fn try_from_bytes(bytes: &'a [u8]) -> Result<Self, HgIndexError> {
+        if bytes.len() < 8 {
+            return Err(format!("BedRecord needs at least 8 bytes, found {}", bytes.len()).into());
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+
     fn from_bytes(bytes: &'a [u8]) -> Self {
         if bytes.len() < 8 {
             panic!("Internal error: invalid byte record, bytes length too small.")
@@ -138,3 +240,65 @@ impl fmt::Display for BedRecordSlice<'_> {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bed_record_slice_field() {
+        let record = BedRecordSlice {
+            start: 0,
+            end: 10,
+            rest: b"name\t0.5\t+",
+        };
+        assert_eq!(record.field(0), Some(&b"name"[..]));
+        assert_eq!(record.field(2), Some(&b"+"[..]));
+        assert_eq!(record.field(3), None);
+
+        // A trailing tab leaves an empty final field
+        let trailing = BedRecordSlice {
+            start: 0,
+            end: 10,
+            rest: b"name\t",
+        };
+        assert_eq!(trailing.field(1), Some(&b""[..]));
+        assert_eq!(trailing.field(2), None);
+
+        let bare = BedRecordSlice {
+            start: 0,
+            end: 10,
+            rest: b"",
+        };
+        assert_eq!(bare.field(0), None);
+    }
+
+    #[test]
+    fn test_bed_record_strand() {
+        let record = BedRecord {
+            start: 0,
+            end: 10,
+            rest: "name\t0\t-\t5".to_string(),
+        };
+        assert_eq!(record.strand(), Some(Strand::Minus));
+        let bytes = record.to_bytes();
+        assert_eq!(
+            BedRecordSlice::from_bytes(&bytes).strand(),
+            Some(Strand::Minus)
+        );
+
+        for rest in ["name\t0\t.", "name\t0", ""] {
+            let record = BedRecord {
+                start: 0,
+                end: 10,
+                rest: rest.to_string(),
+            };
+            assert_eq!(record.strand(), None);
+        }
+
+        assert!(Strand::Minus.matches(Some(Strand::Minus)));
+        assert!(!Strand::Plus.matches(Some(Strand::Minus)));
+        assert!(!Strand::Plus.matches(None));
+        assert!(Strand::Both.matches(None));
+    }
+}
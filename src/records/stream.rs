@@ -0,0 +1,191 @@
+// records/stream.rs
+//
+// A binary stream of stored record payloads, for piping query results from one
+// store into another without a lossy text round trip.
+//
+// Format (all integers little-endian):
+//
+//   magic:  b"GIDS"
+//   frames: a sequence of tagged frames until EOF
+//     0x01 chrom frame:  u32 name length, then the UTF-8 sequence name. Sets
+//                        the sequence of all following record frames.
+//     0x02 record frame: u64 payload length, then the payload bytes exactly
+//                        as stored in a data file (i.e. `Record::to_bytes`).
+//                        Readers reject lengths over DEFAULT_MAX_RECORD_LEN.
+//
+// Writers emit a chrom frame only when the sequence changes, so the chrom
+// "sidecar" costs one frame per sequence rather than one per record.
+
+use std::io::{self, Read, Write};
+
+use crate::error::HgIndexError;
+use crate::store::DEFAULT_MAX_RECORD_LEN;
+
+pub const RECORD_STREAM_MAGIC: [u8; 4] = *b"GIDS";
+
+const CHROM_FRAME: u8 = 0x01;
+const RECORD_FRAME: u8 = 0x02;
+
+/// Writes record payloads in the `hgidx-records` stream format.
+pub struct RecordStreamWriter<W: Write> {
+    writer: W,
+    chrom: Option<String>,
+}
+
+impl<W: Write> RecordStreamWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&RECORD_STREAM_MAGIC)?;
+        Ok(Self {
+            writer,
+            chrom: None,
+        })
+    }
+
+    pub fn write_record(&mut self, chrom: &str, payload: &[u8]) -> io::Result<()> {
+        if self.chrom.as_deref() != Some(chrom) {
+            self.writer.write_all(&[CHROM_FRAME])?;
+            self.writer.write_all(&(chrom.len() as u32).to_le_bytes())?;
+            self.writer.write_all(chrom.as_bytes())?;
+            self.chrom = Some(chrom.to_string());
+        }
+        self.writer.write_all(&[RECORD_FRAME])?;
+        self.writer
+            .write_all(&(payload.len() as u64).to_le_bytes())?;
+        self.writer.write_all(payload)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reads record payloads from an `hgidx-records` stream.
+pub struct RecordStreamReader<R: Read> {
+    reader: R,
+    chrom: Option<String>,
+    payload: Vec<u8>,
+    // Bytes read so far, to locate corrupt frames
+    offset: u64,
+}
+
+impl<R: Read> RecordStreamReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, HgIndexError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != RECORD_STREAM_MAGIC {
+            return Err("Input is not an hgidx-records stream (bad magic).".into());
+        }
+        Ok(Self {
+            reader,
+            chrom: None,
+            payload: Vec::new(),
+            offset: RECORD_STREAM_MAGIC.len() as u64,
+        })
+    }
+
+    /// Read the next record, returning its sequence name and stored payload,
+    /// or `None` at the end of the stream.
+    pub fn next_record(&mut self) -> Result<Option<(&str, &[u8])>, HgIndexError> {
+        loop {
+            let mut tag = [0u8; 1];
+            match self.reader.read_exact(&mut tag) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+
+            match tag[0] {
+                CHROM_FRAME => {
+                    let mut len = [0u8; 4];
+                    self.reader.read_exact(&mut len)?;
+                    let mut name = vec![0u8; u32::from_le_bytes(len) as usize];
+                    self.reader.read_exact(&mut name)?;
+                    self.offset += 1 + len.len() as u64 + name.len() as u64;
+                    let name = String::from_utf8(name)
+                        .map_err(|_| "Invalid UTF-8 sequence name in record stream.")?;
+                    self.chrom = Some(name);
+                }
+                RECORD_FRAME => {
+                    let mut len = [0u8; 8];
+                    self.reader.read_exact(&mut len)?;
+                    // Check the length before allocating for it
+                    let length = u64::from_le_bytes(len);
+                    if length > DEFAULT_MAX_RECORD_LEN {
+                        return Err(HgIndexError::CorruptRecord {
+                            offset: self.offset,
+                            reason: format!(
+                                "record length {} exceeds the maximum record length {}",
+                                length, DEFAULT_MAX_RECORD_LEN
+                            ),
+                        });
+                    }
+                    self.payload.resize(length as usize, 0);
+                    self.reader.read_exact(&mut self.payload)?;
+                    self.offset += 1 + len.len() as u64 + length;
+                    let chrom = self
+                        .chrom
+                        .as_deref()
+                        .ok_or("Record frame before any chrom frame in record stream.")?;
+                    return Ok(Some((chrom, &self.payload)));
+                }
+                tag => {
+                    return Err(format!("Unknown frame tag {:#04x} in record stream.", tag).into())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_stream_round_trip() {
+        let mut buffer = Vec::new();
+        {
+            let mut writer = RecordStreamWriter::new(&mut buffer).unwrap();
+            writer.write_record("chr1", b"first").unwrap();
+            writer.write_record("chr1", b"").unwrap();
+            writer.write_record("chr2", b"third").unwrap();
+            writer.flush().unwrap();
+        }
+
+        let mut reader = RecordStreamReader::new(buffer.as_slice()).unwrap();
+        let mut records = Vec::new();
+        while let Some((chrom, payload)) = reader.next_record().unwrap() {
+            records.push((chrom.to_string(), payload.to_vec()));
+        }
+        assert_eq!(
+            records,
+            vec![
+                ("chr1".to_string(), b"first".to_vec()),
+                ("chr1".to_string(), Vec::new()),
+                ("chr2".to_string(), b"third".to_vec()),
+            ]
+        );
+
+        assert!(RecordStreamReader::new(&b"chr1\t1\t2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_record_stream_rejects_long_records() {
+        let mut buffer = Vec::new();
+        {
+            let mut writer = RecordStreamWriter::new(&mut buffer).unwrap();
+            writer.write_record("chr1", b"first").unwrap();
+        }
+        // A record frame claiming an implausible length, without its bytes
+        buffer.push(RECORD_FRAME);
+        buffer.extend_from_slice(&u64::MAX.to_le_bytes());
+
+        let mut reader = RecordStreamReader::new(buffer.as_slice()).unwrap();
+        assert!(reader.next_record().unwrap().is_some());
+        match reader.next_record() {
+            Err(HgIndexError::CorruptRecord { offset, .. }) => {
+                assert_eq!(offset, 4 + 1 + 4 + 4 + 1 + 8 + 5)
+            }
+            other => panic!("expected CorruptRecord, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// records/vcf.rs
+//
+// VCF sites as store records, so VCF files can be packed and queried like
+// BED files.
+
+use std::fmt;
+
+use super::interval::IntervalFormat;
+use super::{Record, RecordSlice};
+use crate::error::HgIndexError;
+
+/// A VCF data line, less its CHROM (implied by the sequence it is stored
+/// under). The site spans `[pos - 1, end)`, 0-based half-open: from its
+/// 1-based `POS` through its `REF` allele, or through `INFO`'s `END` if
+/// that lies further (e.g. for symbolic alleles), as tabix computes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcfRecord {
+    /// The 1-based `POS` column.
+    pub pos: u32,
+    /// The 0-based, exclusive end of the site.
+    pub end: u32,
+    /// The columns after `POS` (ID, REF, ALT, ...), tab-delimited.
+    pub rest: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VcfRecordSlice<'a> {
+    pub pos: u32,
+    pub end: u32,
+    pub rest: &'a [u8],
+}
+
+impl VcfRecord {
+    /// Parse a VCF data line into its CHROM and record.
+    pub fn parse(line: &str) -> Result<(&str, Self), HgIndexError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (chrom, start, end) = IntervalFormat::Vcf.parse_interval(&fields)?;
+
+        // Everything after the POS column
+        let rest = match line.splitn(3, '\t').nth(2) {
+            Some(rest) => rest.as_bytes().to_vec(),
+            None => Vec::new(),
+        };
+        Ok((
+            chrom,
+            VcfRecord {
+                pos: start + 1,
+                end,
+                rest,
+            },
+        ))
+    }
+}
+
+impl Record for VcfRecord {
+    type Slice<'a> = VcfRecordSlice<'a>;
+
+    fn start(&self) -> u32 {
+        self.pos - 1
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.rest.len());
+        bytes.extend_from_slice(&self.pos.to_le_bytes());
+        bytes.extend_from_slice(&self.end.to_le_bytes());
+        bytes.extend_from_slice(&self.rest);
+        bytes
+    }
+}
+
+impl<'a> VcfRecordSlice<'a> {
+    /// The `n`-th tab-delimited field of `rest`, where 0 is ID, 1 is REF,
+    /// and so on. Returns `None` if the record has fewer fields.
+    pub fn field(&self, n: usize) -> Option<&'a [u8]> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        self.rest.split(|&b| b == b'\t').nth(n)
+    }
+}
+
+impl<'a> RecordSlice<'a> for VcfRecordSlice<'a> {
+    type Owned = VcfRecord;
+
+    fn start(&self) -> u32 {
+        self.pos - 1
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+
+    fn try_from_bytes(bytes: &'a [u8]) -> Result<Self, HgIndexError> {
+        if bytes.len() < 8 {
+            return Err(format!("VcfRecord needs at least 8 bytes, found {}", bytes.len()).into());
+        }
+        // POS is 1-based, so 0 has no start
+        if bytes[0..4] == [0; 4] {
+            return Err("VcfRecord POS must be greater than 0".into());
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+
+    fn from_bytes(bytes: &'a [u8]) -> Self {
+        if bytes.len() < 8 {
+            panic!("Internal error: invalid byte record, bytes length too small.")
+        }
+        let pos = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let end = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        Self {
+            pos,
+            end,
+            rest: &bytes[8..],
+        }
+    }
+
+    fn to_owned(self) -> Self::Owned {
+        self.into()
+    }
+}
+
+impl From<VcfRecordSlice<'_>> for VcfRecord {
+    fn from(slice: VcfRecordSlice<'_>) -> Self {
+        Self {
+            pos: slice.pos,
+            end: slice.end,
+            rest: slice.rest.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for VcfRecordSlice<'_> {
+    /// The line as written, less its CHROM column.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rest.is_empty() {
+            write!(f, "{}", self.pos)
+        } else {
+            write!(f, "{}\t{}", self.pos, String::from_utf8_lossy(self.rest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vcf_record() {
+        let line = "chr1\t101\trs1\tACG\tA\t50\tPASS\tDP=10\n";
+        let (chrom, record) = VcfRecord::parse(line).unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!((record.start(), record.end()), (100, 103));
+        assert_eq!(record.rest, b"rs1\tACG\tA\t50\tPASS\tDP=10");
+
+        let bytes = record.to_bytes();
+        let slice = VcfRecordSlice::from_bytes(&bytes);
+        assert_eq!((slice.start(), slice.end()), (100, 103));
+        assert_eq!(slice.field(1), Some(&b"ACG"[..]));
+        assert_eq!(slice.to_string(), "101\trs1\tACG\tA\t50\tPASS\tDP=10");
+        assert_eq!(VcfRecord::from(slice), record);
+
+        // A symbolic deletion spanning to its INFO END
+        let (_, record) =
+            VcfRecord::parse("chr1\t1000\t.\tN\t<DEL>\t.\t.\tSVTYPE=DEL;END=1500").unwrap();
+        assert_eq!((record.start(), record.end()), (999, 1500));
+
+        assert!(VcfRecord::parse("chr1\t0\t.\tA").is_err());
+        assert!(VcfRecordSlice::try_from_bytes(&[0; 4]).is_err());
+        assert!(VcfRecordSlice::try_from_bytes(&[0; 8]).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+// region.rs
+
+use std::cmp::Ordering;
+
+use crate::error::HgIndexError;
+
+/// Parse a tabix-style region string into a sequence name and 0-based,
+/// half-open `[start, end)` coordinates.
+///
+/// Accepted forms, with 1-based inclusive coordinates as in tabix and samtools:
+///
+/// - `chr1:1,000-2,000` → `("chr1", 999, 2000)` (commas are ignored)
+/// - `chr1:1000` → `("chr1", 999, u32::MAX)`, i.e. from a position to the end
+/// - `chr1` → `("chr1", 0, u32::MAX)`, i.e. the whole sequence
+///
+/// Sequence names may themselves contain `:` (e.g. `HLA-A*01:01`); the text
+/// after the last `:` is only treated as coordinates if it looks like them, so
+/// such names need an explicit range (e.g. `HLA-A*01:01:1-100`).
+pub fn parse_region(region: &str) -> Result<(String, u32, u32), HgIndexError> {
+    let region = region.trim();
+    if region.is_empty() {
+        return Err("Invalid region: empty region string.".into());
+    }
+
+    let Some((seqname, coords)) = region.rsplit_once(':') else {
+        return Ok((region.to_string(), 0, u32::MAX));
+    };
+    let Some((start, end)) = parse_coordinates(coords)? else {
+        return Ok((region.to_string(), 0, u32::MAX));
+    };
+    if seqname.is_empty() {
+        return Err(format!("Invalid region {}: missing sequence name.", region).into());
+    }
+
+    // Convert to 0-based, half-open coordinates
+    let start = start
+        .checked_sub(1)
+        .ok_or("Start coordinate must be greater than 0")?;
+    if end <= start {
+        return Err(HgIndexError::InvalidInterval { start, end });
+    }
+    Ok((seqname.to_string(), start, end))
+}
+
+/// Parse `start-end` or `start` (1-based, with optional commas). Returns
+/// `Ok(None)` if `coords` doesn't look like coordinates at all.
+fn parse_coordinates(coords: &str) -> Result<Option<(u32, u32)>, HgIndexError> {
+    let coords = coords.replace(',', "");
+    let looks_numeric = !coords.is_empty()
+        && coords.chars().all(|c| c.is_ascii_digit() || c == '-')
+        && !coords.starts_with('-');
+    if !looks_numeric {
+        return Ok(None);
+    }
+
+    let (start, end) = match coords.split_once('-') {
+        Some((start, end)) => (start, Some(end)),
+        None => (coords.as_str(), None),
+    };
+    let start: u32 = start
+        .parse()
+        .map_err(|_| format!("Invalid start coordinate: {}", start))?;
+    let end: u32 = match end {
+        Some(end) => end
+            .parse()
+            .map_err(|_| format!("Invalid end coordinate: {}", end))?,
+        None => u32::MAX,
+    };
+    Ok(Some((start, end)))
+}
+
+/// The query interval whose overlaps are exactly the features *touching*
+/// the half-open interval `[start, end)`.
+///
+/// Overlap is strict: a feature `[fs, fe)` overlaps `[start, end)` iff
+/// `fs < end && fe > start`, so book-ended features (`fe == start` or
+/// `fs == end`), which share no base with the query, are excluded. A feature
+/// touches the query iff `fs <= end && fe >= start`, i.e. it overlaps or is
+/// book-ended. For integer coordinates that is the same as strictly
+/// overlapping `[start - 1, end + 1)`, which this returns (saturating at the
+/// ends of the coordinate range).
+pub fn touching_window(start: u32, end: u32) -> (u32, u32) {
+    (start.saturating_sub(1), end.saturating_add(1))
+}
+
+/// Compare sequence names in natural order, treating runs of digits as
+/// numbers, so `chr2` sorts before `chr10` (as in `sort -V`).
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a.as_bytes(), b.as_bytes());
+    while let (Some(&x), Some(&y)) = (a_rest.first(), b_rest.first()) {
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_len = a_rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b_rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            let (a_num, b_num) = (&a_rest[..a_len], &b_rest[..b_len]);
+            // Compare by magnitude without parsing, which could overflow
+            let a_trimmed = trim_leading_zeros(a_num);
+            let b_trimmed = trim_leading_zeros(b_num);
+            let ordering = a_trimmed
+                .len()
+                .cmp(&b_trimmed.len())
+                .then_with(|| a_trimmed.cmp(b_trimmed));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            a_rest = &a_rest[a_len..];
+            b_rest = &b_rest[b_len..];
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            a_rest = &a_rest[1..];
+            b_rest = &b_rest[1..];
+        }
+    }
+    a_rest.len().cmp(&b_rest.len()).then_with(|| a.cmp(b))
+}
+
+/// Compare chromosome names in karyotype order: numbered chromosomes by
+/// number, then X, Y, and the mitochondrial genome (M or MT), then any other
+/// sequences (unplaced, `_random`, `_alt`, and `Un` contigs, etc.) in
+/// natural order. A `chr` prefix (in any case) is ignored for ranking, so
+/// `chr2`, `2`, and `Chr2` rank together, tied by natural order.
+pub fn natural_chrom_cmp(a: &str, b: &str) -> Ordering {
+    chrom_rank(a)
+        .cmp(&chrom_rank(b))
+        .then_with(|| match (chrom_number(a), chrom_number(b)) {
+            (Some(a_num), Some(b_num)) => natural_cmp(a_num, b_num),
+            _ => Ordering::Equal,
+        })
+        .then_with(|| natural_cmp(a, b))
+}
+
+// Strip a `chr` prefix, in any case
+fn strip_chr(chrom: &str) -> &str {
+    match chrom.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("chr") => &chrom[3..],
+        _ => chrom,
+    }
+}
+
+// The number of a numbered chromosome
+fn chrom_number(chrom: &str) -> Option<&str> {
+    let name = strip_chr(chrom);
+    (!name.is_empty() && name.bytes().all(|c| c.is_ascii_digit())).then_some(name)
+}
+
+// Numbered chromosomes, X, Y, mitochondria, then everything else
+fn chrom_rank(chrom: &str) -> u8 {
+    if chrom_number(chrom).is_some() {
+        return 0;
+    }
+    match strip_chr(chrom).to_ascii_uppercase().as_str() {
+        "X" => 1,
+        "Y" => 2,
+        "M" | "MT" => 3,
+        _ => 4,
+    }
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(
+            parse_region("chr17:7661779-7687538").unwrap(),
+            ("chr17".to_string(), 7661778, 7687538)
+        );
+        assert_eq!(
+            parse_region("chr1:1,000-2,000").unwrap(),
+            ("chr1".to_string(), 999, 2000)
+        );
+        assert_eq!(
+            parse_region("chr1:1000").unwrap(),
+            ("chr1".to_string(), 999, u32::MAX)
+        );
+        assert_eq!(
+            parse_region("chrX").unwrap(),
+            ("chrX".to_string(), 0, u32::MAX)
+        );
+        assert_eq!(
+            parse_region("chrUn:alt").unwrap(),
+            ("chrUn:alt".to_string(), 0, u32::MAX)
+        );
+        assert_eq!(
+            parse_region("HLA-A*01:01:100-200").unwrap(),
+            ("HLA-A*01:01".to_string(), 99, 200)
+        );
+        // A single base
+        assert_eq!(
+            parse_region("chr2:5-5").unwrap(),
+            ("chr2".to_string(), 4, 5)
+        );
+    }
+
+    #[test]
+    fn test_touching_window() {
+        assert_eq!(touching_window(100, 200), (99, 201));
+        assert_eq!(touching_window(0, 10), (0, 11));
+        let (start, end) = touching_window(100, 200);
+        // Book-ended on either side, and one base beyond
+        for (fs, fe, touches) in [
+            (50, 100, true),
+            (200, 250, true),
+            (50, 99, false),
+            (201, 250, false),
+        ] {
+            assert_eq!(fs < end && fe > start, touches);
+        }
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["chr10", "chrX", "chr2", "chr1", "chr1_alt", "chrM", "chr02"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["chr1", "chr1_alt", "chr02", "chr2", "chr10", "chrM", "chrX"]
+        );
+    }
+
+    #[test]
+    fn test_natural_chrom_cmp() {
+        let mut names = vec![
+            "chrUn_KI270302v1",
+            "chrM",
+            "chr10",
+            "chrY",
+            "chr1_KI270706v1_random",
+            "chrX",
+            "chr2",
+            "chr1",
+            "chrEBV",
+            "chr22",
+        ];
+        names.sort_by(|a, b| natural_chrom_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "chr1",
+                "chr2",
+                "chr10",
+                "chr22",
+                "chrX",
+                "chrY",
+                "chrM",
+                "chr1_KI270706v1_random",
+                "chrEBV",
+                "chrUn_KI270302v1",
+            ]
+        );
+
+        // Ensembl-style names, without the prefix
+        let mut names = vec!["MT", "X", "10", "2", "GL000192.1"];
+        names.sort_by(|a, b| natural_chrom_cmp(a, b));
+        assert_eq!(names, vec!["2", "10", "X", "MT", "GL000192.1"]);
+
+        assert!(natural_chrom_cmp("2", "chr2").is_lt());
+        assert!(natural_chrom_cmp("chr1", "Chr2").is_lt());
+        assert!(natural_chrom_cmp("chr99999999999999999999", "chrX").is_lt());
+    }
+
+    #[test]
+    fn test_parse_region_errors() {
+        assert!(parse_region("").is_err());
+        assert!(parse_region("chr1:0-100").is_err());
+        assert!(parse_region("chr1:200-100").is_err());
+        assert!(parse_region("chr1:1-2-3").is_err());
+        assert!(parse_region(":1-100").is_err());
+        assert!(parse_region("chr1:99999999999-100").is_err());
+    }
+}
@@ -1,10 +1,13 @@
 // src/stats.rs
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::BinningIndex;
 
+/// Maximum number of synthetic queries used to estimate linear index pruning.
+const PRUNING_SAMPLE_QUERIES: usize = 1000;
+
 /// Detailed statistics about bin utilization and access patterns
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BinningStats {
@@ -18,7 +21,7 @@ pub struct BinningStats {
     pub level_stats: Vec<LevelStats>,
 
     // Distribution analysis
-    pub bin_occupancy: HashMap<u32, usize>, // bin_id -> feature count
+    pub bin_occupancy: BTreeMap<u32, usize>, // bin_id -> feature count
     pub feature_size_dist: SizeDistribution,
 
     // Query performance predictors
@@ -32,6 +35,10 @@ pub struct BinningStats {
     pub level_shift: u32,
     pub num_levels: usize,
     pub linear_index_present: bool,
+    /// Estimated fraction of candidate features (those in the bins a query
+    /// visits) that the linear index's `min_offset` rules out, over a sample
+    /// of synthetic queries. `None` without a linear index.
+    pub linear_index_pruning: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,7 +58,70 @@ pub struct SizeDistribution {
     pub max_size: u32,
     pub mean_size: f64,
     pub median_size: f64,
-    pub size_histogram: HashMap<u32, u32>, // size bucket -> count
+    pub size_histogram: BTreeMap<u32, u32>, // size bucket -> count
+}
+
+/// Statistics maintained incrementally as features are added to a
+/// `BinningIndex`, so a summary is available without a full scan.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunningStats {
+    pub total_features: u64,
+    pub total_bins_used: u64,
+    /// Smallest feature width seen (0 if no features).
+    pub min_size: u32,
+    /// Largest feature width seen.
+    pub max_size: u32,
+    /// Sum of all feature widths, for the mean.
+    pub total_size: u64,
+}
+
+impl RunningStats {
+    /// Record a newly added feature, and whether it was the first in its bin.
+    pub fn record(&mut self, size: u32, new_bin: bool) {
+        if self.total_features == 0 || size < self.min_size {
+            self.min_size = size;
+        }
+        self.max_size = self.max_size.max(size);
+        self.total_size += size as u64;
+        self.total_features += 1;
+        if new_bin {
+            self.total_bins_used += 1;
+        }
+    }
+
+    /// Add the statistics of features recorded elsewhere, e.g. in an index
+    /// being merged into this one. Bins used by both are counted twice.
+    pub fn merge(&mut self, other: &RunningStats) {
+        if other.total_features == 0 {
+            return;
+        }
+        if self.total_features == 0 || other.min_size < self.min_size {
+            self.min_size = other.min_size;
+        }
+        self.max_size = self.max_size.max(other.max_size);
+        self.total_size += other.total_size;
+        self.total_features += other.total_features;
+        self.total_bins_used += other.total_bins_used;
+    }
+
+    pub fn mean_size(&self) -> f64 {
+        if self.total_features == 0 {
+            return 0.0;
+        }
+        self.total_size as f64 / self.total_features as f64
+    }
+
+    /// Print a one-line summary.
+    pub fn print_summary(&self) {
+        eprintln!(
+            "Indexed {} features in {} bins (sizes: min {}, max {}, mean {:.2}).",
+            self.total_features,
+            self.total_bins_used,
+            self.min_size,
+            self.max_size,
+            self.mean_size()
+        );
+    }
 }
 
 impl BinningStats {
@@ -63,7 +133,7 @@ impl BinningStats {
             total_possible_bins: 0,
             bin_utilization: 0.0,
             level_stats: Vec::new(),
-            bin_occupancy: HashMap::new(),
+            bin_occupancy: BTreeMap::new(),
             feature_size_dist: SizeDistribution::default(),
             bin_density: 0.0,
             feature_overlap: 0.0,
@@ -73,6 +143,7 @@ impl BinningStats {
             level_shift: index.bins.level_shift,
             num_levels: index.bins.num_levels,
             linear_index_present: index.bins.uses_linear_index(),
+            linear_index_pruning: None,
         };
 
         // Calculate total possible bins
@@ -84,8 +155,8 @@ impl BinningStats {
 
         for seq_index in index.sequences.values() {
             // Count features and bin usage
-            for (bin_id, features) in &seq_index.bins {
-                stats.bin_occupancy.insert(*bin_id, features.len());
+            for (bin_id, features) in seq_index.iter_bins() {
+                stats.bin_occupancy.insert(bin_id, features.len());
                 stats.total_features += features.len() as u64;
 
                 // Collect size info
@@ -135,9 +206,59 @@ impl BinningStats {
             stats.level_overhead = total_overhead / stats.total_features as f64;
         }
 
+        if stats.linear_index_present {
+            stats.linear_index_pruning = Self::estimate_linear_pruning(index);
+        }
+
         stats
     }
 
+    /// Run base-bin-sized synthetic queries starting at evenly spaced
+    /// features, and compare the candidate features in the visited bins with
+    /// and without the linear index's `min_offset` filter.
+    fn estimate_linear_pruning(index: &BinningIndex) -> Option<f64> {
+        let mut chroms: Vec<&String> = index.sequences.keys().collect();
+        chroms.sort();
+
+        let total_features: usize = index
+            .sequences
+            .values()
+            .flat_map(|seq| seq.iter_bins())
+            .map(|(_, features)| features.len())
+            .sum();
+        let step = total_features.div_ceil(PRUNING_SAMPLE_QUERIES).max(1);
+
+        let mut candidates = 0u64;
+        let mut pruned = 0u64;
+        for chrom in chroms {
+            let seq = &index.sequences[chrom];
+            let Some(linear_index) = &seq.linear_index else {
+                continue;
+            };
+            let bins = seq.binning.as_ref().unwrap_or(&index.bins);
+            let query_width = 1u32 << bins.base_shift;
+
+            let mut starts: Vec<u32> = seq
+                .iter_bins()
+                .flat_map(|(_, features)| features)
+                .map(|f| f.start)
+                .collect();
+            starts.sort_unstable();
+            for &start in starts.iter().step_by(step) {
+                let end = start.saturating_add(query_width).max(start + 1);
+                let min_offset = linear_index.get_min_offset(start).unwrap_or(0);
+                for bin_id in bins.region_to_bins_iter(start, end) {
+                    if let Some(features) = seq.bin(bin_id) {
+                        candidates += features.len() as u64;
+                        pruned += features.iter().filter(|f| f.index < min_offset).count() as u64;
+                    }
+                }
+            }
+        }
+
+        (candidates > 0).then(|| pruned as f64 / candidates as f64)
+    }
+
     fn calculate_level_stats(index: &BinningIndex) -> Vec<LevelStats> {
         let mut level_stats = Vec::new();
         let mut current_offset = 0;
@@ -150,10 +271,10 @@ impl BinningStats {
             for (bin_id, features) in index
                 .sequences
                 .values()
-                .flat_map(|seq| &seq.bins)
-                .filter(|(id, _)| **id >= current_offset && **id < current_offset + level_size)
+                .flat_map(|seq| seq.iter_bins())
+                .filter(|(id, _)| *id >= current_offset && *id < current_offset + level_size)
             {
-                level_bins.insert(*bin_id, features.len());
+                level_bins.insert(bin_id, features.len());
             }
 
             if !level_bins.is_empty() {
@@ -178,8 +299,8 @@ impl BinningStats {
         level_stats
     }
 
-    fn calculate_size_histogram(sizes: &[u32]) -> HashMap<u32, u32> {
-        let mut histogram = HashMap::new();
+    fn calculate_size_histogram(sizes: &[u32]) -> BTreeMap<u32, u32> {
+        let mut histogram = BTreeMap::new();
 
         // Create log-scale buckets
         for &size in sizes {
@@ -221,9 +342,19 @@ impl BinningStats {
             self.feature_overlap
         ));
         report.push_str(&format!(
-            "- Level traversal overhead: {:.2}\n\n",
+            "- Level traversal overhead: {:.2}\n",
             self.level_overhead
         ));
+        match self.linear_index_pruning {
+            Some(pruning) => report.push_str(&format!(
+                "- Linear index pruning: {:.2}% of candidate features skipped\n\n",
+                pruning * 100.0
+            )),
+            None if self.linear_index_present => {
+                report.push_str("- Linear index pruning: n/a (no features)\n\n")
+            }
+            None => report.push_str("- Linear index pruning: n/a (no linear index)\n\n"),
+        }
 
         // Level analysis
 
@@ -341,7 +472,57 @@ impl Default for SizeDistribution {
             max_size: 0,
             mean_size: 0.0,
             median_size: 0.0,
-            size_histogram: HashMap::new(),
+            size_histogram: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinningSchema;
+
+    #[test]
+    fn test_empty_sequences() {
+        let mut index = BinningIndex::new(&BinningSchema::Tabix);
+        let empty = crate::SequenceIndex::new(&index.bins);
+        index.sequences.insert("chr1".to_string(), empty);
+
+        let stats = BinningStats::analyze(&index);
+        assert_eq!(stats.total_features, 0);
+        assert_eq!(stats.bin_density, 0.0);
+        assert_eq!(stats.linear_index_pruning, None);
+        assert!(stats.generate_performance_report().contains("n/a"));
+    }
+
+    #[test]
+    fn test_linear_index_pruning() {
+        let mut index = BinningIndex::new(&BinningSchema::Tabix);
+        // Large early features land in a 1Mb-level bin that queries near the
+        // end of that bin also visit; the linear index rules them out there.
+        let mut offset = 0;
+        for i in 0..100u32 {
+            index
+                .add_feature("chr1", i * 100, i * 100 + 200_000, offset, 10)
+                .unwrap();
+            offset += 10;
+        }
+        for i in 0..100u32 {
+            let start = 900_000 + i * 100;
+            index
+                .add_feature("chr1", start, start + 50, offset, 10)
+                .unwrap();
+            offset += 10;
         }
+
+        let stats = BinningStats::analyze(&index);
+        let pruning = stats.linear_index_pruning.unwrap();
+        assert!(pruning > 0.0 && pruning < 1.0, "pruning = {}", pruning);
+        assert!(stats
+            .generate_performance_report()
+            .contains("Linear index pruning"));
+
+        index.disable_linear_index();
+        assert_eq!(BinningStats::analyze(&index).linear_index_pruning, None);
     }
 }
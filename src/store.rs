@@ -2,22 +2,174 @@
 
 use std::io;
 use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::{BufWriter, Seek, Write},
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
     marker::PhantomData,
     path::{Path, PathBuf},
 };
 
 use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 
-use crate::{error::HgIndexError, index::BinningIndex, BinningSchema};
-use crate::{Record, RecordSlice};
+use crate::{
+    error::HgIndexError,
+    index::{BinningIndex, Feature, HierarchicalBins},
+    region::{natural_chrom_cmp, natural_cmp, touching_window},
+    stats::{BinningStats, RunningStats},
+    BinningSchema, ChromAliases,
+};
+use crate::{Record, RecordSlice, Strand};
+
+/// Counts from `GenomicDataStore::map_overlapping_checked`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MapSummary {
+    /// Records passed to the callback.
+    pub records: usize,
+    /// Corrupt records skipped (only in non-strict mode).
+    pub corrupt: usize,
+}
+
+/// Problems found by `GenomicDataStore::verify`, e.g. after a killed
+/// process truncated a data file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Indexed records checked.
+    pub records: u64,
+    /// Sequences with indexed records but no data file.
+    pub missing_files: Vec<String>,
+    /// Data files whose header is invalid, as `(chrom, reason)`.
+    pub bad_headers: Vec<(String, String)>,
+    /// Indexed records as `(chrom, offset, length)` extending past the end
+    /// of their data file, or whose stored length prefix differs.
+    pub bad_records: Vec<(String, u64, u64)>,
+    /// Bytes of each data file not covered by any indexed record, as
+    /// `(chrom, bytes)`, including data files of unindexed sequences.
+    pub orphaned: Vec<(String, u64)>,
+}
+
+impl VerifyReport {
+    /// True if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.missing_files.is_empty()
+            && self.bad_headers.is_empty()
+            && self.bad_records.is_empty()
+            && self.orphaned.is_empty()
+    }
+}
+
+/// A record paired with the `(start, end)` query interval it overlapped.
+pub type JoinedRecord<T> = ((u32, u32), T);
+
+/// A `(start, index)` position among a sequence's records, for paging with
+/// `GenomicDataStore::features_after`.
+pub type FeatureCursor = (u32, u64);
+
+/// A record's `(start, end)` and its stored payload bytes.
+pub type StoredPayload<'a> = (u32, u32, &'a [u8]);
+
+/// Computes the `Feature::extra` value stored in the index for a record.
+/// Boxed, so it may capture state such as a category lookup table.
+pub type FeatureExtraFn<T> = Box<dyn Fn(&<T as Record>::Slice<'_>) -> u32 + Send>;
+
+// A set `FeatureExtraFn`, which can't derive `Debug`
+struct FeatureExtra<T: Record>(FeatureExtraFn<T>);
+
+impl<T: Record> fmt::Debug for FeatureExtra<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FeatureExtraFn")
+    }
+}
+
+/// The default `GenomicDataStore::set_max_record_len`: far longer than any
+/// line of text, so longer lengths indicate corruption.
+pub const DEFAULT_MAX_RECORD_LEN: u64 = 8 * 1024 * 1024;
+
+// Data file writers kept open at once; past this, the others are closed and
+// reopened for appending if their sequence reappears
+const MAX_OPEN_WRITERS: usize = 256;
+
+// Reject an implausible stored record length as corruption, before slicing
+fn check_record_len(
+    offset: u64,
+    length: u64,
+    max_record_len: Option<u64>,
+) -> Result<(), HgIndexError> {
+    match max_record_len {
+        Some(max) if length > max => Err(HgIndexError::CorruptRecord {
+            offset,
+            reason: format!(
+                "record length {} exceeds the maximum record length {}",
+                length, max
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+// The bytes of the record at `offset`, after its length prefix. An implausible
+// length, or a record extending past the end of the data file, is corruption.
+fn record_bytes(
+    mmap: &[u8],
+    offset: u64,
+    length: u64,
+    max_record_len: Option<u64>,
+) -> Result<&[u8], HgIndexError> {
+    check_record_len(offset, length, max_record_len)?;
+    let start = offset.checked_add(8);
+    let end = start.and_then(|start| start.checked_add(length));
+    start
+        .zip(end)
+        .and_then(|(start, end)| mmap.get(usize::try_from(start).ok()?..usize::try_from(end).ok()?))
+        .ok_or_else(|| HgIndexError::CorruptRecord {
+            offset,
+            reason: "record extends past the end of the data file".into(),
+        })
+}
+
+// Ask the OS to read ahead the pages holding `ranges`, byte ranges of `mmap`,
+// in file order, merging ranges a page or less apart into one request. This is
+// only advice, so failures (and platforms without madvise) are ignored.
+fn prefetch_ranges(mmap: &Mmap, ranges: &mut [(usize, usize)]) {
+    #[cfg(unix)]
+    {
+        const PAGE_SIZE: usize = 4096;
+        let advise = |(start, end): (usize, usize)| {
+            let _ = mmap.advise_range(memmap2::Advice::WillNeed, start, end - start);
+        };
+        ranges.sort_unstable();
+        let mut merged: Option<(usize, usize)> = None;
+        for &(start, end) in ranges.iter() {
+            match merged.as_mut() {
+                Some((_, merged_end)) if start <= *merged_end + PAGE_SIZE => {
+                    *merged_end = (*merged_end).max(end)
+                }
+                _ => merged.replace((start, end)).into_iter().for_each(advise),
+            }
+        }
+        merged.into_iter().for_each(advise);
+    }
+    #[cfg(not(unix))]
+    let _ = (mmap, ranges);
+}
+
+// A data file open for appending records, buffered across `add_record` calls
+#[derive(Debug)]
+struct DataWriter {
+    writer: BufWriter<File>,
+    // File position of the next record, including still-buffered bytes
+    offset: u64,
+}
 
 #[derive(Debug)]
 enum FileHandle {
-    Write(File),
+    Write(DataWriter),
     Read(Mmap),
 }
 
@@ -28,6 +180,23 @@ where
 {
     index: BinningIndex,
     data_files: HashMap<String, FileHandle>,
+    // Data files created by this store, which must never be re-created
+    created_files: HashSet<String>,
+    max_open_files: Option<usize>,
+    // Records per prefetched chunk in `get_overlapping_batch`
+    read_batch_size: Option<usize>,
+    // Queries matching more records than this fail with ResultLimitExceeded
+    max_results: Option<usize>,
+    // Longer stored records are rejected as corrupt
+    max_record_len: Option<u64>,
+    // Computes each added record's `Feature::extra`
+    feature_extra: Option<FeatureExtra<T>>,
+    // Totals added since `create`, reported at `finalize`
+    records_written: u64,
+    bytes_written: u64,
+    // Last access tick of each open read mmap, for LRU eviction
+    last_access: HashMap<String, u64>,
+    access_clock: u64,
     directory: PathBuf,
     key: Option<String>,
     results_buffer: Vec<T>,
@@ -36,14 +205,35 @@ where
 
 impl<T: Record> GenomicDataStore<T> {
     const MAGIC: [u8; 4] = *b"GIDX";
+    // Data files start with the magic, a layout version, and flags (e.g. for
+    // compression), so layout changes are detected rather than misread
+    const DATA_VERSION: u8 = 1;
+    // No flags are defined for this version
+    const DATA_FLAGS: u8 = 0;
+    const HEADER_LEN: usize = Self::MAGIC.len() + 2;
     const INDEX_FILENAME: &'static str = "index.bin";
+    const INDEX_PARTS_DIRNAME: &'static str = "index.parts";
+
+    // The directory holding the data files and index
+    fn data_dir(&self) -> PathBuf {
+        match &self.key {
+            Some(key) => self.directory.join(key),
+            None => self.directory.clone(),
+        }
+    }
 
     fn get_data_path(&self, chrom: &str) -> PathBuf {
-        let mut path = self.directory.clone();
-        if let Some(key) = &self.key {
-            path = path.join(key);
+        self.data_dir().join(format!("{}.bin", chrom))
+    }
+
+    // The canonical name for `chrom` (see `BinningIndex::canonical_chrom`),
+    // borrowing `chrom` rather than the index, so the store stays free to
+    // mutate; only an alias is copied
+    fn resolve_chrom<'c>(&self, chrom: &'c str) -> Cow<'c, str> {
+        match self.index.canonical_chrom(chrom) {
+            Cow::Borrowed(canonical) if canonical == chrom => Cow::Borrowed(chrom),
+            canonical => Cow::Owned(canonical.into_owned()),
         }
-        path.join(format!("{}.bin", chrom))
     }
 
     pub fn create(directory: &Path, key: Option<String>) -> io::Result<Self> {
@@ -62,6 +252,16 @@ impl<T: Record> GenomicDataStore<T> {
         Ok(Self {
             index: BinningIndex::new(schema),
             data_files: HashMap::new(),
+            created_files: HashSet::new(),
+            max_open_files: None,
+            read_batch_size: None,
+            max_results: None,
+            max_record_len: Some(DEFAULT_MAX_RECORD_LEN),
+            feature_extra: None,
+            records_written: 0,
+            bytes_written: 0,
+            last_access: HashMap::new(),
+            access_clock: 0,
             directory: directory.to_path_buf(),
             key,
             results_buffer: Vec::with_capacity(1000),
@@ -69,58 +269,165 @@ impl<T: Record> GenomicDataStore<T> {
         })
     }
 
-    fn get_or_create_file(&mut self, chrom: &str) -> std::io::Result<&mut File> {
+    /// Create a store whose index is spilled to disk one sequence at a time.
+    ///
+    /// Records must be added grouped by sequence (as in a sorted BED file).
+    /// Each sequence's index is written out when the next sequence begins, and
+    /// the parts are reassembled into the index file at `finalize`, so peak
+    /// index memory is that of the largest single sequence.
+    pub fn create_streaming(
+        directory: &Path,
+        key: Option<String>,
+        schema: &BinningSchema,
+    ) -> io::Result<Self> {
+        let mut store = Self::create_with_schema(directory, key, schema)?;
+        let mut parts_dir = store.directory.clone();
+        if let Some(key) = &store.key {
+            parts_dir = parts_dir.join(key);
+        }
+        store
+            .index
+            .enable_spilling(&parts_dir.join(Self::INDEX_PARTS_DIRNAME))?;
+        Ok(store)
+    }
+
+    fn get_or_create_file(&mut self, chrom: &str) -> Result<&mut DataWriter, HgIndexError> {
         if !self.data_files.contains_key(chrom) {
             let data_path = self.get_data_path(chrom);
-            let file = File::create(&data_path)?;
-            let mut writer = BufWriter::new(file);
-            writer.write_all(&Self::MAGIC)?;
-            writer.flush()?;
-            let file = writer.into_inner()?;
+            let data_writer = if self.created_files.contains(chrom) {
+                // File::create truncates, which would silently drop records
+                // the index still references, so reopen for appending
+                let mut file = OpenOptions::new().append(true).open(&data_path)?;
+                let offset = file.seek(SeekFrom::End(0))?;
+                DataWriter {
+                    writer: BufWriter::new(file),
+                    offset,
+                }
+            } else {
+                let mut writer = BufWriter::new(File::create(&data_path)?);
+                writer.write_all(&Self::MAGIC)?;
+                writer.write_all(&[Self::DATA_VERSION, Self::DATA_FLAGS])?;
+                self.created_files.insert(chrom.to_string());
+                DataWriter {
+                    writer,
+                    offset: Self::HEADER_LEN as u64,
+                }
+            };
             self.data_files
-                .insert(chrom.to_string(), FileHandle::Write(file));
+                .insert(chrom.to_string(), FileHandle::Write(data_writer));
         }
 
         match self.data_files.get_mut(chrom).unwrap() {
-            FileHandle::Write(file) => Ok(file),
-            FileHandle::Read(_) => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "File is open for reading",
-            )),
+            FileHandle::Write(data_writer) => Ok(data_writer),
+            FileHandle::Read(_) => Err(io::Error::other("File is open for reading").into()),
         }
     }
 
+    /// Append a record to its sequence's data file and index it.
+    ///
+    /// Records of different sequences may be interleaved, but each sequence's
+    /// records must be added in sorted order. Stores created with
+    /// `create_streaming` are the exception: they require records grouped by
+    /// sequence, and reject a sequence that reappears after another has begun
+    /// with `UngroupedSequence`.
     pub fn add_record(&mut self, chrom: &str, record: &T) -> Result<(), HgIndexError> {
+        let payload = record.to_bytes();
+        let extra = self
+            .feature_extra
+            .as_ref()
+            .map_or(0, |extra| (extra.0)(&T::Slice::from_bytes(&payload)));
+        self.write_record(chrom, record.start(), record.end(), extra, &payload)
+    }
+
+    /// Append an already-serialized record payload (as produced by
+    /// `Record::to_bytes`), e.g. one read from another store, without
+    /// parsing it into an owned record.
+    pub fn add_record_bytes(&mut self, chrom: &str, payload: &[u8]) -> Result<(), HgIndexError> {
+        let (start, end, extra) = {
+            let record = T::Slice::try_from_bytes(payload)?;
+            let extra = self
+                .feature_extra
+                .as_ref()
+                .map_or(0, |extra| (extra.0)(&record));
+            (record.start(), record.end(), extra)
+        };
+        self.write_record(chrom, start, end, extra, payload)
+    }
+
+    // Append a record's payload to its sequence's data file and index it. The
+    // index validates the record first, so a rejected record writes nothing.
+    fn write_record(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        extra: u32,
+        payload: &[u8],
+    ) -> Result<(), HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+        self.index.check_feature(chrom, start, end)?;
+
         if !self.data_files.contains_key(chrom) {
-            self.data_files.retain(|k, _| k == chrom);
+            let grouped = self.index.is_spilling();
+            if grouped && self.created_files.contains(chrom) {
+                return Err(HgIndexError::UngroupedSequence(chrom.to_string()));
+            }
+            let writers = self
+                .data_files
+                .values()
+                .filter(|handle| matches!(handle, FileHandle::Write(_)))
+                .count();
+            if grouped || writers >= MAX_OPEN_WRITERS {
+                // Close the other sequences' writers; read mmaps stay open
+                self.close_writers()?;
+            }
+            if !self.created_files.contains(chrom) {
+                trace_event!(info, chrom, "sequence started");
+            }
         }
 
-        let file = self.get_or_create_file(chrom)?;
+        let data_writer = self.get_or_create_file(chrom)?;
+        let offset = data_writer.offset;
+        let length = payload.len() as u64;
+        data_writer.writer.write_all(&length.to_le_bytes())?;
+        data_writer.writer.write_all(payload)?;
+        data_writer.offset += std::mem::size_of::<u64>() as u64 + length;
 
-        let length;
-        let offset = {
-            let mut writer = BufWriter::new(file);
-            let offset = writer.stream_position()?;
-
-            // Use Record trait instead of bincode
-            let record_data = record.to_bytes();
-            length = record_data.len() as u64;
+        self.index
+            .add_feature_with_extra(chrom, start, end, offset, length, extra)?;
+        self.records_written += 1;
+        self.bytes_written += std::mem::size_of::<u64>() as u64 + length;
+        Ok(())
+    }
 
-            writer.write_all(&length.to_le_bytes())?;
-            writer.write_all(&record_data)?;
-            writer.flush()?;
+    /// Number of records added since the store was created.
+    pub fn records_written(&self) -> u64 {
+        self.records_written
+    }
 
-            offset
-        };
+    /// Bytes of record data (length prefixes and payloads) written to data
+    /// files since the store was created.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
 
-        self.index
-            .add_feature(chrom, record.start(), record.end(), offset, length)?;
+    // Flush and close all data file writers, keeping read mmaps open
+    fn close_writers(&mut self) -> io::Result<()> {
+        for handle in self.data_files.values_mut() {
+            if let FileHandle::Write(data_writer) = handle {
+                data_writer.writer.flush()?;
+            }
+        }
+        self.data_files
+            .retain(|_, handle| matches!(handle, FileHandle::Read(_)));
         Ok(())
     }
 
     // Add a method to explicitly close files
     fn close_files(&mut self) -> io::Result<()> {
+        self.close_writers()?;
         self.data_files.clear();
+        self.last_access.clear();
         Ok(())
     }
 
@@ -128,16 +435,460 @@ impl<T: Record> GenomicDataStore<T> {
         self.close_files()?;
 
         // Write index to file
-        let index_path = if let Some(ref key) = self.key {
-            self.directory.join(key).join(Self::INDEX_FILENAME)
-        } else {
-            self.directory.join(Self::INDEX_FILENAME)
-        };
+        let index_path = self.index_path();
 
         self.index.finalize(index_path.as_path())?;
+        trace_event!(
+            info,
+            records = self.records_written,
+            bytes = self.bytes_written,
+            "store finalized"
+        );
         Ok(())
     }
 
+    /// Rebuild the index of the store at `directory` under `schema` by
+    /// scanning its data files, and rewrite its index file, leaving the data
+    /// files untouched; cheaper than re-packing, and possible without the
+    /// original input. Records must still be sorted, and keep their
+    /// `Feature::extra` values. Returns the number of records indexed.
+    pub fn reindex(
+        directory: &Path,
+        key: Option<String>,
+        schema: &BinningSchema,
+    ) -> Result<u64, HgIndexError> {
+        let mut store = Self::open(directory, key)?;
+        let mut index = store.index.empty_like(schema);
+        let mut count = 0;
+
+        for chrom in store.sequence_names() {
+            // Carry over each record's extra value, by its data file offset
+            let extras: HashMap<u64, u32> = store.index.sequences[&chrom]
+                .iter_bins()
+                .flat_map(|(_, features)| features)
+                .map(|feature| (feature.index, feature.extra))
+                .collect();
+            store.open_chrom_file(&chrom)?;
+            let Some(FileHandle::Read(mmap)) = store.data_files.get(&chrom) else {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            };
+
+            Self::scan_records(
+                mmap,
+                store.max_record_len,
+                false,
+                |offset, length, record| {
+                    let extra = extras.get(&offset).copied().unwrap_or(0);
+                    index.add_feature_with_extra(
+                        &chrom,
+                        record.start(),
+                        record.end(),
+                        offset,
+                        length,
+                        extra,
+                    )?;
+                    count += 1;
+                    Ok(())
+                },
+            )?;
+        }
+
+        // Replace the index file only once the new one is complete
+        let index_path = store.index_path();
+        let tmp_path = index_path.with_extension("bin.tmp");
+        index.finalize(&tmp_path)?;
+        fs::rename(&tmp_path, &index_path)?;
+        Ok(count)
+    }
+
+    /// Recover a store whose index was never written, e.g. after `pack`
+    /// crashed, by scanning the data files in `directory` and writing a
+    /// fresh index under `schema`. A final record cut short by the crash is
+    /// dropped and its data file truncated to the last whole record. Records
+    /// must be sorted within each sequence, as when they were added.
+    ///
+    /// Only what the data files hold is recovered: metadata, aliases, and
+    /// `Feature::extra` values are lost. Returns the number of records
+    /// indexed.
+    pub fn recover(
+        directory: &Path,
+        key: Option<String>,
+        schema: &BinningSchema,
+    ) -> Result<u64, HgIndexError> {
+        let target_dir = match &key {
+            Some(key) => directory.join(key),
+            None => directory.to_path_buf(),
+        };
+
+        let mut data_paths = Vec::new();
+        for entry in fs::read_dir(&target_dir)? {
+            let path = entry?.path();
+            let is_data = path.extension().is_some_and(|ext| ext == "bin")
+                && path
+                    .file_name()
+                    .is_some_and(|name| name != Self::INDEX_FILENAME);
+            if is_data {
+                data_paths.push(path);
+            }
+        }
+        data_paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+        let mut index = BinningIndex::new(schema);
+        let mut count = 0;
+        for path in data_paths {
+            let chrom = path
+                .file_stem()
+                .expect("data files have a name")
+                .to_string_lossy()
+                .into_owned();
+            let file = File::options().read(true).write(true).open(&path)?;
+            // A crash can leave a file too short to hold even the header
+            if file.metadata()?.len() < Self::HEADER_LEN as u64 {
+                continue;
+            }
+            let mmap = unsafe { Mmap::map(&file)? };
+            Self::check_header(&mmap).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+            let end = Self::scan_records(
+                &mmap,
+                Some(DEFAULT_MAX_RECORD_LEN),
+                true,
+                |offset, length, record| {
+                    index.add_feature(&chrom, record.start(), record.end(), offset, length)?;
+                    count += 1;
+                    Ok(())
+                },
+            )?;
+            let truncated = end < mmap.len() as u64;
+            drop(mmap);
+            if truncated {
+                file.set_len(end)?;
+            }
+        }
+
+        let index_path = target_dir.join(Self::INDEX_FILENAME);
+        let tmp_path = index_path.with_extension("bin.tmp");
+        index.finalize(&tmp_path)?;
+        fs::rename(&tmp_path, &index_path)?;
+        Ok(count)
+    }
+
+    // Walk the records of a data file, which follow the magic as (u64
+    // length, payload) pairs, passing each one's offset, length, and parsed
+    // record to `fun`. With `truncated_tail`, a final record cut short ends
+    // the scan rather than failing it. Returns the end of the last record.
+    fn scan_records<'a, F>(
+        data: &'a [u8],
+        max_record_len: Option<u64>,
+        truncated_tail: bool,
+        mut fun: F,
+    ) -> Result<u64, HgIndexError>
+    where
+        F: FnMut(u64, u64, T::Slice<'a>) -> Result<(), HgIndexError>,
+    {
+        let mut offset = Self::HEADER_LEN as u64;
+        while (offset as usize) < data.len() {
+            let corrupt = |reason: &str| HgIndexError::CorruptRecord {
+                offset,
+                reason: reason.to_string(),
+            };
+            let Some(prefix) = data.get(offset as usize..offset as usize + 8) else {
+                if truncated_tail {
+                    break;
+                }
+                return Err(corrupt("truncated record length"));
+            };
+            let length = u64::from_le_bytes(prefix.try_into().unwrap());
+            check_record_len(offset, length, max_record_len)?;
+            let Some(payload) = data.get(offset as usize + 8..(offset + 8 + length) as usize)
+            else {
+                if truncated_tail {
+                    break;
+                }
+                return Err(corrupt("record extends past the end of the data file"));
+            };
+            let record = T::Slice::try_from_bytes(payload).map_err(|e| corrupt(&e.to_string()))?;
+            fun(offset, length, record)?;
+            offset += 8 + length;
+        }
+        Ok(offset)
+    }
+
+    /// Check every indexed record against its data file: that the file
+    /// exists with a valid header, and that the record lies within it with
+    /// the length the index expects. Also reports data the index doesn't
+    /// cover, so an empty query result can be told apart from a damaged
+    /// store. Problems are collected into the report rather than returned as
+    /// errors; only I/O failures are.
+    pub fn verify(&self) -> Result<VerifyReport, HgIndexError> {
+        let mut report = VerifyReport::default();
+
+        for chrom in self.sequence_names() {
+            let mut features: Vec<(u64, u64)> = self.index.sequences[&chrom]
+                .iter_bins()
+                .flat_map(|(_, features)| features)
+                .map(|feature| (feature.index, feature.length))
+                .collect();
+            features.sort_unstable();
+            report.records += features.len() as u64;
+
+            let file = match File::open(self.get_data_path(&chrom)) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    report.missing_files.push(chrom);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let mmap = unsafe { Mmap::map(&file)? };
+            if let Err(e) = Self::check_header(&mmap) {
+                report.bad_headers.push((chrom, e.to_string()));
+                continue;
+            }
+
+            let mut covered = Self::HEADER_LEN as u64;
+            for (offset, length) in features {
+                let prefix = mmap
+                    .get(offset as usize..(offset as usize).saturating_add(8))
+                    .map(|prefix| u64::from_le_bytes(prefix.try_into().unwrap()));
+                let in_bounds = offset
+                    .checked_add(8)
+                    .and_then(|offset| offset.checked_add(length))
+                    .is_some_and(|end| end <= mmap.len() as u64);
+                if !in_bounds || prefix != Some(length) {
+                    report.bad_records.push((chrom.clone(), offset, length));
+                    continue;
+                }
+                covered += 8 + length;
+            }
+            let orphaned = (mmap.len() as u64).saturating_sub(covered);
+            if orphaned > 0 {
+                report.orphaned.push((chrom, orphaned));
+            }
+        }
+
+        // Data files of sequences the index doesn't know
+        for entry in fs::read_dir(self.data_dir())? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "bin")
+                || path
+                    .file_name()
+                    .is_some_and(|name| name == Self::INDEX_FILENAME)
+            {
+                continue;
+            }
+            let chrom = path
+                .file_stem()
+                .expect("data files have a name")
+                .to_string_lossy()
+                .into_owned();
+            if !self.index.has_features(&chrom) {
+                report.orphaned.push((chrom, fs::metadata(&path)?.len()));
+            }
+        }
+        report
+            .orphaned
+            .sort_by(|a, b| natural_chrom_cmp(&a.0, &b.0));
+        Ok(report)
+    }
+
+    /// Remove `chrom` from the store, e.g. to add it again with regenerated
+    /// records: its data file is deleted and its records dropped from the
+    /// index. Call `finalize` to persist the updated index. Returns false if
+    /// the store has no such sequence.
+    pub fn remove_chromosome(&mut self, chrom: &str) -> Result<bool, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if !self.index.remove_sequence(chrom) {
+            return Ok(false);
+        }
+        self.data_files.remove(chrom);
+        self.last_access.remove(chrom);
+        self.created_files.remove(chrom);
+        match fs::remove_file(self.get_data_path(chrom)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(true),
+        }
+    }
+
+    /// Rewrite each data file to hold only the records its index refers to,
+    /// in their original order, reclaiming the space of any others (e.g. a
+    /// tail left by a crashed write), then rebuild the index with the new
+    /// offsets and write it. Records must have been added in sorted order.
+    /// Returns the number of bytes reclaimed.
+    ///
+    /// All rewritten files are written alongside the originals first, so an
+    /// error (e.g. a corrupt record) leaves the store as it was.
+    pub fn compact(&mut self) -> Result<u64, HgIndexError> {
+        self.close_files()?;
+        let schema = self.schema().clone();
+        let mut index = self.index.empty_like(&schema);
+        let index_path = self.index_path();
+        let mut renames = Vec::new();
+        let mut reclaimed = 0;
+
+        let mut write_all = || -> Result<(), HgIndexError> {
+            for chrom in self.sequence_names() {
+                let path = self.get_data_path(&chrom);
+                let tmp_path = path.with_extension("bin.tmp");
+                renames.push((tmp_path.clone(), path.clone()));
+                reclaimed += self.write_compacted(&chrom, &path, &tmp_path, &mut index)?;
+            }
+            let tmp_path = index_path.with_extension("bin.tmp");
+            renames.push((tmp_path.clone(), index_path.clone()));
+            index.finalize(&tmp_path)?;
+            Ok(())
+        };
+        if let Err(error) = write_all() {
+            for (tmp_path, _) in &renames {
+                let _ = fs::remove_file(tmp_path);
+            }
+            return Err(error);
+        }
+
+        // Replace the index file only once the data files are replaced
+        for (tmp_path, path) in renames {
+            fs::rename(&tmp_path, &path)?;
+        }
+        self.index = index;
+        Ok(reclaimed)
+    }
+
+    // Write the records of `chrom` the index refers to, in their original
+    // order, from `path` to `tmp_path`, adding them with their new offsets to
+    // `index`. Returns the number of bytes left out.
+    fn write_compacted(
+        &self,
+        chrom: &str,
+        path: &Path,
+        tmp_path: &Path,
+        index: &mut BinningIndex,
+    ) -> Result<u64, HgIndexError> {
+        let mut features: Vec<Feature> = self.index.sequences[chrom]
+            .iter_bins()
+            .flat_map(|(_, features)| features)
+            .cloned()
+            .collect();
+        features.sort_unstable_by_key(|feature| feature.index);
+
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::check_header(&mmap).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let mut writer = BufWriter::new(File::create(tmp_path)?);
+        writer.write_all(&mmap[..Self::HEADER_LEN])?;
+        let mut offset = Self::HEADER_LEN as u64;
+        for feature in features {
+            // Copied with its stored length prefix, once known to be in bounds
+            let payload = record_bytes(&mmap, feature.index, feature.length, None)?;
+            let start = feature.index as usize;
+            writer.write_all(&mmap[start..start + 8 + payload.len()])?;
+            index.add_feature_with_extra(
+                chrom,
+                feature.start,
+                feature.end,
+                offset,
+                feature.length,
+                feature.extra,
+            )?;
+            offset += 8 + feature.length;
+        }
+        writer.flush()?;
+        // Features sharing a record write it more than once
+        Ok((mmap.len() as u64).saturating_sub(offset))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        match &self.key {
+            Some(key) => self.directory.join(key).join(Self::INDEX_FILENAME),
+            None => self.directory.join(Self::INDEX_FILENAME),
+        }
+    }
+
+    /// Record sequence lengths, so that short sequences (e.g. unplaced and alt
+    /// contigs) get a compact single-bin index. Must be called before records
+    /// on those sequences are added.
+    pub fn set_sequence_lengths<I>(&mut self, lengths: I)
+    where
+        I: IntoIterator<Item = (String, u32)>,
+    {
+        self.index.set_sequence_lengths(lengths);
+    }
+
+    /// Skip validating record sort order for trusted, pre-sorted input. See
+    /// `BinningIndex::set_unchecked_insertion`: unsorted input then yields a
+    /// broken index.
+    pub fn set_unchecked_insertion(&mut self, unchecked: bool) {
+        self.index.set_unchecked_insertion(unchecked);
+    }
+
+    /// Build the index without a linear index, regardless of the schema. Must
+    /// be called before records are added.
+    pub fn disable_linear_index(&mut self) {
+        self.index.disable_linear_index();
+    }
+
+    /// Add chromosome aliases, so records and queries may use any alias of a
+    /// sequence name. Aliases added before `finalize` are stored in the index.
+    pub fn add_chrom_aliases(&mut self, aliases: ChromAliases) {
+        self.index.add_chrom_aliases(aliases);
+    }
+
+    pub fn chrom_aliases(&self) -> &ChromAliases {
+        self.index.chrom_aliases()
+    }
+
+    /// Store a small value computed from each added record in its index
+    /// entry (`Feature::extra`), e.g. a category ID or strand bit, so
+    /// `features_overlapping` can filter on it without reading data files.
+    /// Set this before adding records.
+    pub fn set_feature_extra(&mut self, extra_fn: Option<FeatureExtraFn<T>>) {
+        self.feature_extra = extra_fn.map(FeatureExtra);
+    }
+
+    /// Return the index entries of features overlapping `chrom:start-end`,
+    /// including each one's `extra` value, without touching the data files.
+    pub fn features_overlapping(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<&Feature>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+        let features = self.index.overlapping_features(chrom, start, end);
+        self.check_result_limit(features.len())?;
+        Ok(features)
+    }
+
+    /// Match sequence names case-insensitively (e.g. `Chr1` finds records
+    /// added as `chr1`) by lowercasing them when adding records and
+    /// querying. Set this before adding records; the choice is stored in the
+    /// index. Off by default.
+    pub fn set_lowercase_chroms(&mut self, lowercase: bool) {
+        self.index.set_lowercase_chroms(lowercase);
+    }
+
+    /// Statistics maintained as records were added to the index.
+    pub fn running_stats(&self) -> &RunningStats {
+        self.index.running_stats()
+    }
+
+    /// The binning schema the store was indexed with.
+    pub fn schema(&self) -> &BinningSchema {
+        &self.index.bins.schema
+    }
+
+    /// The binning parameters (shifts, levels, linear index) the store was
+    /// indexed with, e.g. to check compatibility before cross-store
+    /// operations.
+    pub fn hierarchical_bins(&self) -> &HierarchicalBins {
+        &self.index.bins
+    }
+
     // Get metadata if it exists
     pub fn metadata<M: for<'de> Deserialize<'de>>(&self) -> Option<M> {
         self.index.metadata()
@@ -153,14 +904,16 @@ impl<T: Record> GenomicDataStore<T> {
         self.close_files()?;
 
         // Write index to file
-        let index_path = if let Some(ref key) = self.key {
-            self.directory.join(key).join(Self::INDEX_FILENAME)
-        } else {
-            self.directory.join(Self::INDEX_FILENAME)
-        };
+        let index_path = self.index_path();
 
         self.index
             .finalize_with_metadata(index_path.as_path(), &metadata)?;
+        trace_event!(
+            info,
+            records = self.records_written,
+            bytes = self.bytes_written,
+            "store finalized"
+        );
         Ok(())
     }
 
@@ -182,6 +935,16 @@ impl<T: Record> GenomicDataStore<T> {
         Ok(Self {
             index,
             data_files: HashMap::new(),
+            created_files: HashSet::new(),
+            max_open_files: None,
+            read_batch_size: None,
+            max_results: None,
+            max_record_len: Some(DEFAULT_MAX_RECORD_LEN),
+            feature_extra: None,
+            records_written: 0,
+            bytes_written: 0,
+            last_access: HashMap::new(),
+            access_clock: 0,
             directory: directory.to_path_buf(),
             key,
             results_buffer: Vec::with_capacity(1000),
@@ -189,126 +952,414 @@ impl<T: Record> GenomicDataStore<T> {
         })
     }
 
-    // NOTE: currently this is not faster than the version below, but
-    // it maybe in some cases — needs future benchmarking.
-    // pub fn open_chrom_file(&mut self, chrom: &str) -> std::io::Result<()> {
-    //     if !self.data_files.contains_key(chrom) {
-    //         let data_path = self.get_data_path(chrom);
-    //         let mmap = unsafe {
-    //             // Add MAP_POPULATE to preload pages
-    //             let file = File::open(&data_path)?;
-    //             let mut options = memmap2::MmapOptions::new();
-    //             let mmap_opts = options.populate();
-    //             mmap_opts.map(&file)?
-    //         };
-    //
-    //         if mmap[0..4] != Self::MAGIC {
-    //             return Err(std::io::Error::new(
-    //                 std::io::ErrorKind::InvalidData,
-    //                 "Invalid file format",
-    //             ));
-    //         }
-    //         self.data_files
-    //             .insert(chrom.to_string(), FileHandle::Read(mmap));
-    //     }
-    //     Ok(())
-    // }
+    /// Open a finalized store to add more records, then `finalize` it again.
+    /// Each sequence's data file is reopened for appending after its stored
+    /// records when a record is next added to it. Records must still be added
+    /// in sorted order within each sequence, starting at or after the last
+    /// stored start.
+    pub fn open_append(
+        directory: &Path,
+        key: Option<String>,
+    ) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let mut store = Self::open(directory, key)?;
+        // Sequences without features have no data file to append to
+        let stored: HashSet<String> = store
+            .index
+            .sequences
+            .keys()
+            .filter(|chrom| store.index.has_features(chrom))
+            .cloned()
+            .collect();
+        for chrom in &stored {
+            let path = store.get_data_path(chrom);
+            // Mapped rather than reading just the header, so a legacy layout
+            // can be told apart from an unsupported version
+            let mmap = unsafe { Mmap::map(&File::open(&path)?)? };
+            Self::check_header(&mmap).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        // Existing data files must be appended to, never re-created
+        store.created_files = stored;
+        Ok(store)
+    }
 
-    pub fn open_chrom_file(&mut self, chrom: &str) -> std::io::Result<()> {
-        if !self.data_files.contains_key(chrom) {
-            let data_path = self.get_data_path(chrom);
-            let file = File::open(&data_path)?;
-            let mmap = unsafe { Mmap::map(&file)? };
+    /// Read records in chunks of `batch_size` in `get_overlapping_batch`:
+    /// each chunk's records are validated, then their pages are prefetched
+    /// in file order (with `madvise`, on unix) before they're parsed. The
+    /// best size depends on record size, storage and CPU cache, so this
+    /// needs benchmarking per workload (see `benches/bench.rs`); `None` (the
+    /// default) parses records in a single pass without prefetching.
+    pub fn set_read_batch_size(&mut self, batch_size: Option<usize>) {
+        self.read_batch_size = batch_size.map(|size| size.max(1));
+    }
 
-            if mmap[0..4] != Self::MAGIC {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Invalid file format",
-                ));
-            }
-            self.data_files
-                .insert(chrom.to_string(), FileHandle::Read(mmap));
-        }
-        Ok(())
+    /// Cap the number of records a single query may match: queries over
+    /// more fail with `ResultLimitExceeded` before any records are read,
+    /// so one query over a dense region can't exhaust memory. Applies to
+    /// the record-reading queries and `features_overlapping`; for
+    /// `get_overlapping_blocks`, to the records overlapping the blocks' span.
+    /// `None` (the default) is unlimited.
+    pub fn set_max_results(&mut self, max_results: Option<usize>) {
+        self.max_results = max_results;
     }
 
-    // Rename to just map_overlapping since there's no batching
-    pub fn map_overlapping<F>(
-        &mut self,
+    /// Match zero-width records (e.g. insertion points) touching either edge
+    /// of a query window, rather than only those strictly inside it; see
+    /// [`BinningIndex::set_zero_width_inclusive`]. Off by default.
+    pub fn set_zero_width_inclusive(&mut self, inclusive: bool) {
+        self.index.set_zero_width_inclusive(inclusive);
+    }
+
+    // Offsets of the records overlapping `chrom:start-end`, enforcing
+    // `max_results`: one record past the limit is enough to reject the query,
+    // so no more are collected
+    fn overlapping_offsets(
+        &self,
         chrom: &str,
         start: u32,
         end: u32,
-        mut fun: F,
-    ) -> Result<usize, HgIndexError>
-    where
-        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
-    {
-        if end <= start {
-            return Err(HgIndexError::InvalidInterval { start, end });
-        }
+    ) -> Result<Vec<(u64, u64)>, HgIndexError> {
+        let max = self
+            .max_results
+            .map_or(usize::MAX, |limit| limit.saturating_add(1));
+        let offsets = self.index.find_overlapping_at_most(chrom, start, end, max);
+        self.check_result_limit(offsets.len())?;
+        Ok(offsets)
+    }
 
-        if !self.index.sequences.contains_key(chrom) {
-            return Ok(0);
+    fn check_result_limit(&self, count: usize) -> Result<(), HgIndexError> {
+        match self.max_results {
+            Some(limit) if count > limit => Err(HgIndexError::ResultLimitExceeded { limit }),
+            _ => Ok(()),
         }
+    }
 
-        if self.open_chrom_file(chrom).is_err() {
-            return Ok(0);
-        }
+    /// Reject records whose stored length exceeds `max_record_len` bytes as
+    /// `CorruptRecord`, rather than reading an implausibly large slice (e.g.
+    /// from a bit-flipped length that still lies within the data file).
+    /// Defaults to [`DEFAULT_MAX_RECORD_LEN`]; raise it for stores of large
+    /// records, or pass `None` to trust every length.
+    pub fn set_max_record_len(&mut self, max_record_len: Option<u64>) {
+        self.max_record_len = max_record_len;
+    }
 
-        let mmap = match self.data_files.get(chrom).unwrap() {
-            FileHandle::Read(mmap) => mmap,
-            FileHandle::Write(_) => {
-                return Err(HgIndexError::StringError("File is open for writing".into()));
+    /// Bound the number of data files mapped at once. When a query needs a new
+    /// file and the limit is reached, the least-recently-used mmap is closed.
+    /// `None` (the default) keeps every file open once accessed.
+    pub fn set_max_open_files(&mut self, max_open_files: Option<usize>) {
+        self.max_open_files = max_open_files.map(|max| max.max(1));
+        // Track files opened while there was no limit
+        for (chrom, handle) in &self.data_files {
+            if matches!(handle, FileHandle::Read(_)) {
+                self.last_access.entry(chrom.clone()).or_insert(0);
             }
-        };
+        }
+        self.evict_read_files(0);
+    }
 
-        let offsets = self.index.find_overlapping(chrom, start, end);
-        if offsets.is_empty() {
-            return Ok(0);
+    /// Number of data files currently mapped for reading.
+    pub fn open_file_count(&self) -> usize {
+        self.data_files
+            .values()
+            .filter(|handle| matches!(handle, FileHandle::Read(_)))
+            .count()
+    }
+
+    // Close least-recently-used read mmaps until `reserve` more fit under the limit
+    fn evict_read_files(&mut self, reserve: usize) {
+        let Some(max) = self.max_open_files else {
+            return;
+        };
+        while self.open_file_count() + reserve > max {
+            let Some(oldest) = self
+                .last_access
+                .iter()
+                .min_by_key(|(_, &tick)| tick)
+                .map(|(chrom, _)| chrom.clone())
+            else {
+                break;
+            };
+            self.last_access.remove(&oldest);
+            self.data_files.remove(&oldest);
         }
+    }
 
-        let mut count = 0;
-        for (offset, length) in offsets {
-            let offset = offset as usize;
-            let length = length as usize;
+    pub fn open_chrom_file(&mut self, chrom: &str) -> std::io::Result<()> {
+        self.open_chrom_file_with(chrom, false)
+    }
+
+    // Open a sequence's data file for a query, returning whether it exists.
+    // A missing file just means no records, but an unreadable one (e.g. an
+    // unsupported header) is an error rather than an empty result.
+    fn open_chrom_file_for_query(&mut self, chrom: &str) -> io::Result<bool> {
+        match self.open_chrom_file(chrom) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
 
-            if offset + 8 > mmap.len() {
+    /// Open and prefault the data files of `chroms`, so the first queries on
+    /// them don't pay page-fault costs (e.g. at server startup). Files already
+    /// opened lazily are remapped. Unknown and empty sequences are skipped.
+    pub fn prewarm(&mut self, chroms: &[&str]) -> std::io::Result<()> {
+        for &chrom in chroms {
+            let chrom = &*self.resolve_chrom(chrom);
+            if !self.index.has_features(chrom) {
                 continue;
             }
+            if matches!(self.data_files.get(chrom), Some(FileHandle::Read(_))) {
+                self.data_files.remove(chrom);
+                self.last_access.remove(chrom);
+            }
+            self.open_chrom_file_with(chrom, true)?;
+        }
+        Ok(())
+    }
 
-            if offset + 8 + length > mmap.len() {
-                continue;
+    // Open a data file lazily, or with MAP_POPULATE to preload its pages
+    fn open_chrom_file_with(&mut self, chrom: &str, populate: bool) -> std::io::Result<()> {
+        if self.max_open_files.is_some() {
+            self.access_clock += 1;
+            if let Some(tick) = self.last_access.get_mut(chrom) {
+                *tick = self.access_clock;
             }
+        }
+        if !self.data_files.contains_key(chrom) {
+            self.evict_read_files(1);
+            let data_path = self.get_data_path(chrom);
+            let file = File::open(&data_path)?;
+            let mmap = unsafe {
+                let mut options = memmap2::MmapOptions::new();
+                if populate {
+                    options.populate();
+                }
+                options.map(&file)?
+            };
 
-            // Use RecordSlice for zero-copy parsing
-            let record = T::Slice::from_bytes(&mmap[offset + 8..offset + 8 + length]);
-            fun(record)?;
-            count += 1;
+            Self::check_header(&mmap)?;
+            self.data_files
+                .insert(chrom.to_string(), FileHandle::Read(mmap));
+            if self.max_open_files.is_some() {
+                self.last_access
+                    .insert(chrom.to_string(), self.access_clock);
+            }
+        }
+        Ok(())
+    }
+
+    // Validate a data file's header: its magic, and a version and flags this
+    // build can read
+    fn check_header(data: &[u8]) -> io::Result<()> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        match data.get(..Self::HEADER_LEN) {
+            Some(header) if header[..4] == Self::MAGIC => {
+                let (version, flags) = (header[4], header[5]);
+                if version != Self::DATA_VERSION && Self::is_legacy_layout(data) {
+                    return Err(invalid(
+                        "Unversioned data file from before data file headers; repack the store"
+                            .to_string(),
+                    ));
+                }
+                if version != Self::DATA_VERSION {
+                    return Err(invalid(format!(
+                        "Unsupported data file version {} (expected {}); repack the store",
+                        version,
+                        Self::DATA_VERSION
+                    )));
+                }
+                if flags & !Self::DATA_FLAGS != 0 {
+                    return Err(invalid(format!(
+                        "Unsupported data file flags {:#04x}",
+                        flags
+                    )));
+                }
+                Ok(())
+            }
+            _ => Err(invalid("Invalid file format".to_string())),
         }
+    }
 
-        Ok(count)
+    // Whether `data` has the layout written before data files had a version:
+    // the bare magic, followed directly by a length-prefixed first record that
+    // fits in the file
+    fn is_legacy_layout(data: &[u8]) -> bool {
+        let Some(prefix) = data.get(Self::MAGIC.len()..Self::MAGIC.len() + 8) else {
+            return false;
+        };
+        let length = u64::from_le_bytes(prefix.try_into().unwrap());
+        data[..Self::MAGIC.len()] == Self::MAGIC
+            && length > 0
+            && length <= (data.len() - Self::MAGIC.len() - 8) as u64
     }
 
-    pub fn get_overlapping(
+    // Rename to just map_overlapping since there's no batching. Corrupt records
+    // are skipped; use `map_overlapping_checked` to fail on them instead.
+    pub fn map_overlapping<F>(
         &mut self,
         chrom: &str,
         start: u32,
         end: u32,
-    ) -> Result<&[T], HgIndexError> {
-        self.results_buffer.clear();
+        fun: F,
+    ) -> Result<usize, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        self.map_overlapping_checked(chrom, start, end, false, fun)
+            .map(|summary| summary.records)
+    }
+
+    /// Like `map_overlapping`, but passing records in `(start, end)` order,
+    /// with remaining ties in the order records were added. For input sorted
+    /// by start and end, this is the order tabix reports them in. This merges
+    /// the candidate bins' start-sorted features rather than sorting results.
+    /// Like `map_overlapping`, corrupt records are skipped.
+    pub fn map_overlapping_sorted<F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        fun: F,
+    ) -> Result<usize, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        self.map_overlapping_inner(chrom, start, end, false, true, fun)
+            .map(|summary| summary.records)
+    }
 
+    /// The summed weight of the records covering each base of `[start, end)`,
+    /// e.g. read depth with each record carrying a count. `weight` gives a
+    /// record's weight; `|_| 1.0` counts overlapping records. Accumulated
+    /// with a difference array, so this is linear in the window and records.
+    pub fn depth_weighted<F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        mut weight: F,
+    ) -> Result<Vec<f64>, HgIndexError>
+    where
+        F: FnMut(&T::Slice<'_>) -> f64,
+    {
         if end <= start {
             return Err(HgIndexError::InvalidInterval { start, end });
         }
+        let width = (end - start) as usize;
+        let mut diff = vec![0.0; width + 1];
+        self.map_overlapping(chrom, start, end, |record| {
+            let w = weight(&record);
+            diff[(record.start().max(start) - start) as usize] += w;
+            diff[(record.end().min(end) - start) as usize] -= w;
+            Ok(())
+        })?;
 
-        if !self.index.sequences.contains_key(chrom) {
-            return Ok(&self.results_buffer);
+        let mut depth = 0.0;
+        diff.truncate(width);
+        for value in diff.iter_mut() {
+            depth += *value;
+            *value = depth;
         }
+        Ok(diff)
+    }
 
-        if self.open_chrom_file(chrom).is_err() {
-            return Ok(&self.results_buffer);
+    /// Query many regions at once, passing each overlapping record along with
+    /// the index of its region in `regions`. Regions are visited grouped by
+    /// sequence (in karyotype order) and by start, so each data file is opened once and adjacent
+    /// regions read nearby pages; records therefore arrive grouped by region
+    /// but not in input order. Returns the total number of records passed.
+    pub fn map_overlapping_multi<F>(
+        &mut self,
+        regions: &[(String, u32, u32)],
+        mut fun: F,
+    ) -> Result<usize, HgIndexError>
+    where
+        F: FnMut(usize, T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        let mut order: Vec<(Cow<str>, usize)> = regions
+            .iter()
+            .enumerate()
+            .map(|(i, (chrom, _, _))| (self.resolve_chrom(chrom), i))
+            .collect();
+        order.sort_by(|(a_chrom, a), (b_chrom, b)| {
+            natural_chrom_cmp(a_chrom, b_chrom).then(regions[*a].1.cmp(&regions[*b].1))
+        });
+
+        let mut total = 0;
+        for (_, i) in order {
+            let (chrom, start, end) = &regions[i];
+            total += self.map_overlapping(chrom, *start, *end, |record| fun(i, record))?;
+        }
+        Ok(total)
+    }
+
+    /// Like `map_overlapping_multi`, collecting each region's records into
+    /// the vector at its index in `regions`.
+    pub fn get_overlapping_multi(
+        &mut self,
+        regions: &[(String, u32, u32)],
+    ) -> Result<Vec<Vec<T>>, HgIndexError> {
+        let mut results: Vec<Vec<T>> = regions.iter().map(|_| Vec::new()).collect();
+        self.map_overlapping_multi(regions, |i, record| {
+            results[i].push(record.into());
+            Ok(())
+        })?;
+        Ok(results)
+    }
+
+    /// Like `map_overlapping`, but with control over corrupt records (those
+    /// extending past the end of the data file, or that fail to parse). With
+    /// `strict`, the first corrupt record fails the query with
+    /// `CorruptRecord`; otherwise corrupt records are skipped and counted.
+    pub fn map_overlapping_checked<F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        strict: bool,
+        fun: F,
+    ) -> Result<MapSummary, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        self.map_overlapping_inner(chrom, start, end, strict, false, fun)
+    }
+
+    fn map_overlapping_inner<F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        strict: bool,
+        sorted: bool,
+        mut fun: F,
+    ) -> Result<MapSummary, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
         }
 
+        let mut summary = MapSummary::default();
+
+        // Skip absent or empty sequences without opening their data file
+        if !self.index.has_features(chrom) {
+            return Ok(summary);
+        }
+
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(summary);
+        }
+
+        let offsets = if sorted {
+            let offsets = self.index.find_overlapping_sorted(chrom, start, end);
+            self.check_result_limit(offsets.len())?;
+            offsets
+        } else {
+            self.overlapping_offsets(chrom, start, end)?
+        };
         let mmap = match self.data_files.get(chrom).unwrap() {
             FileHandle::Read(mmap) => mmap,
             FileHandle::Write(_) => {
@@ -316,516 +1367,3346 @@ impl<T: Record> GenomicDataStore<T> {
             }
         };
 
-        let offsets = self.index.find_overlapping(chrom, start, end);
-        if offsets.is_empty() {
-            return Ok(&self.results_buffer);
-        }
-
         for (offset, length) in offsets {
-            let offset = offset as usize;
-            let length = length as usize;
-
-            if offset + 8 > mmap.len() {
-                continue;
+            let record =
+                record_bytes(mmap, offset, length, self.max_record_len).and_then(|bytes| {
+                    T::Slice::try_from_bytes(bytes).map_err(|e| HgIndexError::CorruptRecord {
+                        offset,
+                        reason: e.to_string(),
+                    })
+                });
+            match record {
+                Ok(record) => {
+                    fun(record)?;
+                    summary.records += 1;
+                }
+                Err(e) if strict => return Err(e),
+                Err(_) => summary.corrupt += 1,
             }
+        }
 
-            if offset + 8 + length > mmap.len() {
-                continue;
-            }
+        Ok(summary)
+    }
 
-            // Parse as slice then convert to owned
-            let slice = T::Slice::from_bytes(&mmap[offset + 8..offset + 8 + length]);
-            self.results_buffer.push(slice.into())
+    /// Write each record overlapping `chrom:start-end` straight to `writer`,
+    /// formatting zero-copy slices off the mmap with `format_fn`, without
+    /// collecting owned records. Returns the number of records written.
+    pub fn write_overlapping<W, F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        writer: &mut W,
+        mut format_fn: F,
+    ) -> Result<usize, HgIndexError>
+    where
+        W: Write,
+        F: FnMut(T::Slice<'_>, &mut W) -> Result<(), HgIndexError>,
+    {
+        self.map_overlapping(chrom, start, end, |record| format_fn(record, writer))
+    }
+
+    /// Join query intervals on `chrom` against the store, returning each
+    /// overlapping record paired with the query interval it matched. A record
+    /// overlapping several queries appears once per query, as with
+    /// bedtools intersect -wa -wb.
+    pub fn join_overlapping(
+        &mut self,
+        chrom: &str,
+        queries: &[(u32, u32)],
+    ) -> Result<Vec<JoinedRecord<T>>, HgIndexError> {
+        let mut results = Vec::new();
+        for &query in queries {
+            self.map_overlapping(chrom, query.0, query.1, |record| {
+                results.push((query, T::from(record)));
+                Ok(())
+            })?;
         }
+        Ok(results)
+    }
 
-        Ok(&self.results_buffer)
+    /// Return the distinct names of records overlapping `chrom:start-end`,
+    /// as extracted from each zero-copy record slice by `name_fn`, e.g. the
+    /// genes hit by a peak. Records for which `name_fn` returns `None` are
+    /// skipped.
+    pub fn overlapping_names<F>(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        mut name_fn: F,
+    ) -> Result<HashSet<String>, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Option<String>,
+    {
+        let mut names = HashSet::new();
+        self.map_overlapping(chrom, start, end, |record| {
+            names.extend(name_fn(record));
+            Ok(())
+        })?;
+        Ok(names)
     }
 
-    pub fn get_overlapping_batch<'a>(
-        &'a mut self,
+    /// Like `map_overlapping`, but hands `fun` each record's raw stored payload
+    /// (the bytes after the length prefix) without parsing it, for pure
+    /// passthrough of overlapping records.
+    pub fn map_overlapping_bytes<F>(
+        &mut self,
         chrom: &str,
         start: u32,
         end: u32,
-    ) -> Result<Vec<T::Slice<'a>>, HgIndexError> {
-        let mut results = Vec::new();
+        mut fun: F,
+    ) -> Result<usize, HgIndexError>
+    where
+        F: FnMut(&[u8]) -> Result<(), HgIndexError>,
+    {
+        let chrom = &*self.resolve_chrom(chrom);
+
         if end <= start {
             return Err(HgIndexError::InvalidInterval { start, end });
         }
-        if !self.index.sequences.contains_key(chrom) {
-            return Ok(results);
+
+        if !self.index.has_features(chrom) {
+            return Ok(0);
         }
-        if self.open_chrom_file(chrom).is_err() {
-            return Ok(results);
+
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(0);
         }
 
+        let offsets = self.overlapping_offsets(chrom, start, end)?;
         let mmap = match self.data_files.get(chrom).unwrap() {
             FileHandle::Read(mmap) => mmap,
             FileHandle::Write(_) => {
-                return Err(HgIndexError::StringError("File is open for writing".into()))
+                return Err(HgIndexError::StringError("File is open for writing".into()));
             }
         };
 
-        // Get all overlapping records at once
-        let offsets = self.index.find_overlapping(chrom, start, end);
-
-        // Pre-allocate to avoid resizing
-        results.reserve(offsets.len());
-
-        // Needs more extensive benchmarking:
-        let chunk = false;
-        if chunk {
-            // Process in chunks to improve cache utilization
-            const CHUNK_SIZE: usize = 32;
-            for chunk in offsets.chunks(CHUNK_SIZE) {
-                for &(offset, length) in chunk {
-                    let offset = offset as usize;
-                    let length = length as usize;
-                    let record = T::Slice::from_bytes(&mmap[offset + 8..offset + 8 + length]);
-                    results.push(record);
-                }
-            }
-        } else {
-            for (offset, length) in offsets {
-                let offset = offset as usize;
-                let length = length as usize;
-                let record = T::Slice::from_bytes(&mmap[offset + 8..offset + 8 + length]);
-                results.push(record);
-            }
+        let mut count = 0;
+        for (offset, length) in offsets {
+            fun(record_bytes(mmap, offset, length, self.max_record_len)?)?;
+            count += 1;
         }
 
-        Ok(results)
+        Ok(count)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::io::Write;
-
-    use crate::test_utils::test_utils::TestDir;
+    /// The smallest start and largest end of the records on `chrom`, or
+    /// `None` if it has none; see `BinningIndex::sequence_bounds`.
+    pub fn sequence_bounds(&self, chrom: &str) -> Option<(u32, u32)> {
+        self.index.sequence_bounds(&self.resolve_chrom(chrom))
+    }
 
-    use super::*;
-    use serde::{Deserialize, Serialize};
+    /// The number of records in the store, as indexed.
+    pub fn len(&self) -> usize {
+        self.index.num_features()
+    }
 
-    // --- Test types ---
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-    // A simple test record type
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
-    struct TestRecord {
-        start: u32,
-        end: u32,
-        name: String,
-        score: f64,
-        tags: Vec<String>,
+    /// The names of the store's sequences, in karyotype order; see
+    /// [`BinningIndex::chromosomes`].
+    pub fn chromosomes(&self) -> Vec<&str> {
+        self.index.chromosomes()
     }
 
-    // The slice variant of TestRecord
-    #[derive(Clone, Debug, Deserialize)]
-    struct TestRecordSlice<'a> {
-        start: u32,
-        end: u32,
-        name: &'a str,
-        score: f64,
-        tags: Vec<&'a str>,
+    /// Each sequence's `(chrom, count, min_start, max_end)`, from the index
+    /// alone; see [`BinningIndex::chrom_summary`].
+    pub fn chrom_summary(&self) -> Vec<(String, u64, u32, u32)> {
+        self.index.chrom_summary()
     }
 
-    // Then implement Record and RecordSlice
-    impl Record for TestRecord {
-        type Slice<'a> = TestRecordSlice<'a>;
-        fn start(&self) -> u32 {
-            self.start
-        }
-        fn end(&self) -> u32 {
-            self.end
-        }
-        fn to_bytes(&self) -> Vec<u8> {
-            // we can use bincode here for simplicity,
-            // rather than manual serialization
-            bincode::serialize(self).unwrap()
-        }
+    /// Names of the sequences with at least one record, in karyotype order
+    /// (`chr2` before `chr10`, then `chrX`); see [`natural_chrom_cmp`].
+    pub fn sequence_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .index
+            .sequences
+            .keys()
+            .filter(|chrom| self.index.has_features(chrom))
+            .cloned()
+            .collect();
+        names.sort_by(|a, b| natural_chrom_cmp(a, b));
+        names
     }
 
-    impl<'a> RecordSlice<'a> for TestRecordSlice<'a> {
-        type Owned = TestRecord;
+    /// Iterate over the `(start, end, payload)` of every record of `chrom`
+    /// in coordinate order (by start, then end, then insertion order), where
+    /// `payload` is the stored record bytes. Only the index's feature metadata
+    /// is sorted; records are read lazily from the mmap.
+    pub fn sequence_payloads(
+        &mut self,
+        chrom: &str,
+    ) -> Result<impl Iterator<Item = Result<StoredPayload<'_>, HgIndexError>> + '_, HgIndexError>
+    {
+        let chrom = &*self.resolve_chrom(chrom);
 
-        fn start(&self) -> u32 {
-            self.start
+        let mut features: Vec<(u32, u32, u64, u64)> = Vec::new();
+        if self.index.has_features(chrom) {
+            self.open_chrom_file(chrom)?;
+            features = self.index.sequences[chrom]
+                .iter_bins()
+                .flat_map(|(_, features)| features)
+                .map(|f| (f.start, f.end, f.index, f.length))
+                .collect();
+            features.sort_unstable();
         }
 
-        fn end(&self) -> u32 {
-            self.end
-        }
+        let mmap = match self.data_files.get(chrom) {
+            Some(FileHandle::Read(mmap)) => Some(mmap),
+            Some(FileHandle::Write(_)) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+            None => None,
+        };
 
-        fn from_bytes(bytes: &'a [u8]) -> Self {
-            bincode::deserialize(bytes)
-                .map_err(|e| HgIndexError::StringError(e.to_string()))
-                .unwrap()
-        }
+        let max_record_len = self.max_record_len;
+        Ok(features
+            .into_iter()
+            .map(move |(start, end, offset, length)| {
+                check_record_len(offset, length, max_record_len)?;
+                let bytes = mmap
+                    .and_then(|mmap| mmap.get(offset as usize + 8..(offset + 8 + length) as usize))
+                    .ok_or_else(|| HgIndexError::CorruptRecord {
+                        offset,
+                        reason: "record extends past the end of the data file".into(),
+                    })?;
+                Ok((start, end, bytes))
+            }))
+    }
 
-        fn to_owned(self) -> Self::Owned {
-            Self::Owned {
-                start: self.start,
-                end: self.end,
-                name: self.name.to_owned(),
-                score: self.score,
-                tags: self.tags.into_iter().map(|v| v.to_string()).collect(),
+    /// Iterate over every record of `chrom` in stored (i.e. added) order,
+    /// walking its data file directly rather than the index, e.g. to
+    /// re-export or transform a store. Records are parsed lazily off the
+    /// mmap; a record cut short at the end of the file, or one that fails to
+    /// parse, ends iteration.
+    pub fn iter_chrom(
+        &mut self,
+        chrom: &str,
+    ) -> Result<impl Iterator<Item = T::Slice<'_>> + '_, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        let data: &[u8] = if self.index.has_features(chrom) {
+            self.open_chrom_file(chrom)?;
+            match self.data_files.get(chrom).unwrap() {
+                FileHandle::Read(mmap) => mmap,
+                FileHandle::Write(_) => {
+                    return Err(HgIndexError::StringError("File is open for writing".into()));
+                }
             }
-        }
+        } else {
+            &[]
+        };
+
+        // The header was validated when the file was opened
+        let mut offset = Self::HEADER_LEN;
+        Ok(std::iter::from_fn(move || {
+            let prefix = data.get(offset..offset.checked_add(8)?)?;
+            let length = usize::try_from(u64::from_le_bytes(prefix.try_into().unwrap())).ok()?;
+            let start = offset + 8;
+            let payload = data.get(start..start.checked_add(length)?)?;
+            offset = start + length;
+            T::Slice::try_from_bytes(payload).ok()
+        }))
     }
 
-    impl From<TestRecordSlice<'_>> for TestRecord {
-        fn from(slice: TestRecordSlice<'_>) -> Self {
-            Self {
-                start: slice.start,
-                end: slice.end,
-                name: slice.name.to_owned(),
-                score: slice.score,
-                tags: slice.tags.iter().map(|&s| s.to_owned()).collect(),
-            }
+    /// Call `fun` on every record of `chrom` in coordinate order, e.g. to
+    /// export a whole sequence; see `sequence_payloads`.
+    pub fn map_sequence<F>(&mut self, chrom: &str, mut fun: F) -> Result<usize, HgIndexError>
+    where
+        F: FnMut(T::Slice<'_>) -> Result<(), HgIndexError>,
+    {
+        let mut count = 0;
+        for record in self.sequence_payloads(chrom)? {
+            let (_, _, payload) = record?;
+            fun(T::Slice::try_from_bytes(payload)?)?;
+            count += 1;
         }
+        Ok(count)
     }
 
-    #[derive(Debug, Serialize, Deserialize, PartialEq)]
-    struct MinimalTestRecord {
+    /// Like `get_overlapping`, but also returning book-ended features, those
+    /// ending exactly at `start` or starting exactly at `end`. Coordinates
+    /// are half-open, so such features share no base with the query; see
+    /// [`touching_window`](crate::region::touching_window).
+    pub fn get_overlapping_touching(
+        &mut self,
+        chrom: &str,
         start: u32,
         end: u32,
-        score: f64,
+    ) -> Result<&[T], HgIndexError> {
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+        let (start, end) = touching_window(start, end);
+        self.get_overlapping(chrom, start, end)
     }
 
-    #[derive(Debug, Deserialize)]
-    struct MinimalTestRecordSlice<'a> {
+    /// Return the records containing the single position `pos`, i.e. those
+    /// overlapping `[pos, pos + 1)`. Since records are half-open with `u32`
+    /// ends, none can contain `u32::MAX`, which returns no records.
+    pub fn get_at(&mut self, chrom: &str, pos: u32) -> Result<&[T], HgIndexError> {
+        match pos.checked_add(1) {
+            Some(end) => self.get_overlapping(chrom, pos, end),
+            None => {
+                self.results_buffer.clear();
+                Ok(&self.results_buffer)
+            }
+        }
+    }
+
+    pub fn get_overlapping(
+        &mut self,
+        chrom: &str,
         start: u32,
         end: u32,
-        score: f64,
-        _lifetime: PhantomData<&'a ()>,
+    ) -> Result<&[T], HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        self.results_buffer.clear();
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+
+        if !self.index.has_features(chrom) {
+            return Ok(&self.results_buffer);
+        }
+
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(&self.results_buffer);
+        }
+
+        let offsets = self.overlapping_offsets(chrom, start, end)?;
+        self.read_results(chrom, offsets)
     }
 
-    impl Record for MinimalTestRecord {
-        type Slice<'a> = MinimalTestRecordSlice<'a>;
-        fn start(&self) -> u32 {
-            self.start
+    /// Like `get_overlapping`, but taking `&self` and returning owned records,
+    /// so several results can be held at once (e.g. to compare two regions).
+    /// Data files not yet opened by a `&mut self` query are mapped just for
+    /// this call, so open them first (e.g. with `prewarm`) for repeated use.
+    pub fn query_overlapping(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<T>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
         }
-        fn end(&self) -> u32 {
-            self.end
+
+        let mut records = Vec::new();
+        if !self.index.has_features(chrom) {
+            return Ok(records);
         }
-        fn to_bytes(&self) -> Vec<u8> {
-            bincode::serialize(self).unwrap()
+
+        let mapped;
+        let mmap = match self.data_files.get(chrom) {
+            Some(FileHandle::Read(mmap)) => mmap,
+            Some(FileHandle::Write(_)) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+            None => {
+                // Only a missing data file means no records, as with
+                // `open_chrom_file_for_query`
+                let opened = File::open(self.get_data_path(chrom))
+                    .and_then(|file| unsafe { Mmap::map(&file) })
+                    .and_then(|mmap| Self::check_header(&mmap).map(|_| mmap));
+                mapped = match opened {
+                    Ok(mmap) => mmap,
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(records),
+                    Err(error) => return Err(error.into()),
+                };
+                &mapped
+            }
+        };
+
+        let offsets = self.overlapping_offsets(chrom, start, end)?;
+        records.reserve(offsets.len());
+        Self::parse_records(mmap, offsets, self.max_record_len, &mut records)?;
+        Ok(records)
+    }
+
+    /// Like `get_overlapping`, but keeping only records whose overlap with
+    /// `[start, end)` covers at least `min_frac` of the record's length (as
+    /// with bedtools intersect -f) and, if `reciprocal`, also at least
+    /// `min_frac` of the query's length (-f with -r). Records are filtered on
+    /// their indexed coordinates, before any are read.
+    pub fn get_overlapping_frac(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        min_frac: f64,
+        reciprocal: bool,
+    ) -> Result<&[T], HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        self.results_buffer.clear();
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+        if !(0.0..=1.0).contains(&min_frac) {
+            return Err(format!(
+                "Minimum overlap fraction must be between 0 and 1, got {}",
+                min_frac
+            )
+            .into());
         }
+
+        if !self.index.has_features(chrom) || !self.open_chrom_file_for_query(chrom)? {
+            return Ok(&self.results_buffer);
+        }
+
+        let query_length = (end - start) as f64;
+        let offsets: Vec<(u64, u64)> = self
+            .index
+            .overlapping_features(chrom, start, end)
+            .into_iter()
+            .filter(|feature| {
+                let overlap = (feature.end.min(end) - feature.start.max(start)) as f64;
+                let feature_length = (feature.end - feature.start) as f64;
+                overlap >= min_frac * feature_length
+                    && (!reciprocal || overlap >= min_frac * query_length)
+            })
+            .map(|feature| (feature.index, feature.length))
+            .collect();
+        self.check_result_limit(offsets.len())?;
+        self.read_results(chrom, offsets)
     }
 
-    impl<'a> RecordSlice<'a> for MinimalTestRecordSlice<'a> {
-        type Owned = MinimalTestRecord;
-        fn start(&self) -> u32 {
-            self.start
+    /// Like `get_overlapping`, but grouping records by the level of the bin
+    /// each was assigned to, from level 0 (the coarsest bins, holding the
+    /// largest records) to the finest; levels without records are left out.
+    /// E.g. a browser zoomed out can draw only the coarse levels' records.
+    /// Levels are those of the sequence's binning, which for short sequences
+    /// with a flat index is a single level.
+    pub fn get_overlapping_by_level(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<(usize, Vec<T>)>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
         }
-        fn end(&self) -> u32 {
-            self.end
+
+        let levels: Vec<(usize, Vec<(u64, u64)>)> = self
+            .index
+            .overlapping_features_by_level(chrom, start, end)
+            .into_iter()
+            .map(|(level, features)| {
+                let offsets = features
+                    .into_iter()
+                    .map(|feature| (feature.index, feature.length))
+                    .collect();
+                (level, offsets)
+            })
+            .collect();
+        self.check_result_limit(levels.iter().map(|(_, offsets)| offsets.len()).sum())?;
+
+        let mut results = Vec::with_capacity(levels.len());
+        for (level, offsets) in levels {
+            results.push((level, self.read_records(chrom, offsets)?));
         }
-        fn from_bytes(bytes: &'a [u8]) -> Self {
-            bincode::deserialize(bytes)
-                .map_err(|e| HgIndexError::StringError(e.to_string()))
-                .unwrap()
+        Ok(results)
+    }
+
+    /// Like `get_overlapping`, but keeping only records on `strand`, as
+    /// reported by `RecordSlice::strand`. Records of unknown strand are kept
+    /// only for `Strand::Both`. Records are filtered before they're converted
+    /// to owned records, and the result limit counts only those kept.
+    pub fn get_overlapping_stranded(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+        strand: Strand,
+    ) -> Result<&[T], HgIndexError> {
+        let mut records = std::mem::take(&mut self.results_buffer);
+        records.clear();
+        let limit = self.max_results.take();
+        let result = self.map_overlapping_checked(chrom, start, end, true, |record| {
+            if strand.matches(record.strand()) {
+                if let Some(limit) = limit.filter(|&limit| records.len() >= limit) {
+                    return Err(HgIndexError::ResultLimitExceeded { limit });
+                }
+                records.push(record.into());
+            }
+            Ok(())
+        });
+        self.max_results = limit;
+        self.results_buffer = records;
+        result?;
+        Ok(&self.results_buffer)
+    }
+
+    /// Like `get_overlapping`, but keeping only records fully contained in
+    /// `[start, end)` (`start >= query_start && end <= query_end`). Every bin
+    /// overlapping the query is still visited, since small contained records
+    /// sit in finer bins than the query region's own.
+    pub fn get_contained(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<&[T], HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        self.results_buffer.clear();
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
         }
 
-        fn to_owned(self) -> Self::Owned {
-            Self::Owned {
-                start: self.start,
-                end: self.end,
-                score: self.score,
+        if !self.index.has_features(chrom) || !self.open_chrom_file_for_query(chrom)? {
+            return Ok(&self.results_buffer);
+        }
+
+        let offsets: Vec<(u64, u64)> = self
+            .index
+            .overlapping_features(chrom, start, end)
+            .into_iter()
+            .filter(|feature| feature.start >= start && feature.end <= end)
+            .map(|feature| (feature.index, feature.length))
+            .collect();
+        self.check_result_limit(offsets.len())?;
+        self.read_results(chrom, offsets)
+    }
+
+    // Read the records at `offsets` in `chrom`'s open data file into the
+    // results buffer, skipping any extending past the end of the file
+    fn read_results(
+        &mut self,
+        chrom: &str,
+        offsets: Vec<(u64, u64)>,
+    ) -> Result<&[T], HgIndexError> {
+        let mmap = match self.data_files.get(chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+        };
+        Self::parse_records(mmap, offsets, self.max_record_len, &mut self.results_buffer)?;
+        Ok(&self.results_buffer)
+    }
+
+    // Like `read_results`, but into a new vector
+    fn read_records(
+        &mut self,
+        chrom: &str,
+        offsets: Vec<(u64, u64)>,
+    ) -> Result<Vec<T>, HgIndexError> {
+        let mut records = Vec::with_capacity(offsets.len());
+        if offsets.is_empty() || !self.open_chrom_file_for_query(chrom)? {
+            return Ok(records);
+        }
+        let mmap = match self.data_files.get(chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
             }
+        };
+        Self::parse_records(mmap, offsets, self.max_record_len, &mut records)?;
+        Ok(records)
+    }
+
+    fn parse_records(
+        mmap: &[u8],
+        offsets: Vec<(u64, u64)>,
+        max_record_len: Option<u64>,
+        records: &mut Vec<T>,
+    ) -> Result<(), HgIndexError> {
+        for (offset, length) in offsets {
+            // Parse as slice then convert to owned
+            let bytes = record_bytes(mmap, offset, length, max_record_len)?;
+            records.push(T::Slice::from_bytes(bytes).into())
         }
+        Ok(())
     }
 
-    impl From<MinimalTestRecordSlice<'_>> for MinimalTestRecord {
-        fn from(slice: MinimalTestRecordSlice<'_>) -> Self {
-            Self {
-                start: slice.start,
-                end: slice.end,
-                score: slice.score,
+    /// Return records overlapping at least one of the `query_blocks`, e.g. the
+    /// aligned blocks of a spliced read.
+    ///
+    /// Unlike a single-interval query over the blocks' span, a record falling
+    /// entirely within a gap between blocks (such as an intron-only feature)
+    /// is not returned. Blocks are half-open `[start, end)` and need not be
+    /// sorted.
+    pub fn get_overlapping_blocks(
+        &mut self,
+        chrom: &str,
+        query_blocks: &[(u32, u32)],
+    ) -> Result<&[T], HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        self.results_buffer.clear();
+
+        for &(start, end) in query_blocks {
+            if end <= start {
+                return Err(HgIndexError::InvalidInterval { start, end });
+            }
+        }
+        let Some(span_start) = query_blocks.iter().map(|&(start, _)| start).min() else {
+            return Ok(&self.results_buffer);
+        };
+        let span_end = query_blocks.iter().map(|&(_, end)| end).max().unwrap();
+
+        if !self.index.has_features(chrom) {
+            return Ok(&self.results_buffer);
+        }
+
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(&self.results_buffer);
+        }
+
+        let offsets = self.overlapping_offsets(chrom, span_start, span_end)?;
+        let mmap = match self.data_files.get(chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+        };
+
+        for (offset, length) in offsets {
+            let bytes = record_bytes(mmap, offset, length, self.max_record_len)?;
+            let slice = T::Slice::from_bytes(bytes);
+            let overlaps_block = query_blocks
+                .iter()
+                .any(|&(start, end)| slice.start() < end && slice.end() > start);
+            if overlaps_block {
+                self.results_buffer.push(slice.into());
             }
         }
+
+        Ok(&self.results_buffer)
     }
 
-    fn make_test_records() -> Vec<(String, TestRecord)> {
-        vec![
-            (
-                "chr1".to_string(),
-                TestRecord {
-                    start: 1000,
-                    end: 2000,
-                    name: "feature1".to_string(),
-                    score: 0.5,
-                    tags: vec!["exon".to_string(), "coding".to_string()],
-                },
-            ),
-            (
-                "chr1".to_string(),
-                TestRecord {
-                    start: 1500,
-                    end: 2500,
-                    name: "feature2".to_string(),
-                    score: 0.8,
-                    tags: vec!["promoter".to_string()],
-                },
-            ),
-            (
-                "chr2".to_string(),
-                TestRecord {
-                    start: 50000,
-                    end: 60000,
-                    name: "feature3".to_string(),
-                    score: 0.3,
-                    tags: vec!["intron".to_string()],
-                },
-            ),
-        ]
+    /// Return the overlapping record with the smallest start, without
+    /// collecting every overlap.
+    ///
+    /// Ties on start are broken by the order records were added (the earliest
+    /// added wins), so the result is deterministic for a given store.
+    pub fn first_overlapping(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Option<T>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+
+        if !self.index.has_features(chrom) {
+            return Ok(None);
+        }
+
+        let Some((offset, length)) = self.index.first_overlapping(chrom, start, end) else {
+            return Ok(None);
+        };
+
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(None);
+        }
+
+        let mmap = match self.data_files.get(chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+        };
+
+        let bytes = record_bytes(mmap, offset, length, self.max_record_len)?;
+        Ok(Some(T::Slice::from_bytes(bytes).into()))
     }
 
-    // --- Test Functions ---
+    /// Return the `k` records on `chrom` closest to `pos`, even if none
+    /// overlap it (like bedtools closest), by ascending distance with ties
+    /// broken by start. The distance is 0 for records containing `pos`,
+    /// `start - pos` for records to its right, and `pos - end` for those to
+    /// its left.
+    pub fn nearest(&mut self, chrom: &str, pos: u32, k: usize) -> Result<Vec<T>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
 
-    #[test]
-    fn test_store_and_retrieve() {
-        let test_dir = TestDir::new("store_and_retrieve").expect("Failed to create test dir");
-        let base_dir = test_dir.path(); // Don't add test.gidx
+        let features: Vec<(u64, u64)> = self
+            .index
+            .nearest_features(chrom, pos, k)
+            .into_iter()
+            .map(|feature| (feature.index, feature.length))
+            .collect();
+        self.read_records(chrom, features)
+    }
+
+    /// Return up to `limit` records on `chrom` at or after `cursor`, in
+    /// coordinate order (by start, ties in the order records were added),
+    /// with the cursor of the next page, or `None` after the last record.
+    /// The cursor is a `(start, index)` position: begin at `(pos, 0)` for the
+    /// records starting at or after `pos`, then pass each returned cursor to
+    /// page through a sequence without skipping records that share a start.
+    /// Only records near the cursor are read.
+    pub fn features_after(
+        &mut self,
+        chrom: &str,
+        cursor: FeatureCursor,
+        limit: usize,
+    ) -> Result<(Vec<T>, Option<FeatureCursor>), HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        let features = self.index.features_after(chrom, cursor, limit);
+        let next = match features.last() {
+            Some(last) if features.len() == limit => Some((last.start, last.index + 1)),
+            _ => None,
+        };
+        let features: Vec<(u64, u64)> = features
+            .into_iter()
+            .map(|feature| (feature.index, feature.length))
+            .collect();
+        Ok((self.read_records(chrom, features)?, next))
+    }
+
+    /// Read the payload of the record stored at `offset` with `length` bytes
+    /// (a `Feature`'s `index` and `length`, e.g. from `features_overlapping`)
+    /// as a stream, so a large record such as a long sequence can be consumed
+    /// in chunks without being copied into one allocation.
+    ///
+    /// The maximum record length is not applied, but `length` must match the
+    /// length stored before the payload.
+    pub fn record_reader(
+        &mut self,
+        chrom: &str,
+        offset: u64,
+        length: u64,
+    ) -> Result<impl Read + '_, HgIndexError> {
+        let chrom = self.resolve_chrom(chrom).into_owned();
+
+        self.open_chrom_file(&chrom)?;
+        let mmap = match self.data_files.get(&chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()));
+            }
+        };
+
+        let corrupt = |reason: String| HgIndexError::CorruptRecord { offset, reason };
+        let header = usize::try_from(offset)
+            .ok()
+            .and_then(|start| mmap.get(start..start.checked_add(8)?))
+            .ok_or_else(|| corrupt("offset lies beyond the end of the data file".into()))?;
+        let stored = u64::from_le_bytes(header.try_into().unwrap());
+        if stored != length {
+            return Err(corrupt(format!(
+                "stored record length {} does not match the expected length {}",
+                stored, length
+            )));
+        }
+        let start = offset as usize + 8;
+        usize::try_from(length)
+            .ok()
+            .and_then(|length| mmap.get(start..start.checked_add(length)?))
+            .ok_or_else(|| corrupt("record extends beyond the end of the data file".into()))
+    }
+
+    pub fn get_overlapping_batch<'a>(
+        &'a mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<T::Slice<'a>>, HgIndexError> {
+        let chrom = &*self.resolve_chrom(chrom);
+
+        let mut results = Vec::new();
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+        if !self.index.has_features(chrom) {
+            return Ok(results);
+        }
+        if !self.open_chrom_file_for_query(chrom)? {
+            return Ok(results);
+        }
+
+        // Get all overlapping records at once
+        let offsets = self.overlapping_offsets(chrom, start, end)?;
+        let mmap = match self.data_files.get(chrom).unwrap() {
+            FileHandle::Read(mmap) => mmap,
+            FileHandle::Write(_) => {
+                return Err(HgIndexError::StringError("File is open for writing".into()))
+            }
+        };
+
+        // Pre-allocate to avoid resizing
+        results.reserve(offsets.len());
+
+        match self.read_batch_size {
+            Some(batch_size) => {
+                // Prefetch each chunk's records before parsing them, so the
+                // parse doesn't fault pages in one at a time
+                let mut records = Vec::with_capacity(batch_size);
+                let mut ranges = Vec::with_capacity(batch_size);
+                for chunk in offsets.chunks(batch_size) {
+                    ranges.clear();
+                    for &(offset, length) in chunk {
+                        let bytes = record_bytes(mmap, offset, length, self.max_record_len)?;
+                        let offset = offset as usize;
+                        ranges.push((offset, offset + 8 + bytes.len()));
+                        records.push(bytes);
+                    }
+                    prefetch_ranges(mmap, &mut ranges);
+                    results.extend(records.drain(..).map(T::Slice::from_bytes));
+                }
+            }
+            None => {
+                for (offset, length) in offsets {
+                    let bytes = record_bytes(mmap, offset, length, self.max_record_len)?;
+                    results.push(T::Slice::from_bytes(bytes));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Records buffered between the blocking reader and an async stream consumer.
+#[cfg(feature = "async")]
+const STREAM_BUFFER_SIZE: usize = 1024;
+
+#[cfg(feature = "async")]
+impl<T> GenomicDataStore<T>
+where
+    T: Record + Send + 'static,
+{
+    /// Stream the records overlapping `chrom:start-end` for async callers.
+    ///
+    /// The store's query API takes `&mut self`, so the store is shared behind
+    /// a mutex. The index traversal and mmap reads run on tokio's blocking
+    /// pool via `spawn_blocking`, holding the lock for the whole query, and
+    /// records are sent back through a bounded channel. Dropping the stream
+    /// stops the query early. Must be called within a tokio runtime.
+    pub fn get_overlapping_stream(
+        store: Arc<Mutex<Self>>,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> impl Stream<Item = Result<T, HgIndexError>> {
+        let (sender, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER_SIZE);
+        let chrom = chrom.to_string();
+        tokio::task::spawn_blocking(move || {
+            let Ok(mut store) = store.lock() else {
+                let _ = sender.blocking_send(Err("GenomicDataStore mutex was poisoned".into()));
+                return;
+            };
+            let result = store.map_overlapping(&chrom, start, end, |record| {
+                sender
+                    .blocking_send(Ok(record.into()))
+                    .map_err(|_| HgIndexError::from("stream dropped"))
+            });
+            if let Err(e) = result {
+                // Fails only if the receiver is gone, when there's no one to tell
+                let _ = sender.blocking_send(Err(e));
+            }
+        });
+        ReceiverStream::new(receiver)
+    }
+}
+
+/// A read-only handle on a store's index alone (`index.bin`), for interval
+/// analysis without the `{chrom}.bin` data files, e.g. when only the index is
+/// shared for membership checks.
+#[derive(Debug)]
+pub struct IndexOnlyStore {
+    index: BinningIndex,
+}
+
+impl IndexOnlyStore {
+    /// Open an index file, such as the `index.bin` inside a store directory.
+    pub fn open(index_path: &Path) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            index: BinningIndex::open(index_path)?,
+        })
+    }
+
+    pub fn index(&self) -> &BinningIndex {
+        &self.index
+    }
+
+    /// The binning schema the index was built with.
+    pub fn schema(&self) -> &BinningSchema {
+        &self.index.bins.schema
+    }
+
+    /// The binning parameters the index was built with.
+    pub fn hierarchical_bins(&self) -> &HierarchicalBins {
+        &self.index.bins
+    }
+
+    /// Count the features overlapping `chrom:start-end`.
+    pub fn count_overlapping(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<usize, HgIndexError> {
+        Ok(self.spans_overlapping(chrom, start, end)?.len())
+    }
+
+    /// The `(start, end)` spans of features overlapping `chrom:start-end`,
+    /// in index order.
+    pub fn spans_overlapping(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<(u32, u32)>, HgIndexError> {
+        Ok(self
+            .features_overlapping(chrom, start, end)?
+            .into_iter()
+            .map(|feature| (feature.start, feature.end))
+            .collect())
+    }
+
+    /// The index entries of features overlapping `chrom:start-end`, in index
+    /// order, including any `Feature::extra` values stored with them.
+    pub fn features_overlapping(
+        &self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<&Feature>, HgIndexError> {
+        let chrom = &*self.index.canonical_chrom(chrom);
+
+        if end <= start {
+            return Err(HgIndexError::InvalidInterval { start, end });
+        }
+        Ok(self.index.overlapping_features(chrom, start, end))
+    }
+
+    /// Analyze the index's bin utilization and structure.
+    pub fn stats(&self) -> BinningStats {
+        BinningStats::analyze(&self.index)
+    }
+}
+
+/// A record paired with the position of the store it came from in a
+/// [`MultiStore`].
+pub type SourcedRecord<T> = (usize, T);
+
+/// Several stores of the same record type, e.g. annotation tracks packed
+/// separately, queried together. Unlike keyed stores sharing a directory,
+/// the stores can live anywhere.
+#[derive(Debug)]
+pub struct MultiStore<T: Record> {
+    stores: Vec<(String, GenomicDataStore<T>)>,
+}
+
+impl<T: Record> MultiStore<T> {
+    /// Open the store in each of `directories`, each named by its path.
+    pub fn open<P: AsRef<Path>>(
+        directories: &[P],
+    ) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let mut stores = Vec::with_capacity(directories.len());
+        for directory in directories {
+            let directory = directory.as_ref();
+            let store = GenomicDataStore::open(directory, None)?;
+            stores.push((directory.display().to_string(), store));
+        }
+        Ok(Self { stores })
+    }
+
+    /// Combine already-opened stores, e.g. keyed stores, under the given
+    /// names.
+    pub fn from_stores(stores: Vec<(String, GenomicDataStore<T>)>) -> Self {
+        Self { stores }
+    }
+
+    /// The names of the stores, in source order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.stores.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The number of stores.
+    pub fn len(&self) -> usize {
+        self.stores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stores.is_empty()
+    }
+
+    /// Return the records of every store overlapping `chrom:start-end`, each
+    /// tagged with its store's position (as in `names`). Results are grouped
+    /// by store, in source order.
+    pub fn get_overlapping_union(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<SourcedRecord<T>>, HgIndexError> {
+        let mut results = Vec::new();
+        for (source, (_, store)) in self.stores.iter_mut().enumerate() {
+            store.map_overlapping(chrom, start, end, |record| {
+                results.push((source, record.into()));
+                Ok(())
+            })?;
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::test_utils::test_utils::TestDir;
+
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    // --- Test types ---
+
+    // A simple test record type
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+    struct TestRecord {
+        start: u32,
+        end: u32,
+        name: String,
+        score: f64,
+        tags: Vec<String>,
+    }
+
+    // The slice variant of TestRecord
+    #[derive(Clone, Debug, Deserialize)]
+    struct TestRecordSlice<'a> {
+        start: u32,
+        end: u32,
+        name: &'a str,
+        score: f64,
+        tags: Vec<&'a str>,
+    }
+
+    // Then implement Record and RecordSlice
+    impl Record for TestRecord {
+        type Slice<'a> = TestRecordSlice<'a>;
+        fn start(&self) -> u32 {
+            self.start
+        }
+        fn end(&self) -> u32 {
+            self.end
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            // we can use bincode here for simplicity,
+            // rather than manual serialization
+            bincode::serialize(self).unwrap()
+        }
+    }
+
+    impl<'a> RecordSlice<'a> for TestRecordSlice<'a> {
+        type Owned = TestRecord;
+
+        fn start(&self) -> u32 {
+            self.start
+        }
+
+        fn end(&self) -> u32 {
+            self.end
+        }
+
+        fn from_bytes(bytes: &'a [u8]) -> Self {
+            bincode::deserialize(bytes)
+                .map_err(|e| HgIndexError::StringError(e.to_string()))
+                .unwrap()
+        }
+
+        fn to_owned(self) -> Self::Owned {
+            Self::Owned {
+                start: self.start,
+                end: self.end,
+                name: self.name.to_owned(),
+                score: self.score,
+                tags: self.tags.into_iter().map(|v| v.to_string()).collect(),
+            }
+        }
+    }
+
+    impl From<TestRecordSlice<'_>> for TestRecord {
+        fn from(slice: TestRecordSlice<'_>) -> Self {
+            Self {
+                start: slice.start,
+                end: slice.end,
+                name: slice.name.to_owned(),
+                score: slice.score,
+                tags: slice.tags.iter().map(|&s| s.to_owned()).collect(),
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct MinimalTestRecord {
+        start: u32,
+        end: u32,
+        score: f64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct MinimalTestRecordSlice<'a> {
+        start: u32,
+        end: u32,
+        score: f64,
+        _lifetime: PhantomData<&'a ()>,
+    }
+
+    impl Record for MinimalTestRecord {
+        type Slice<'a> = MinimalTestRecordSlice<'a>;
+        fn start(&self) -> u32 {
+            self.start
+        }
+        fn end(&self) -> u32 {
+            self.end
+        }
+        fn to_bytes(&self) -> Vec<u8> {
+            bincode::serialize(self).unwrap()
+        }
+    }
+
+    impl<'a> RecordSlice<'a> for MinimalTestRecordSlice<'a> {
+        type Owned = MinimalTestRecord;
+        fn start(&self) -> u32 {
+            self.start
+        }
+        fn end(&self) -> u32 {
+            self.end
+        }
+        fn from_bytes(bytes: &'a [u8]) -> Self {
+            bincode::deserialize(bytes)
+                .map_err(|e| HgIndexError::StringError(e.to_string()))
+                .unwrap()
+        }
+
+        fn to_owned(self) -> Self::Owned {
+            Self::Owned {
+                start: self.start,
+                end: self.end,
+                score: self.score,
+            }
+        }
+    }
+
+    impl From<MinimalTestRecordSlice<'_>> for MinimalTestRecord {
+        fn from(slice: MinimalTestRecordSlice<'_>) -> Self {
+            Self {
+                start: slice.start,
+                end: slice.end,
+                score: slice.score,
+            }
+        }
+    }
+
+    // Pack `make_test_records()` into a new store in the test directory `name`,
+    // reopened for querying
+    fn packed_test_store(name: &str) -> (TestDir, GenomicDataStore<TestRecord>) {
+        let test_dir = TestDir::new(name).expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        store.finalize().unwrap();
+        let store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        (test_dir, store)
+    }
+
+    fn make_test_records() -> Vec<(String, TestRecord)> {
+        vec![
+            (
+                "chr1".to_string(),
+                TestRecord {
+                    start: 1000,
+                    end: 2000,
+                    name: "feature1".to_string(),
+                    score: 0.5,
+                    tags: vec!["exon".to_string(), "coding".to_string()],
+                },
+            ),
+            (
+                "chr1".to_string(),
+                TestRecord {
+                    start: 1500,
+                    end: 2500,
+                    name: "feature2".to_string(),
+                    score: 0.8,
+                    tags: vec!["promoter".to_string()],
+                },
+            ),
+            (
+                "chr2".to_string(),
+                TestRecord {
+                    start: 50000,
+                    end: 60000,
+                    name: "feature3".to_string(),
+                    score: 0.3,
+                    tags: vec!["intron".to_string()],
+                },
+            ),
+        ]
+    }
+
+    // --- Test Functions ---
+
+    #[test]
+    fn test_store_and_retrieve() {
+        let test_dir = TestDir::new("store_and_retrieve").expect("Failed to create test dir");
+        let base_dir = test_dir.path(); // Don't add test.gidx
+
+        // An example key
+        let key = "example-key".to_string();
+
+        // Create store and add records
+        let mut store = GenomicDataStore::<TestRecord>::create(base_dir, Some(key.clone()))
+            .expect("Failed to create store");
+        for (chrom, record) in make_test_records() {
+            store
+                .add_record(&chrom, &record)
+                .expect("Failed to add record");
+        }
+
+        store.finalize().expect("Failed to finalize store");
+
+        let mut store = GenomicDataStore::<TestRecord>::open(base_dir, Some(key.clone()))
+            .expect("Failed to open store");
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.sequence_bounds("chr1"), Some((1000, 2500)));
+
+        // Test overlapping query
+        let results = store.get_overlapping("chr1", 1200, 1800).unwrap();
+        assert_eq!(results.len(), 2); // Should get both chr1 features
+        assert_eq!(results[0].name, "feature1");
+        assert_eq!(results[1].name, "feature2");
+
+        // Test non-overlapping region
+        let results = store.get_overlapping("chr1", 3000, 4000).unwrap();
+        assert_eq!(results.len(), 0);
+
+        // Test different chromosome
+        let results = store.get_overlapping("chr2", 55000, 58000).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "feature3");
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let test_dir = TestDir::new("invalid_file").expect("Failed to create test dir");
+        let bad_file = test_dir.path().join("bad.gidx");
+
+        // Create file with invalid magic number
+        let mut file = File::create(&bad_file).expect("Failed to create file");
+        file.write_all(b"BAD!").expect("Failed to write");
+
+        // Attempt to open should fail
+        let result = GenomicDataStore::<TestRecord>::open(&bad_file, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_empty_regions() {
+        let test_dir = TestDir::new("empty_regions").expect("Failed to create test dir");
+        let store_path = test_dir.path().join("empty.gidx");
+
+        let mut store = GenomicDataStore::<TestRecord>::create(&store_path, None)
+            .expect("Failed to create store");
+
+        store.finalize().expect("Failed to finalize store");
+
+        // Query empty store
+        let mut store =
+            GenomicDataStore::<TestRecord>::open(&store_path, None).expect("Failed to open store");
+
+        let results = store.get_overlapping("chr1", 0, 1000).unwrap();
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_reads() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let test_dir = TestDir::new("concurrent").expect("Failed to create test dir");
+        let store_path = test_dir.path().join("test.gidx");
+
+        // Create and populate store
+        {
+            let mut store = GenomicDataStore::<MinimalTestRecord>::create(&store_path, None)
+                .expect("Failed to create store");
+
+            // Add some overlapping records
+            for i in 0..10 {
+                let start = i * 1000;
+                let end = (i + 2) * 1000; // Overlapping regions
+                store
+                    .add_record(
+                        "chr1",
+                        &MinimalTestRecord {
+                            start,
+                            end,
+                            score: i as f64,
+                        },
+                    )
+                    .expect("Failed to add record");
+            }
+            store.finalize().expect("Failed to finalize");
+        }
+
+        // Create path that can be shared between threads
+        let path = Arc::new(store_path);
+
+        // Spawn multiple reader threads
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let path = Arc::clone(&path);
+                thread::spawn(move || {
+                    let mut store = GenomicDataStore::<MinimalTestRecord>::open(&path, None)
+                        .expect("Failed to open store");
+
+                    // Each thread queries a different but overlapping region
+                    let start = i * 500;
+                    let end = start + 2000;
+                    let results = store.get_overlapping("chr1", start, end).unwrap();
+
+                    // Results should not be empty due to overlapping regions
+                    assert!(!results.is_empty());
+                    results.len()
+                })
+            })
+            .collect();
+
+        // Verify all threads completed successfully
+        let result_counts: Vec<_> = handles
+            .into_iter()
+            .map(|h| h.join().expect("Thread panicked"))
+            .collect();
+
+        // Verify that at least some threads got different numbers of results
+        // due to querying different regions
+        assert!(result_counts.iter().any(|&x| x != result_counts[0]));
+    }
+
+    #[test]
+    fn test_map_vs_get_consistency() {
+        let (_test_dir, mut store) = packed_test_store("map_vs_get_consistency");
+
+        // Define test queries
+        let queries = vec![
+            ("chr1", 1200, 1800),
+            ("chr1", 0, 3000),
+            ("chr2", 50000, 60000),
+            ("chr2", 55000, 58000),
+            ("chr3", 0, 10000),
+        ];
+
+        for (chrom, start, end) in queries {
+            // Get overlapping records
+            let get_results = store.get_overlapping(chrom, start, end).unwrap().to_vec();
+
+            // Map overlapping records
+            let mut map_results = Vec::new();
+            store
+                .map_overlapping(chrom, start, end, |record| {
+                    map_results.push(record.to_owned());
+                    Ok(())
+                })
+                .unwrap();
+
+            // Assert that both results are identical
+            assert_eq!(
+                get_results, map_results,
+                "Mismatch for chrom: {}, start: {}, end: {}",
+                chrom, start, end
+            );
+        }
+    }
+
+    #[test]
+    fn test_metadata_storage_and_retrieval() {
+        use std::collections::HashMap;
+        let test_dir = TestDir::new("metadata_test").expect("Failed to create test dir");
+        let base_dir = test_dir.path();
+
+        // Create some test metadata (using a simple struct)
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct TestMetadata {
+            name: String,
+            values: HashMap<String, i32>,
+        }
+
+        let original_metadata = TestMetadata {
+            name: "test".to_string(),
+            values: {
+                let mut m = HashMap::new();
+                m.insert("key1".to_string(), 42);
+                m.insert("key2".to_string(), 100);
+                m
+            },
+        };
+
+        // Create and populate store
+        {
+            let mut store = GenomicDataStore::<TestRecord>::create(base_dir, None)
+                .expect("Failed to create store");
+
+            // Add some test records
+            let record = TestRecord {
+                start: 1000,
+                end: 2000,
+                name: "feature1".to_string(),
+                score: 0.5,
+                tags: vec!["test".to_string()],
+            };
+            store
+                .add_record("chr1", &record)
+                .expect("Failed to add record");
+
+            // Finalize with metadata
+            store
+                .finalize_with_metadata(&original_metadata)
+                .expect("Failed to finalize with metadata");
+        }
+
+        // Reopen and check metadata
+        {
+            let store =
+                GenomicDataStore::<TestRecord>::open(base_dir, None).expect("Failed to open store");
+
+            let retrieved_metadata: Option<TestMetadata> = store.metadata();
+            assert!(retrieved_metadata.is_some());
+
+            let retrieved_metadata = retrieved_metadata.unwrap();
+            assert_eq!(retrieved_metadata, original_metadata);
+            assert_eq!(retrieved_metadata.name, "test");
+            assert_eq!(retrieved_metadata.values.get("key1"), Some(&42));
+            assert_eq!(retrieved_metadata.values.get("key2"), Some(&100));
+        }
+    }
+
+    #[test]
+    fn test_nearest() {
+        let test_dir = TestDir::new("nearest").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let ranges = [(1000, 1100), (1500, 1600), (5000, 6000), (5000, 5100)];
+        for (start, end) in ranges {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        // A distant feature, reachable only after widening several times
+        let far = MinimalTestRecord {
+            start: 50_000_000,
+            end: 50_000_100,
+            score: 0.0,
+        };
+        store.add_record("chr1", &far).unwrap();
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let spans = |records: Vec<MinimalTestRecord>| {
+            records
+                .into_iter()
+                .map(|record| (record.start, record.end))
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing overlaps 1300: 1100 is 200 to the left, 1500 is 200 to the
+        // right, and the tie goes to the smaller start
+        assert_eq!(
+            spans(store.nearest("chr1", 1300, 2).unwrap()),
+            [(1000, 1100), (1500, 1600)]
+        );
+        // Overlapping features have distance 0, tied ones in added order
+        assert_eq!(
+            spans(store.nearest("chr1", 5050, 3).unwrap()),
+            [(5000, 6000), (5000, 5100), (1500, 1600)]
+        );
+        assert_eq!(
+            spans(store.nearest("chr1", 40_000_000, 1).unwrap()),
+            [(50_000_000, 50_000_100)]
+        );
+        // Asking for more than exist returns them all
+        assert_eq!(store.nearest("chr1", 0, 10).unwrap().len(), 5);
+        assert!(store.nearest("chr1", 0, 0).unwrap().is_empty());
+        assert!(store.nearest("chrX", 0, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_overlapping() {
+        let (test_dir, mut store) = packed_test_store("query_overlapping");
+        let names = |records: &[TestRecord]| {
+            let mut names: Vec<_> = records.iter().map(|r| r.name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        // Both results are held at once, with chr2's file mapped for the call
+        let left = store.query_overlapping("chr1", 1000, 1400).unwrap();
+        let right = store.query_overlapping("chr1", 1400, 3000).unwrap();
+        let chr2 = store.query_overlapping("chr2", 55000, 56000).unwrap();
+        assert_eq!(names(&left), ["feature1"]);
+        assert_eq!(names(&right), ["feature1", "feature2"]);
+        assert_eq!(names(&chr2), ["feature3"]);
+        let shared: Vec<_> = right
+            .iter()
+            .filter(|record| left.iter().any(|other| other.name == record.name))
+            .collect();
+        assert_eq!(shared.len(), 1);
+
+        // Matches get_overlapping once the file is open
+        let expected = names(store.get_overlapping("chr1", 1400, 3000).unwrap());
+        assert_eq!(
+            names(&store.query_overlapping("chr1", 1400, 3000).unwrap()),
+            expected
+        );
+
+        assert!(store.query_overlapping("chrX", 0, 1000).unwrap().is_empty());
+        assert!(store.query_overlapping("chr1", 2000, 1000).is_err());
+
+        // An unreadable data file fails the query; only a missing one is empty
+        let chr2_path = test_dir.path().join("chr2.bin");
+        fs::write(&chr2_path, b"XXXX\x01\x00").unwrap();
+        assert!(store.query_overlapping("chr2", 55000, 56000).is_err());
+        fs::remove_file(&chr2_path).unwrap();
+        assert!(store
+            .query_overlapping("chr2", 55000, 56000)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_zero_width_features() {
+        let test_dir = TestDir::new("zero_width").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        // Insertion points, one at a bin boundary (16384) and one at 0,
+        // between ordinary features
+        let ranges = [
+            (0, 0),
+            (100, 200),
+            (500, 500),
+            (16384, 16384),
+            (16384, 16500),
+        ];
+        for (start, end) in ranges {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let spans = |store: &mut GenomicDataStore<MinimalTestRecord>, start, end| {
+            let mut spans: Vec<_> = store
+                .get_overlapping("chr1", start, end)
+                .unwrap()
+                .iter()
+                .map(|record| (record.start, record.end))
+                .collect();
+            spans.sort();
+            spans
+        };
+
+        // Half-open: only windows strictly containing the point match
+        assert_eq!(spans(&mut store, 499, 501), [(500, 500)]);
+        assert!(spans(&mut store, 500, 501).is_empty());
+        assert!(spans(&mut store, 499, 500).is_empty());
+        assert_eq!(spans(&mut store, 16384, 16385), [(16384, 16500)]);
+
+        store.set_zero_width_inclusive(true);
+        assert_eq!(spans(&mut store, 500, 501), [(500, 500)]);
+        assert_eq!(spans(&mut store, 499, 500), [(500, 500)]);
+        assert!(spans(&mut store, 501, 502).is_empty());
+        assert_eq!(spans(&mut store, 16383, 16384), [(16384, 16384)]);
+        assert_eq!(
+            spans(&mut store, 16384, 16385),
+            [(16384, 16384), (16384, 16500)]
+        );
+        assert_eq!(spans(&mut store, 0, 1), [(0, 0)]);
+        // Ordinary features keep half-open semantics
+        assert!(spans(&mut store, 200, 300).is_empty());
+
+        let mut sorted = Vec::new();
+        store
+            .map_overlapping_sorted("chr1", 16384, 16385, |record| {
+                sorted.push((record.start, record.end));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(sorted, [(16384, 16384), (16384, 16500)]);
+    }
+
+    #[test]
+    fn test_depth_weighted() {
+        let test_dir = TestDir::new("depth_weighted").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        // (start, end, count)
+        let ranges = [(0, 4, 2.0), (2, 6, 1.0), (3, 4, 0.5), (8, 20, 3.0)];
+        for (start, end, score) in ranges {
+            let record = MinimalTestRecord { start, end, score };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(
+            store
+                .depth_weighted("chr1", 1, 10, |record| record.score)
+                .unwrap(),
+            [2.0, 3.0, 3.5, 1.0, 1.0, 0.0, 0.0, 3.0, 3.0]
+        );
+        // Unit weights count overlapping records
+        assert_eq!(
+            store.depth_weighted("chr1", 0, 5, |_| 1.0).unwrap(),
+            [1.0, 1.0, 2.0, 3.0, 1.0]
+        );
+        assert_eq!(
+            store.depth_weighted("chrX", 0, 3, |_| 1.0).unwrap(),
+            [0.0; 3]
+        );
+        assert!(store.depth_weighted("chr1", 5, 5, |_| 1.0).is_err());
+    }
+
+    #[test]
+    fn test_get_overlapping_multi() {
+        let (_test_dir, mut store) = packed_test_store("overlapping_multi");
+        // Interleaved sequences, out of order, with a repeat and a miss
+        let regions = [
+            ("chr2".to_string(), 55000, 56000),
+            ("chr1".to_string(), 2200, 3000),
+            ("chrX".to_string(), 0, 1000),
+            ("chr1".to_string(), 1000, 1600),
+            ("chr2".to_string(), 55000, 56000),
+        ];
+        let results = store.get_overlapping_multi(&regions).unwrap();
+        let names: Vec<Vec<&str>> = results
+            .iter()
+            .map(|records| {
+                let mut names: Vec<_> = records.iter().map(|r| r.name.as_str()).collect();
+                names.sort();
+                names
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                vec!["feature3"],
+                vec!["feature2"],
+                vec![],
+                vec!["feature1", "feature2"],
+                vec!["feature3"],
+            ]
+        );
+
+        // Records arrive grouped by region, sorted by sequence and start
+        let mut visited = Vec::new();
+        let total = store
+            .map_overlapping_multi(&regions, |i, _| {
+                if visited.last() != Some(&i) {
+                    visited.push(i);
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(visited, [3, 1, 0, 4]);
+
+        let invalid = [("chr1".to_string(), 2000, 1000)];
+        assert!(store.get_overlapping_multi(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_get_overlapping_by_level() {
+        let test_dir = TestDir::new("overlapping_by_level").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create_with_schema(
+            test_dir.path(),
+            None,
+            &BinningSchema::Tabix,
+        )
+        .expect("Failed to create store");
+        // A record per level of increasing size, all overlapping 1000-1001
+        let ranges = [
+            (0, 300_000_000),
+            (0, 60_000_000),
+            (0, 5_000_000),
+            (0, 1_000_000),
+            (0, 100_000),
+            (1000, 1100),
+            (1000, 1200),
+        ];
+        for (start, end) in ranges {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let levels: Vec<(usize, Vec<(u32, u32)>)> = store
+            .get_overlapping_by_level("chr1", 1000, 1001)
+            .unwrap()
+            .into_iter()
+            .map(|(level, records)| {
+                let mut spans: Vec<_> = records.iter().map(|r| (r.start, r.end)).collect();
+                spans.sort();
+                (level, spans)
+            })
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                (0, vec![(0, 300_000_000)]),
+                (1, vec![(0, 60_000_000)]),
+                (2, vec![(0, 5_000_000)]),
+                (3, vec![(0, 1_000_000)]),
+                (4, vec![(0, 100_000)]),
+                (5, vec![(1000, 1100), (1000, 1200)]),
+            ]
+        );
+
+        // Only the coarse records reach past the fine ones
+        let levels = store
+            .get_overlapping_by_level("chr1", 200_000, 200_001)
+            .unwrap();
+        assert_eq!(
+            levels.iter().map(|(level, _)| *level).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        assert!(store
+            .get_overlapping_by_level("chrX", 0, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_contained() {
+        let test_dir = TestDir::new("get_contained").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let ranges = [
+            (500, 900_000), // spans the whole query
+            (900, 1100),    // overlaps the query's start
+            (1000, 1050),   // small, in a fine bin, contained
+            (1200, 2000),   // contained, ending at the query's end
+            (1900, 2100),   // overlaps the query's end
+            (5000, 6000),   // outside
+        ];
+        for (start, end) in ranges {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.get_overlapping("chr1", 1000, 2000).unwrap().len(), 5);
+
+        let mut contained: Vec<_> = store
+            .get_contained("chr1", 1000, 2000)
+            .unwrap()
+            .iter()
+            .map(|record| (record.start, record.end))
+            .collect();
+        contained.sort();
+        assert_eq!(contained, [(1000, 1050), (1200, 2000)]);
+
+        // A query covering everything contains everything
+        assert_eq!(store.get_contained("chr1", 0, 1_000_000).unwrap().len(), 6);
+        assert!(store.get_contained("chr1", 2500, 4000).unwrap().is_empty());
+        assert!(store.get_contained("chrX", 0, 1000).unwrap().is_empty());
+        assert!(store.get_contained("chr1", 2000, 1000).is_err());
+    }
+
+    #[test]
+    fn test_get_overlapping_stranded() {
+        use crate::BedRecord;
+
+        let test_dir = TestDir::new("stranded").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<BedRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        for (start, rest) in [
+            (100, "a\t0\t+"),
+            (200, "b\t0\t-"),
+            (300, "c\t0\t."),
+            (400, "d"),
+        ] {
+            let record = BedRecord {
+                start,
+                end: start + 50,
+                rest: rest.to_string(),
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<BedRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let names = |records: &[BedRecord]| -> Vec<String> {
+            records.iter().map(|r| r.rest[..1].to_string()).collect()
+        };
+        let plus = store
+            .get_overlapping_stranded("chr1", 0, 1000, Strand::Plus)
+            .unwrap();
+        assert_eq!(names(plus), vec!["a"]);
+        let minus = store
+            .get_overlapping_stranded("chr1", 0, 1000, Strand::Minus)
+            .unwrap();
+        assert_eq!(names(minus), vec!["b"]);
+        let both = store
+            .get_overlapping_stranded("chr1", 0, 1000, Strand::Both)
+            .unwrap();
+        assert_eq!(both.len(), 4);
+
+        // The limit counts only the records kept
+        store.set_max_results(Some(1));
+        let plus = store
+            .get_overlapping_stranded("chr1", 0, 1000, Strand::Plus)
+            .unwrap();
+        assert_eq!(names(plus), vec!["a"]);
+        assert!(matches!(
+            store.get_overlapping_stranded("chr1", 0, 1000, Strand::Both),
+            Err(HgIndexError::ResultLimitExceeded { limit: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_features_after() {
+        let test_dir = TestDir::new("features_after").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let ranges = [
+            (1000, 1100),
+            (1500, 1600),
+            (1700, 900_000),
+            (5000, 5100),
+            (5000, 5200),
+            (5000, 5300),
+            (50_000_000, 50_000_100),
+        ];
+        for (start, end) in ranges {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let spans = |records: Vec<MinimalTestRecord>| {
+            records
+                .into_iter()
+                .map(|record| (record.start, record.end))
+                .collect::<Vec<_>>()
+        };
+
+        // 1000-1100 overlaps 1050 but starts before it
+        let (records, next) = store.features_after("chr1", (1050, 0), 2).unwrap();
+        assert_eq!(spans(records), [(1500, 1600), (1700, 900_000)]);
+        assert!(next.is_some());
+
+        // Paging through the whole sequence two at a time, with the records
+        // starting at 5000 split across pages
+        let mut cursor = Some((0, 0));
+        let mut pages = Vec::new();
+        while let Some(after) = cursor {
+            let (records, next) = store.features_after("chr1", after, 2).unwrap();
+            pages.push(spans(records));
+            cursor = next;
+        }
+        assert_eq!(
+            pages,
+            [
+                vec![(1000, 1100), (1500, 1600)],
+                vec![(1700, 900_000), (5000, 5100)],
+                vec![(5000, 5200), (5000, 5300)],
+                vec![(50_000_000, 50_000_100)],
+            ]
+        );
+
+        let (records, next) = store.features_after("chr1", (0, 0), 0).unwrap();
+        assert!(records.is_empty() && next.is_none());
+        let (records, next) = store.features_after("chr1", (60_000_000, 0), 1).unwrap();
+        assert!(records.is_empty() && next.is_none());
+        assert!(store
+            .features_after("chrX", (0, 0), 1)
+            .unwrap()
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn test_first_overlapping() {
+        let test_dir = TestDir::new("first_overlapping").expect("Failed to create test dir");
+        let base_dir = test_dir.path();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(base_dir, None)
+            .expect("Failed to create store");
+        // Two features tie at start 1500, and one large feature lands in a
+        // coarse bin.
+        let ranges = [(1000, 1100), (1500, 1600), (1500, 1700), (3000, 900_000)];
+        for (i, (start, end)) in ranges.into_iter().enumerate() {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: i as f64,
+            };
+            store
+                .add_record("chr1", &record)
+                .expect("Failed to add record");
+        }
+        store.finalize().expect("Failed to finalize store");
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(base_dir, None)
+            .expect("Failed to open store");
+
+        // The earlier-added of the tied features wins.
+        let first = store
+            .first_overlapping("chr1", 1550, 1560)
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.start, first.score), (1500, 1.0));
+
+        let first = store
+            .first_overlapping("chr1", 1050, 5000)
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.start, first.score), (1000, 0.0));
+
+        let first = store
+            .first_overlapping("chr1", 4000, 4100)
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.start, first.score), (3000, 3.0));
+
+        assert!(store
+            .first_overlapping("chr1", 950_000, 960_000)
+            .unwrap()
+            .is_none());
+        assert!(store
+            .first_overlapping("chr2", 1000, 2000)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_overlapping_blocks() {
+        let test_dir = TestDir::new("overlapping_blocks").expect("Failed to create test dir");
+        let base_dir = test_dir.path();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(base_dir, None)
+            .expect("Failed to create store");
+        // An exon, an intron-only feature, and a second exon.
+        for (i, (start, end)) in [(1000, 1200), (1300, 1700), (1800, 2000)]
+            .into_iter()
+            .enumerate()
+        {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: i as f64,
+            };
+            store
+                .add_record("chr1", &record)
+                .expect("Failed to add record");
+        }
+        store.finalize().expect("Failed to finalize store");
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(base_dir, None)
+            .expect("Failed to open store");
+
+        // A read spliced across the intron: the span overlaps all three, but
+        // only the exons overlap a block.
+        let span = store.get_overlapping("chr1", 1100, 1900).unwrap().len();
+        assert_eq!(span, 3);
+        let results = store
+            .get_overlapping_blocks("chr1", &[(1850, 1900), (1100, 1250)])
+            .unwrap();
+        let scores: Vec<f64> = results.iter().map(|r| r.score).collect();
+        assert_eq!(scores, vec![0.0, 2.0]);
+
+        assert!(store
+            .get_overlapping_blocks("chr1", &[])
+            .unwrap()
+            .is_empty());
+        assert!(store
+            .get_overlapping_blocks("chr1", &[(1100, 1100)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_map_overlapping_corrupt_records() {
+        use crate::BedRecord;
+
+        let test_dir = TestDir::new("corrupt_records").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<BedRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        for (start, name) in [(100, "a"), (200, "b"), (300, "c")] {
+            let record = BedRecord {
+                start,
+                end: start + 50,
+                rest: name.to_string(),
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        // Truncate the data file partway into the last record
+        let data_path = test_dir.path().join("chr1.bin");
+        let len = fs::metadata(&data_path).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&data_path)
+            .unwrap()
+            .set_len(len - 2)
+            .unwrap();
+
+        let mut store = GenomicDataStore::<BedRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        // Shrink the second record's stored length below a BedRecord's minimum
+        let sequence = store.index.sequences.get_mut("chr1").unwrap();
+        sequence.thaw();
+        for feature in sequence.bins.values_mut().flatten() {
+            if feature.start == 200 {
+                feature.length = 4;
+            }
+        }
+
+        let mut names = Vec::new();
+        let summary = store
+            .map_overlapping_checked("chr1", 0, 1000, false, |record| {
+                names.push(String::from_utf8(record.rest.to_vec()).unwrap());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            summary,
+            MapSummary {
+                records: 1,
+                corrupt: 2
+            }
+        );
+        assert_eq!(names, vec!["a"]);
+
+        // Skipping is the default; strict mode is opt-in
+        assert_eq!(
+            store.map_overlapping("chr1", 0, 1000, |_| Ok(())).unwrap(),
+            1
+        );
+        assert!(matches!(
+            store.map_overlapping_checked("chr1", 0, 1000, true, |_| Ok(())),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn test_record_reader() {
+        use crate::BedRecord;
+
+        let test_dir = TestDir::new("record_reader").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<BedRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let sequence = "ACGT".repeat(4096);
+        let record = BedRecord {
+            start: 100,
+            end: 100 + sequence.len() as u32,
+            rest: sequence,
+        };
+        store.add_record("chr1", &record).unwrap();
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<BedRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let feature = store.features_overlapping("chr1", 0, 200).unwrap()[0].clone();
+
+        // Stream the payload through a small buffer
+        let mut reader = store
+            .record_reader("chr1", feature.index, feature.length)
+            .unwrap();
+        let mut chunk = [0u8; 1000];
+        let mut payload = Vec::new();
+        loop {
+            let n = reader.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            payload.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(payload, record.to_bytes());
+        drop(reader);
+
+        assert!(matches!(
+            store.record_reader("chr1", feature.index, feature.length - 1),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+        assert!(matches!(
+            store.record_reader("chr1", 1 << 40, 8),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn test_max_record_len() {
+        use crate::BedRecord;
+
+        let test_dir = TestDir::new("max_record_len").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<BedRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        for (start, name) in [(100, "a"), (200, "b"), (300, "c")] {
+            let record = BedRecord {
+                start,
+                end: start + 50,
+                rest: name.to_string(),
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<BedRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        // An absurd length is rejected by the default limit
+        let sequence = store.index.sequences.get_mut("chr1").unwrap();
+        sequence.thaw();
+        for feature in sequence.bins.values_mut().flatten() {
+            if feature.start == 200 {
+                feature.length = 3 << 30;
+            }
+        }
+        match store.get_overlapping("chr1", 0, 1000) {
+            Err(HgIndexError::CorruptRecord { reason, .. }) => {
+                assert!(reason.contains("maximum record length"))
+            }
+            other => panic!("expected CorruptRecord, got {:?}", other.map(|r| r.len())),
+        }
+        assert_eq!(store.get_overlapping("chr1", 0, 150).unwrap().len(), 1);
+
+        // A lower limit catches lengths that still lie within the data file
+        store.set_max_record_len(Some(8));
+        assert!(matches!(
+            store.get_overlapping_batch("chr1", 0, 150),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+        let summary = store
+            .map_overlapping_checked("chr1", 0, 1000, false, |_| Ok(()))
+            .unwrap();
+        assert_eq!(summary.corrupt, 3);
+
+        store.set_max_record_len(None);
+        assert_eq!(store.get_overlapping("chr1", 250, 1000).unwrap().len(), 1);
+
+        // Without a limit, a record extending past the end of the data file is
+        // still corruption, rather than skipped or read out of bounds
+        let past_end = |result: Result<usize, HgIndexError>| match result {
+            Err(HgIndexError::CorruptRecord { reason, .. }) => reason.contains("past the end"),
+            _ => false,
+        };
+        assert!(past_end(
+            store.get_overlapping("chr1", 150, 250).map(|r| r.len())
+        ));
+        assert!(past_end(
+            store
+                .get_overlapping_blocks("chr1", &[(150, 250)])
+                .map(|r| r.len())
+        ));
+        assert!(past_end(
+            store
+                .first_overlapping("chr1", 150, 250)
+                .map(|r| r.into_iter().count())
+        ));
+        assert!(past_end(
+            store
+                .get_overlapping_batch("chr1", 150, 250)
+                .map(|r| r.len())
+        ));
+        assert!(past_end(store.map_overlapping_bytes(
+            "chr1",
+            150,
+            250,
+            |_| Ok(())
+        )));
+    }
+
+    #[test]
+    fn test_write_overlapping() {
+        let (_test_dir, mut store) = packed_test_store("write_overlapping");
+        let mut output = Vec::new();
+        let count = store
+            .write_overlapping("chr1", 1200, 1800, &mut output, |record, writer| {
+                writeln!(writer, "{}\t{}\t{}", record.start, record.end, record.name)?;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1000\t2000\tfeature1\n1500\t2500\tfeature2\n"
+        );
+    }
+
+    #[test]
+    fn test_join_overlapping() {
+        let (_test_dir, mut store) = packed_test_store("join_overlapping");
+        let joined = store
+            .join_overlapping("chr1", &[(900, 1100), (1900, 2100), (5000, 6000)])
+            .unwrap();
+        let pairs: Vec<((u32, u32), &str)> = joined
+            .iter()
+            .map(|(query, record)| (*query, record.name.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ((900, 1100), "feature1"),
+                ((1900, 2100), "feature1"),
+                ((1900, 2100), "feature2"),
+            ]
+        );
+
+        assert!(store.join_overlapping("chr1", &[(10, 5)]).is_err());
+    }
+
+    #[test]
+    fn test_overlapping_names() {
+        let (_test_dir, mut store) = packed_test_store("overlapping_names");
+        let names = store
+            .overlapping_names("chr1", 900, 3000, |record| Some(record.name.to_string()))
+            .unwrap();
+        assert_eq!(
+            names,
+            HashSet::from(["feature1".to_string(), "feature2".to_string()])
+        );
+
+        // Both records share a prefix, so it is reported once
+        let prefixes = store
+            .overlapping_names("chr1", 900, 3000, |record| {
+                Some(record.name.trim_end_matches(char::is_numeric).to_string())
+            })
+            .unwrap();
+        assert_eq!(prefixes, HashSet::from(["feature".to_string()]));
+
+        let coding = store
+            .overlapping_names("chr1", 900, 3000, |record| {
+                record
+                    .tags
+                    .contains(&"coding")
+                    .then(|| record.name.to_string())
+            })
+            .unwrap();
+        assert_eq!(coding, HashSet::from(["feature1".to_string()]));
+    }
+
+    #[test]
+    fn test_map_overlapping_bytes() {
+        let (_test_dir, mut store) = packed_test_store("map_overlapping_bytes");
+
+        let mut payloads = Vec::new();
+        let count = store
+            .map_overlapping_bytes("chr1", 1200, 1800, |bytes| {
+                payloads.push(bytes.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // The raw payloads are exactly what the records serialized to.
+        let expected: Vec<Vec<u8>> = make_test_records()
+            .into_iter()
+            .filter(|(chrom, _)| chrom == "chr1")
+            .map(|(_, record)| record.to_bytes())
+            .collect();
+        assert_eq!(payloads, expected);
+    }
+
+    #[test]
+    fn test_ungrouped_sequence_rejected_when_streaming() {
+        let test_dir = TestDir::new("ungrouped_sequence").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create_streaming(
+            test_dir.path(),
+            None,
+            &BinningSchema::default(),
+        )
+        .expect("Failed to create store");
+        let record = MinimalTestRecord {
+            start: 100,
+            end: 200,
+            score: 1.0,
+        };
+        store.add_record("chr1", &record).unwrap();
+        store.add_record("chr2", &record).unwrap();
+        assert!(matches!(
+            store.add_record("chr1", &record),
+            Err(HgIndexError::UngroupedSequence(chrom)) if chrom == "chr1"
+        ));
+        store.finalize().unwrap();
+
+        // chr1's data file was not truncated
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.get_overlapping("chr1", 0, 1000).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_interleaved_sequences() {
+        let test_dir = TestDir::new("interleaved_sequences").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let record = |start| MinimalTestRecord {
+            start,
+            end: start + 100,
+            score: start as f64,
+        };
+
+        for start in [100, 500, 900] {
+            store.add_record("chr1", &record(start)).unwrap();
+            store.add_record("chr2", &record(start + 1)).unwrap();
+        }
+
+        // With their writers closed, data files are appended to, not re-created
+        store.close_writers().unwrap();
+        store.add_record("chr1", &record(1300)).unwrap();
+        store.add_record("chr2", &record(1301)).unwrap();
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let starts = |store: &mut GenomicDataStore<MinimalTestRecord>, chrom| {
+            store
+                .get_overlapping(chrom, 0, 2000)
+                .unwrap()
+                .iter()
+                .map(|record| record.start)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(&mut store, "chr1"), vec![100, 500, 900, 1300]);
+        assert_eq!(starts(&mut store, "chr2"), vec![101, 501, 901, 1301]);
+    }
+
+    #[test]
+    fn test_disable_linear_index() {
+        let test_dir = TestDir::new("disable_linear_index").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create_with_schema(
+            test_dir.path(),
+            None,
+            &BinningSchema::Tabix,
+        )
+        .expect("Failed to create store");
+        store.disable_linear_index();
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert!(!store.index.has_linear_index());
+        assert!(store
+            .index
+            .sequences
+            .values()
+            .all(|sequence| sequence.linear_index.is_none()));
+        assert_eq!(store.get_overlapping("chr1", 1200, 1800).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_schema_accessors() {
+        let test_dir = TestDir::new("schema_accessors").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create_with_schema(
+            test_dir.path(),
+            None,
+            &BinningSchema::Dense,
+        )
+        .expect("Failed to create store");
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.schema(), &BinningSchema::Dense);
+        assert_eq!(
+            store.hierarchical_bins(),
+            &HierarchicalBins::from_schema(&BinningSchema::Dense)
+        );
+
+        let index_only = IndexOnlyStore::open(&test_dir.path().join("index.bin")).unwrap();
+        assert_eq!(index_only.schema(), &BinningSchema::Dense);
+    }
+
+    #[test]
+    fn test_reindex() {
+        fn score_decile(record: &TestRecordSlice<'_>) -> u32 {
+            (record.score * 10.0) as u32
+        }
+
+        let test_dir = TestDir::new("reindex").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create_with_schema(
+            test_dir.path(),
+            None,
+            &BinningSchema::Tabix,
+        )
+        .expect("Failed to create store");
+        store.set_feature_extra(Some(Box::new(score_decile)));
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        store.finalize_with_metadata(&"meta".to_string()).unwrap();
+        let data_before = fs::read(test_dir.path().join("chr1.bin")).unwrap();
+
+        let count =
+            GenomicDataStore::<TestRecord>::reindex(test_dir.path(), None, &BinningSchema::Sparse)
+                .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            fs::read(test_dir.path().join("chr1.bin")).unwrap(),
+            data_before
+        );
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.schema(), &BinningSchema::Sparse);
+        assert_eq!(store.metadata::<String>().unwrap(), "meta");
+        let names: Vec<String> = store
+            .get_overlapping("chr1", 1200, 1800)
+            .unwrap()
+            .iter()
+            .map(|record| record.name.clone())
+            .collect();
+        assert_eq!(names.len(), 2);
+        let mut extras: Vec<u32> = store
+            .features_overlapping("chr1", 1200, 1800)
+            .unwrap()
+            .iter()
+            .map(|feature| feature.extra)
+            .collect();
+        extras.sort();
+        assert_eq!(extras, vec![5, 8]);
+        assert_eq!(
+            store.get_overlapping("chr2", 50_000, 50_001).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_recover() {
+        let test_dir = TestDir::new("recover").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        // Crash before finalize: the data is written, the index is not
+        drop(store);
+        assert!(!test_dir.path().join("index.bin").exists());
+
+        // ...partway through writing another record
+        let chr1_path = test_dir.path().join("chr1.bin");
+        let chr1_len = fs::metadata(&chr1_path).unwrap().len();
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&chr1_path)
+            .unwrap();
+        file.write_all(&100u64.to_le_bytes()).unwrap();
+        file.write_all(&[0; 10]).unwrap();
+        drop(file);
+
+        let count =
+            GenomicDataStore::<TestRecord>::recover(test_dir.path(), None, &BinningSchema::Tabix)
+                .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(fs::metadata(&chr1_path).unwrap().len(), chr1_len);
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get_overlapping("chr1", 1200, 1800).unwrap().len(), 2);
+        assert_eq!(store.get_at("chr2", 55_000).unwrap()[0].name, "feature3");
+
+        // Corruption other than a cut-short final record is an error
+        fs::write(test_dir.path().join("chr3.bin"), b"NOPE\x01\x00").unwrap();
+        assert!(GenomicDataStore::<TestRecord>::recover(
+            test_dir.path(),
+            None,
+            &BinningSchema::Tabix
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let (test_dir, store) = packed_test_store("verify");
+        let report = store.verify().unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.records, 3);
+
+        // Cut the last chr1 record short, and lose chr2 entirely
+        let chr1_path = test_dir.path().join("chr1.bin");
+        let chr1_len = fs::metadata(&chr1_path).unwrap().len();
+        let file = fs::OpenOptions::new().write(true).open(&chr1_path).unwrap();
+        file.set_len(chr1_len - 5).unwrap();
+        drop(file);
+        fs::remove_file(test_dir.path().join("chr2.bin")).unwrap();
+        fs::write(test_dir.path().join("chr3.bin"), b"GIDX\x01\x00").unwrap();
+
+        let report = store.verify().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.missing_files, vec!["chr2"]);
+        assert_eq!(report.bad_records.len(), 1);
+        assert_eq!(report.bad_records[0].0, "chr1");
+        // The truncated record's remaining bytes, and the unindexed chr3
+        assert_eq!(report.orphaned.len(), 2);
+        assert_eq!(report.orphaned[1], ("chr3".to_string(), 6));
+        assert_eq!(report.orphaned[0].1, chr1_len - 5 - report.bad_records[0].1);
+
+        // An indexed length that would overflow the record's end is bad too
+        let mut store = store;
+        let sequence = store.index.sequences.get_mut("chr1").unwrap();
+        sequence.thaw();
+        for feature in sequence.bins.values_mut().flatten() {
+            feature.length = u64::MAX - 4;
+        }
+        assert_eq!(store.verify().unwrap().bad_records.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_chromosome_and_compact() {
+        let (test_dir, mut store) = packed_test_store("remove_chromosome");
+        assert_eq!(store.get_overlapping("chr1", 0, 10_000).unwrap().len(), 2);
+        assert!(store.remove_chromosome("chr1").unwrap());
+        assert!(!store.remove_chromosome("chr3").unwrap());
+        assert!(!test_dir.path().join("chr1.bin").exists());
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.running_stats().total_features, 1);
+        assert!(store.get_overlapping("chr1", 0, 10_000).unwrap().is_empty());
+
+        // The removed sequence can be added again
+        let replacement = TestRecord {
+            start: 5000,
+            end: 6000,
+            name: "replacement".to_string(),
+            score: 1.0,
+            tags: vec![],
+        };
+        store.add_record("chr1", &replacement).unwrap();
+        store.finalize().unwrap();
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let records = store.get_overlapping("chr1", 0, 10_000).unwrap();
+        assert_eq!(records, [replacement]);
+        assert!(store.remove_chromosome("chr1").unwrap());
+        store.finalize().unwrap();
+        assert_eq!(
+            store.get_overlapping("chr2", 0, 100_000).unwrap()[0].name,
+            "feature3"
+        );
+        assert!(store.verify().unwrap().is_ok());
+
+        // An unindexed tail, as a crashed write would leave, is reclaimed
+        let chr2_path = test_dir.path().join("chr2.bin");
+        let chr2_len = fs::metadata(&chr2_path).unwrap().len();
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&chr2_path)
+            .unwrap();
+        file.write_all(&[0; 20]).unwrap();
+        drop(file);
+        assert_eq!(store.compact().unwrap(), 20);
+        assert_eq!(fs::metadata(&chr2_path).unwrap().len(), chr2_len);
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert!(store.verify().unwrap().is_ok());
+        assert_eq!(
+            store.get_overlapping("chr2", 0, 100_000).unwrap()[0].name,
+            "feature3"
+        );
+    }
+
+    #[test]
+    fn test_compact_error_leaves_store_intact() {
+        let (test_dir, _) = packed_test_store("compact_error");
+
+        // chr1 is compacted first and has a reclaimable tail; chr2 is truncated
+        let chr1_path = test_dir.path().join("chr1.bin");
+        let chr2_path = test_dir.path().join("chr2.bin");
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&chr1_path)
+            .unwrap();
+        file.write_all(&[0; 20]).unwrap();
+        drop(file);
+        let chr2 = fs::read(&chr2_path).unwrap();
+        fs::write(&chr2_path, &chr2[..chr2.len() - 1]).unwrap();
+        let chr1 = fs::read(&chr1_path).unwrap();
+        let index = fs::read(test_dir.path().join("index.bin")).unwrap();
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert!(matches!(
+            store.compact(),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+        assert_eq!(fs::read(&chr1_path).unwrap(), chr1);
+        assert_eq!(fs::read(test_dir.path().join("index.bin")).unwrap(), index);
+        for entry in fs::read_dir(test_dir.path()).unwrap() {
+            let path = entry.unwrap().path();
+            assert!(!path.to_string_lossy().ends_with(".tmp"), "{:?}", path);
+        }
+        assert_eq!(store.get_overlapping("chr1", 0, 10_000).unwrap().len(), 2);
+
+        // As does an indexed length that would overflow the record's end
+        let sequence = store.index.sequences.get_mut("chr1").unwrap();
+        sequence.thaw();
+        for feature in sequence.bins.values_mut().flatten() {
+            feature.length = u64::MAX - 4;
+        }
+        assert!(matches!(
+            store.compact(),
+            Err(HgIndexError::CorruptRecord { .. })
+        ));
+        assert_eq!(fs::read(&chr1_path).unwrap(), chr1);
+    }
+
+    #[test]
+    fn test_open_append() {
+        let (test_dir, mut store) = packed_test_store("open_append");
+        // A sequence indexed without features has no data file
+        let empty = crate::SequenceIndex::new(&store.index.bins);
+        store.index.sequences.insert("chr4".to_string(), empty);
+        store.finalize().unwrap();
+
+        let record = |start: u32, name: &str| TestRecord {
+            start,
+            end: start + 500,
+            name: name.to_string(),
+            score: 0.0,
+            tags: vec![],
+        };
+        let mut store = GenomicDataStore::<TestRecord>::open_append(test_dir.path(), None)
+            .expect("Failed to open store for appending");
+        store.add_record("chr1", &record(3000, "feature4")).unwrap();
+        store.add_record("chr3", &record(100, "feature5")).unwrap();
+        store.add_record("chr4", &record(100, "feature6")).unwrap();
+        // Before the last stored chr2 start, though nothing was added to chr2 yet
+        assert!(matches!(
+            store.add_record("chr2", &record(40_000, "unsorted")),
+            Err(HgIndexError::UnsortedFeatures { .. })
+        ));
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.len(), 6);
+        let names: Vec<String> = store
+            .get_overlapping("chr1", 0, 10_000)
+            .unwrap()
+            .iter()
+            .map(|record| record.name.clone())
+            .collect();
+        assert_eq!(names, ["feature1", "feature2", "feature4"]);
+        assert_eq!(store.get_at("chr3", 200).unwrap()[0].name, "feature5");
+        assert_eq!(store.get_at("chr4", 200).unwrap()[0].name, "feature6");
+        assert_eq!(store.get_at("chr2", 55_000).unwrap()[0].name, "feature3");
+        assert_eq!(
+            &fs::read(test_dir.path().join("chr1.bin")).unwrap()[..6],
+            b"GIDX\x01\x00"
+        );
+
+        // The rejected record wrote nothing
+        assert!(store.verify().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_data_file_header() {
+        let (test_dir, _) = packed_test_store("data_file_header");
+
+        let chr1_path = test_dir.path().join("chr1.bin");
+        let data = fs::read(&chr1_path).unwrap();
+        assert_eq!(&data[..6], b"GIDX\x01\x00");
+
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        store.open_chrom_file("chr1").unwrap();
+
+        // A future layout version or flag is rejected rather than misread
+        for (byte, value, message) in [(4, 2, "version"), (5, 1, "flags")] {
+            let mut patched = data.clone();
+            patched[byte] = value;
+            fs::write(&chr1_path, &patched).unwrap();
+            let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+                .expect("Failed to open store");
+            let error = store.open_chrom_file("chr1").unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+
+            // Queries fail too, rather than finding no records
+            let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+                .expect("Failed to open store");
+            let error = store.get_overlapping("chr1", 0, 1_000_000).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+
+        // A pre-versioned file, with only the magic, is rejected the same way
+        fs::write(&chr1_path, &data[..4]).unwrap();
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert!(store.get_overlapping("chr1", 0, 1_000_000).is_err());
+
+        // ...and one in the legacy layout, records right after the bare magic,
+        // is reported as such
+        let mut legacy = data[..4].to_vec();
+        legacy.extend_from_slice(&data[6..]);
+        fs::write(&chr1_path, &legacy).unwrap();
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let error = store.get_overlapping("chr1", 0, 1_000_000).unwrap_err();
+        assert!(
+            error.to_string().contains("Unversioned data file"),
+            "{}",
+            error
+        );
+        let error = GenomicDataStore::<TestRecord>::open_append(test_dir.path(), None)
+            .err()
+            .unwrap();
+        assert!(
+            error.to_string().contains("Unversioned data file"),
+            "{}",
+            error
+        );
+
+        // Only a missing data file means no records
+        fs::remove_file(&chr1_path).unwrap();
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let records = store.get_overlapping("chr1", 0, 1_000_000).unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn test_iter_chrom() {
+        let test_dir = TestDir::new("iter_chrom").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        // A long feature binned coarsely, so index order differs from stored order
+        for (start, end) in [(100, 200), (150, 5_000_000), (300, 400)] {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
+
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let spans: Vec<_> = store
+            .iter_chrom("chr1")
+            .unwrap()
+            .map(|record| (record.start, record.end))
+            .collect();
+        assert_eq!(spans, [(100, 200), (150, 5_000_000), (300, 400)]);
+        assert_eq!(store.iter_chrom("chrX").unwrap().count(), 0);
+
+        // A record cut short at the end of the file ends iteration cleanly
+        let chr1_path = test_dir.path().join("chr1.bin");
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&chr1_path)
+            .unwrap();
+        file.write_all(&100u64.to_le_bytes()).unwrap();
+        drop(file);
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert_eq!(store.iter_chrom("chr1").unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_empty_sequence_not_opened() {
+        let (_test_dir, mut store) = packed_test_store("empty_sequence");
+        // chr3 is present in the index but has no features (and no data file)
+        let empty = crate::SequenceIndex::new(&store.index.bins);
+        store.index.sequences.insert("chr3".to_string(), empty);
+
+        assert!(store.get_overlapping("chr3", 0, 1000).unwrap().is_empty());
+        assert!(store.first_overlapping("chr3", 0, 1000).unwrap().is_none());
+        assert_eq!(
+            store.map_overlapping("chr3", 0, 1000, |_| Ok(())).unwrap(),
+            0
+        );
+        assert!(!store.data_files.contains_key("chr3"));
+
+        let stats = crate::stats::BinningStats::analyze(&store.index);
+        assert_eq!(stats.total_features, 3);
+        assert!(stats.bin_density.is_finite());
+    }
+
+    #[test]
+    fn test_prewarm() {
+        let (_test_dir, mut store) = packed_test_store("prewarm");
+        store.get_overlapping("chr2", 50_000, 100_000).unwrap();
+        store.prewarm(&["chr1", "chr2", "chrUnknown"]).unwrap();
+        assert_eq!(store.open_file_count(), 2);
+        assert!(!store.data_files.contains_key("chrUnknown"));
+        assert_eq!(store.get_overlapping("chr1", 1200, 1800).unwrap().len(), 2);
+        assert_eq!(
+            store
+                .get_overlapping("chr2", 50_000, 100_000)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_get_overlapping_stream() {
+        use std::sync::{Arc, Mutex};
+        use tokio_stream::StreamExt;
 
-        // An example key
-        let key = "example-key".to_string();
+        let (_test_dir, store) = packed_test_store("overlapping_stream");
+        let store = Arc::new(Mutex::new(store));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let records: Vec<_> =
+                GenomicDataStore::get_overlapping_stream(store.clone(), "chr1", 1200, 1800)
+                    .collect()
+                    .await;
+            let mut names: Vec<String> = records.into_iter().map(|r| r.unwrap().name).collect();
+            names.sort();
+            assert_eq!(names, vec!["feature1", "feature2"]);
 
-        // Create store and add records
-        let mut store = GenomicDataStore::<TestRecord>::create(base_dir, Some(key.clone()))
+            let mut invalid = GenomicDataStore::get_overlapping_stream(store, "chr1", 10, 5);
+            assert!(matches!(
+                invalid.next().await,
+                Some(Err(HgIndexError::InvalidInterval { .. }))
+            ));
+            assert!(invalid.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_map_sequence() {
+        let test_dir = TestDir::new("map_sequence").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
             .expect("Failed to create store");
         for (chrom, record) in make_test_records() {
-            store
-                .add_record(&chrom, &record)
-                .expect("Failed to add record");
+            store.add_record(&chrom, &record).unwrap();
         }
+        // Spread over different bins, so bin order differs from start order
+        for (start, end, name) in [(100, 200, "narrow"), (3000, 10_000_000, "wide")] {
+            let record = TestRecord {
+                start,
+                end,
+                name: name.to_string(),
+                score: 0.0,
+                tags: vec![],
+            };
+            store.add_record("chr10", &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        store.finalize().expect("Failed to finalize store");
-
-        let mut store = GenomicDataStore::<TestRecord>::open(&base_dir, Some(key.clone()))
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
             .expect("Failed to open store");
+        assert_eq!(store.sequence_names(), vec!["chr1", "chr2", "chr10"]);
 
-        // Test overlapping query
-        let results = store.get_overlapping("chr1", 1200, 1800).unwrap();
-        assert_eq!(results.len(), 2); // Should get both chr1 features
-        assert_eq!(results[0].name, "feature1");
-        assert_eq!(results[1].name, "feature2");
+        let mut names = Vec::new();
+        let count = store
+            .map_sequence("chr10", |record| {
+                names.push(record.name.to_string());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(names, vec!["narrow", "wide"]);
+        assert_eq!(store.map_sequence("chrUnknown", |_| Ok(())).unwrap(), 0);
+    }
 
-        // Test non-overlapping region
-        let results = store.get_overlapping("chr1", 3000, 4000).unwrap();
-        assert_eq!(results.len(), 0);
+    #[test]
+    fn test_lowercase_chroms() {
+        let test_dir = TestDir::new("lowercase_chroms").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        store.set_lowercase_chroms(true);
+        let records = make_test_records();
+        // Mixed case within one sequence still groups as one sequence
+        store.add_record("CHR1", &records[0].1).unwrap();
+        store.add_record("Chr1", &records[1].1).unwrap();
+        store.add_record("chr2", &records[2].1).unwrap();
+        store.finalize().unwrap();
+        assert!(test_dir.path().join("chr1.bin").exists());
 
-        // Test different chromosome
-        let results = store.get_overlapping("chr2", 55000, 58000).unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].name, "feature3");
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        for chrom in ["chr1", "Chr1", "CHR1"] {
+            assert_eq!(store.get_overlapping(chrom, 1200, 1800).unwrap().len(), 2);
+        }
+        assert_eq!(
+            store
+                .get_overlapping("CHR2", 50_000, 100_000)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Case-sensitive by default
+        let test_dir = TestDir::new("case_sensitive_chroms").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        store.add_record("chr1", &records[0].1).unwrap();
+        store.finalize().unwrap();
+        let mut store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        assert!(store
+            .get_overlapping("Chr1", 1200, 1800)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_invalid_file() {
-        let test_dir = TestDir::new("invalid_file").expect("Failed to create test dir");
-        let bad_file = test_dir.path().join("bad.gidx");
+    fn test_feature_extra() {
+        // A capturing closure, as for a lookup table
+        let bins = 10.0;
+        let test_dir = TestDir::new("feature_extra").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<TestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        store.set_feature_extra(Some(Box::new(move |record: &TestRecordSlice<'_>| {
+            (record.score * bins) as u32
+        })));
+        for (chrom, record) in make_test_records() {
+            store.add_record(&chrom, &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        // Create file with invalid magic number
-        let mut file = File::create(&bad_file).expect("Failed to create file");
-        file.write_all(b"BAD!").expect("Failed to write");
+        let store = GenomicDataStore::<TestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let mut extras: Vec<u32> = store
+            .features_overlapping("chr1", 1200, 1800)
+            .unwrap()
+            .iter()
+            .map(|feature| feature.extra)
+            .collect();
+        extras.sort();
+        assert_eq!(extras, vec![5, 8]);
 
-        // Attempt to open should fail
-        let result = GenomicDataStore::<TestRecord>::open(&bad_file, None);
-        assert!(result.is_err());
+        let index_only = IndexOnlyStore::open(&test_dir.path().join("index.bin")).unwrap();
+        let features = index_only
+            .features_overlapping("chr2", 50_000, 100_000)
+            .unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].extra, 3);
     }
 
     #[test]
-    fn test_empty_regions() {
-        let test_dir = TestDir::new("empty_regions").expect("Failed to create test dir");
-        let store_path = test_dir.path().join("empty.gidx");
+    fn test_max_results() {
+        let (_test_dir, mut store) = packed_test_store("max_results");
+        store.set_max_results(Some(1));
+        assert!(matches!(
+            store.get_overlapping("chr1", 1200, 1800),
+            Err(HgIndexError::ResultLimitExceeded { limit: 1 })
+        ));
+        assert!(matches!(
+            store.map_overlapping("chr1", 1200, 1800, |_| Ok(())),
+            Err(HgIndexError::ResultLimitExceeded { .. })
+        ));
+        assert!(store.features_overlapping("chr1", 1200, 1800).is_err());
+        // Queries within the limit are unaffected
+        assert_eq!(
+            store.get_overlapping("chr2", 50_000, 60_000).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            store
+                .get_overlapping_batch("chr1", 1000, 1200)
+                .unwrap()
+                .len(),
+            1
+        );
 
-        let mut store = GenomicDataStore::<TestRecord>::create(&store_path, None)
+        store.set_max_results(Some(2));
+        assert_eq!(store.get_overlapping("chr1", 1200, 1800).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_read_batch_size() {
+        let test_dir = TestDir::new("read_batch_size").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
             .expect("Failed to create store");
+        for i in 0..1000 {
+            let record = MinimalTestRecord {
+                start: i * 100,
+                end: i * 100 + 50 + (i % 7) * 1000,
+                score: i as f64,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        store.finalize().expect("Failed to finalize store");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let query = |store: &mut GenomicDataStore<MinimalTestRecord>| {
+            store
+                .get_overlapping_batch("chr1", 5_000, 60_000)
+                .unwrap()
+                .iter()
+                .map(|record| (record.start(), record.end()))
+                .collect::<Vec<_>>()
+        };
+        let expected = query(&mut store);
+        assert!(expected.len() > 500);
+        // Chunking and prefetching leave the results and their order unchanged
+        for batch_size in [1, 7, 32, 10_000] {
+            store.set_read_batch_size(Some(batch_size));
+            assert_eq!(query(&mut store), expected, "batch size {}", batch_size);
+        }
+    }
 
-        // Query empty store
-        let mut store =
-            GenomicDataStore::<TestRecord>::open(&store_path, None).expect("Failed to open store");
+    #[test]
+    fn test_map_overlapping_sorted() {
+        let test_dir = TestDir::new("map_overlapping_sorted").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        // The long feature lands in a coarser bin than its neighbors, while
+        // both features starting at 120 share a bin
+        for (start, end) in [
+            (100, 200),
+            (120, 400),
+            (120, 130),
+            (150, 1_000_000),
+            (150, 160),
+            (300, 400),
+        ] {
+            let record = MinimalTestRecord {
+                start,
+                end,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        let results = store.get_overlapping("chr1", 0, 1000).unwrap();
-        assert_eq!(results.len(), 0);
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        let mut spans = Vec::new();
+        let count = store
+            .map_overlapping_sorted("chr1", 0, 500, |record| {
+                spans.push((record.start, record.end));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 6);
+        // Ties on start are ordered by end, not the order records were added
+        assert_eq!(
+            spans,
+            [
+                (100, 200),
+                (120, 130),
+                (120, 400),
+                (150, 160),
+                (150, 1_000_000),
+                (300, 400)
+            ]
+        );
     }
 
     #[test]
-    fn test_concurrent_reads() {
-        use std::sync::Arc;
-        use std::thread;
+    fn test_get_overlapping_frac() {
+        let (_test_dir, mut store) = packed_test_store("overlapping_frac");
+        let mut names = |min_frac, reciprocal| {
+            let mut names: Vec<String> = store
+                .get_overlapping_frac("chr1", 1500, 2000, min_frac, reciprocal)
+                .unwrap()
+                .iter()
+                .map(|record| record.name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+        // [1500, 2000) covers exactly half of both 1000-bp features
+        assert_eq!(names(0.5, false), ["feature1", "feature2"]);
+        assert!(names(0.51, false).is_empty());
+        // ...and the overlap is the whole 500-bp query
+        assert_eq!(names(0.5, true), ["feature1", "feature2"]);
+        assert_eq!(names(0.0, false), ["feature1", "feature2"]);
 
-        let test_dir = TestDir::new("concurrent").expect("Failed to create test dir");
-        let store_path = test_dir.path().join("test.gidx");
+        // Of the 300-bp query [1800, 2100), feature1 overlaps 200 bp (0.2 of
+        // it, 2/3 of the query) and feature2 all 300 (0.3 of it)
+        let results = store
+            .get_overlapping_frac("chr1", 1800, 2100, 0.2, false)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        let results = store
+            .get_overlapping_frac("chr1", 1800, 2100, 0.3, false)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "feature2");
+        let results = store
+            .get_overlapping_frac("chr1", 1800, 2100, 0.7, true)
+            .unwrap();
+        assert!(results.is_empty());
+        let results = store
+            .get_overlapping_frac("chr1", 1800, 1900, 0.1, true)
+            .unwrap();
+        assert_eq!(results.len(), 2);
 
-        // Create and populate store
-        {
-            let mut store = GenomicDataStore::<MinimalTestRecord>::create(&store_path, None)
-                .expect("Failed to create store");
+        assert!(store
+            .get_overlapping_frac("chr1", 1500, 2000, 1.5, false)
+            .is_err());
+    }
 
-            // Add some overlapping records
-            for i in 0..10 {
-                let start = i * 1000;
-                let end = (i + 2) * 1000; // Overlapping regions
-                store
-                    .add_record(
-                        "chr1",
-                        &MinimalTestRecord {
-                            start,
-                            end,
-                            score: i as f64,
-                        },
-                    )
-                    .expect("Failed to add record");
-            }
-            store.finalize().expect("Failed to finalize");
-        }
+    #[test]
+    fn test_get_at() {
+        let (_test_dir, mut store) = packed_test_store("get_at");
+        let names = |results: &[TestRecord]| {
+            results
+                .iter()
+                .map(|record| record.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(store.get_at("chr1", 1000).unwrap()), ["feature1"]);
+        assert_eq!(
+            names(store.get_at("chr1", 1500).unwrap()),
+            ["feature1", "feature2"]
+        );
+        // Ends are exclusive
+        assert_eq!(names(store.get_at("chr1", 2000).unwrap()), ["feature2"]);
+        assert!(store.get_at("chr1", 999).unwrap().is_empty());
+        assert!(store.get_at("chr1", u32::MAX).unwrap().is_empty());
+    }
 
-        // Create path that can be shared between threads
-        let path = Arc::new(store_path);
+    #[test]
+    fn test_get_overlapping_touching() {
+        let (_test_dir, mut store) = packed_test_store("overlapping_touching");
+        // feature2 (1500-2500) is book-ended by [2500, 3000), not overlapping
+        assert!(store
+            .get_overlapping("chr1", 2500, 3000)
+            .unwrap()
+            .is_empty());
+        let touching = store.get_overlapping_touching("chr1", 2500, 3000).unwrap();
+        assert_eq!(touching.len(), 1);
+        assert_eq!(touching[0].name, "feature2");
 
-        // Spawn multiple reader threads
-        let handles: Vec<_> = (0..4)
-            .map(|i| {
-                let path = Arc::clone(&path);
-                thread::spawn(move || {
-                    let mut store = GenomicDataStore::<MinimalTestRecord>::open(&path, None)
-                        .expect("Failed to open store");
+        // feature1 (1000-2000) starts exactly at the query end
+        let touching = store.get_overlapping_touching("chr1", 500, 1000).unwrap();
+        assert_eq!(touching.len(), 1);
+        assert_eq!(touching[0].name, "feature1");
 
-                    // Each thread queries a different but overlapping region
-                    let start = i * 500;
-                    let end = start + 2000;
-                    let results = store.get_overlapping("chr1", start, end).unwrap();
+        assert!(store
+            .get_overlapping_touching("chr1", 2501, 3000)
+            .unwrap()
+            .is_empty());
+        assert!(store.get_overlapping_touching("chr1", 10, 10).is_err());
+    }
 
-                    // Results should not be empty due to overlapping regions
-                    assert!(!results.is_empty());
-                    results.len()
-                })
-            })
-            .collect();
+    #[test]
+    fn test_index_only_store() {
+        let (test_dir, _) = packed_test_store("index_only_store");
 
-        // Verify all threads completed successfully
-        let result_counts: Vec<_> = handles
-            .into_iter()
-            .map(|h| h.join().expect("Thread panicked"))
-            .collect();
+        // Only the index is needed
+        fs::remove_file(test_dir.path().join("chr1.bin")).unwrap();
+        fs::remove_file(test_dir.path().join("chr2.bin")).unwrap();
 
-        // Verify that at least some threads got different numbers of results
-        // due to querying different regions
-        assert!(result_counts.iter().any(|&x| x != result_counts[0]));
+        let index = IndexOnlyStore::open(&test_dir.path().join("index.bin")).unwrap();
+        assert_eq!(index.count_overlapping("chr1", 1200, 1800).unwrap(), 2);
+        assert_eq!(index.count_overlapping("chr1", 2200, 2300).unwrap(), 1);
+        assert_eq!(index.count_overlapping("chr3", 0, 1000).unwrap(), 0);
+
+        let mut spans = index.spans_overlapping("chr1", 1200, 1800).unwrap();
+        spans.sort();
+        assert_eq!(spans, vec![(1000, 2000), (1500, 2500)]);
+
+        assert!(index.count_overlapping("chr1", 10, 5).is_err());
+        assert_eq!(index.stats().total_features, 3);
     }
 
     #[test]
-    fn test_map_vs_get_consistency() {
-        let test_dir = TestDir::new("map_vs_get_consistency").expect("Failed to create test dir");
-        let base_dir = test_dir.path();
+    fn test_index_only_store_matches_find_overlapping() {
+        let test_dir = TestDir::new("index_only_wide").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        // Widths spanning several bin levels
+        for i in 0..2000u32 {
+            let start = i * 500;
+            let record = MinimalTestRecord {
+                start,
+                end: start + 50 + (i * 7919) % 200_000,
+                score: 0.0,
+            };
+            store.add_record("chr1", &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        // Create the store and add test records
-        let mut store = GenomicDataStore::<TestRecord>::create(base_dir, None)
-            .expect("Failed to create GenomicDataStore");
-        for (chrom, record) in make_test_records() {
-            store
-                .add_record(&chrom, &record)
-                .expect("Failed to add record");
+        // Queries spanning several bins, checked against `find_overlapping`,
+        // which gathers candidate bins with `region_to_bins`
+        let index = IndexOnlyStore::open(&test_dir.path().join("index.bin")).unwrap();
+        for start in (0..1_000_000).step_by(77_777) {
+            for width in [5_000, 40_000, 300_000] {
+                let mut expected = index.index().find_overlapping("chr1", start, start + width);
+                let mut found: Vec<(u64, u64)> = index
+                    .features_overlapping("chr1", start, start + width)
+                    .unwrap()
+                    .into_iter()
+                    .map(|feature| (feature.index, feature.length))
+                    .collect();
+                expected.sort();
+                found.sort();
+                assert_eq!(found, expected);
+            }
         }
-        store.finalize().expect("Failed to finalize store");
+    }
 
-        // Reopen the finalized store
-        let mut store = GenomicDataStore::<TestRecord>::open(base_dir, None)
-            .expect("Failed to open GenomicDataStore");
+    #[test]
+    fn test_multi_store() {
+        let (genes_dir, _) = packed_test_store("multi_store_genes");
+        let repeats_dir = TestDir::new("multi_store_repeats").expect("Failed to create test dir");
 
-        // Define test queries
-        let queries = vec![
-            ("chr1", 1200, 1800),
-            ("chr1", 0, 3000),
-            ("chr2", 50000, 60000),
-            ("chr2", 55000, 58000),
-            ("chr3", 0, 10000),
-        ];
+        let mut store = GenomicDataStore::<TestRecord>::create(repeats_dir.path(), None)
+            .expect("Failed to create store");
+        let repeat = TestRecord {
+            start: 1800,
+            end: 1900,
+            name: "repeat1".to_string(),
+            score: 0.0,
+            tags: vec![],
+        };
+        store.add_record("chr1", &repeat).unwrap();
+        store.finalize().unwrap();
 
-        for (chrom, start, end) in queries {
-            // Get overlapping records
-            let get_results = store.get_overlapping(chrom, start, end).unwrap().to_vec();
+        let mut stores =
+            MultiStore::<TestRecord>::open(&[genes_dir.path(), repeats_dir.path()]).unwrap();
+        assert_eq!(stores.len(), 2);
+        let names: Vec<_> = stores.names().collect();
+        assert_eq!(names[1], repeats_dir.path().display().to_string());
 
-            // Map overlapping records
-            let mut map_results = Vec::new();
-            store
-                .map_overlapping(chrom, start, end, |record| {
-                    map_results.push(record.to_owned());
-                    Ok(())
-                })
-                .unwrap();
+        let results = stores.get_overlapping_union("chr1", 1850, 1860).unwrap();
+        let mut tagged: Vec<_> = results
+            .iter()
+            .map(|(source, record)| (*source, record.name.as_str()))
+            .collect();
+        tagged.sort();
+        assert_eq!(tagged, [(0, "feature1"), (0, "feature2"), (1, "repeat1")]);
 
-            // Assert that both results are identical
-            assert_eq!(
-                get_results, map_results,
-                "Mismatch for chrom: {}, start: {}, end: {}",
-                chrom, start, end
-            );
-        }
+        // A sequence missing from one store still queries the others
+        let results = stores.get_overlapping_union("chr2", 50000, 50100).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 0);
     }
 
     #[test]
-    fn test_metadata_storage_and_retrieval() {
-        use std::collections::HashMap;
-        let test_dir = TestDir::new("metadata_test").expect("Failed to create test dir");
-        let base_dir = test_dir.path();
+    fn test_max_open_files() {
+        let test_dir = TestDir::new("max_open_files").expect("Failed to create test dir");
+        let mut store = GenomicDataStore::<MinimalTestRecord>::create(test_dir.path(), None)
+            .expect("Failed to create store");
+        let chroms = ["chr1", "chr2", "chr3"];
+        for (i, chrom) in chroms.iter().enumerate() {
+            let record = MinimalTestRecord {
+                start: 100,
+                end: 200,
+                score: i as f64,
+            };
+            store.add_record(chrom, &record).unwrap();
+        }
+        store.finalize().unwrap();
 
-        // Create some test metadata (using a simple struct)
-        #[derive(Debug, Serialize, Deserialize, PartialEq)]
-        struct TestMetadata {
-            name: String,
-            values: HashMap<String, i32>,
+        let mut store = GenomicDataStore::<MinimalTestRecord>::open(test_dir.path(), None)
+            .expect("Failed to open store");
+        store.set_max_open_files(Some(2));
+
+        for chrom in chroms {
+            assert_eq!(store.get_overlapping(chrom, 0, 1000).unwrap().len(), 1);
+            assert!(store.open_file_count() <= 2);
         }
 
-        let original_metadata = TestMetadata {
-            name: "test".to_string(),
-            values: {
-                let mut m = HashMap::new();
-                m.insert("key1".to_string(), 42);
-                m.insert("key2".to_string(), 100);
-                m
-            },
-        };
+        // chr2 and chr3 are open; touching chr2 makes chr3 the LRU file,
+        // so reopening chr1 should evict chr3
+        store.get_overlapping("chr2", 0, 1000).unwrap();
+        store.get_overlapping("chr1", 0, 1000).unwrap();
+        assert!(store.data_files.contains_key("chr1"));
+        assert!(store.data_files.contains_key("chr2"));
+        assert!(!store.data_files.contains_key("chr3"));
 
-        // Create and populate store
-        {
-            let mut store = GenomicDataStore::<TestRecord>::create(base_dir, None)
-                .expect("Failed to create store");
+        // Evicted files are transparently reopened
+        let results = store.get_overlapping("chr3", 0, 1000).unwrap();
+        assert_eq!(results[0].score, 2.0);
+        assert_eq!(store.open_file_count(), 2);
+    }
 
-            // Add some test records
-            let record = TestRecord {
-                start: 1000,
-                end: 2000,
-                name: "feature1".to_string(),
-                score: 0.5,
-                tags: vec!["test".to_string()],
-            };
-            store
-                .add_record("chr1", &record)
-                .expect("Failed to add record");
+    #[test]
+    fn test_chrom_aliases() {
+        let test_dir = TestDir::new("chrom_aliases_store").expect("Failed to create test dir");
+        let base_dir = test_dir.path();
 
-            // Finalize with metadata
+        let mut aliases = ChromAliases::new();
+        aliases.insert("1", "chr1");
+        aliases.insert("NC_000001.11", "chr1");
+
+        let mut store =
+            GenomicDataStore::<TestRecord>::create(base_dir, None).expect("Failed to create store");
+        store.add_chrom_aliases(aliases);
+        for (chrom, record) in make_test_records() {
             store
-                .finalize_with_metadata(&original_metadata)
-                .expect("Failed to finalize with metadata");
+                .add_record(&chrom, &record)
+                .expect("Failed to add record");
         }
+        store.finalize().expect("Failed to finalize store");
 
-        // Reopen and check metadata
-        {
-            let store =
-                GenomicDataStore::<TestRecord>::open(base_dir, None).expect("Failed to open store");
-
-            let retrieved_metadata: Option<TestMetadata> = store.metadata();
-            assert!(retrieved_metadata.is_some());
+        // The alias table persists, so queries by any alias resolve to chr1.
+        let mut store =
+            GenomicDataStore::<TestRecord>::open(base_dir, None).expect("Failed to open store");
+        assert_eq!(store.chrom_aliases().resolve("1"), "chr1");
+        assert_eq!(store.get_overlapping("1", 1200, 1800).unwrap().len(), 2);
+        assert_eq!(
+            store
+                .get_overlapping("NC_000001.11", 1200, 1800)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(store.get_overlapping("chr1", 1200, 1800).unwrap().len(), 2);
 
-            let retrieved_metadata = retrieved_metadata.unwrap();
-            assert_eq!(retrieved_metadata, original_metadata);
-            assert_eq!(retrieved_metadata.name, "test");
-            assert_eq!(retrieved_metadata.values.get("key1"), Some(&42));
-            assert_eq!(retrieved_metadata.values.get("key2"), Some(&100));
-        }
+        // Aliases can also be added at read time.
+        let mut aliases = ChromAliases::new();
+        aliases.insert("2", "chr2");
+        store.add_chrom_aliases(aliases);
+        assert_eq!(store.get_overlapping("2", 55000, 58000).unwrap().len(), 1);
     }
 }
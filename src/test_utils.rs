@@ -1,6 +1,7 @@
 // test_utils.rs
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 pub mod test_utils {
     use std::env;
     use std::path::{Path, PathBuf};
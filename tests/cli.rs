@@ -0,0 +1,166 @@
+// End-to-end tests of the hgidx binary's commands.
+#![cfg(feature = "cli")]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+fn hgidx<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(env!("CARGO_BIN_EXE_hgidx"))
+        .args(args)
+        .output()
+        .expect("Failed to run hgidx")
+}
+
+fn write_bed(dir: &Path, name: &str, lines: &[&str]) -> String {
+    let path = dir.join(name);
+    fs::write(&path, lines.join("\n") + "\n").unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+// Run `query` on a store, returning its standard output
+fn query(store: &str, args: &[&str]) -> String {
+    let output = hgidx(["query", "--input", store].iter().chain(args));
+    assert!(
+        output.status.success(),
+        "query failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_pack_multiple_inputs() {
+    let dir = TempDir::new().unwrap();
+    let chr1 = write_bed(
+        dir.path(),
+        "chr1.bed",
+        &["chr1\t10\t20\ta", "chr1\t30\t40\tb"],
+    );
+    let chr2 = write_bed(dir.path(), "chr2.bed", &["chr2\t5\t50\tc"]);
+    let store = dir.path().join("genome.hgidx");
+    let store = store.to_str().unwrap();
+
+    let output = hgidx(["pack", &chr1, &chr2, "-o", store]);
+    assert!(output.status.success());
+    assert_eq!(
+        query(store, &["chr1:1-100"]),
+        "chr1\t10\t20\ta\nchr1\t30\t40\tb\n"
+    );
+    assert_eq!(query(store, &["chr2:1-100"]), "chr2\t5\t50\tc\n");
+
+    // A sequence split across non-adjacent files isn't grouped
+    let more_chr1 = write_bed(dir.path(), "chr1_more.bed", &["chr1\t50\t60\td"]);
+    let output = hgidx([
+        "pack",
+        &chr1,
+        &chr2,
+        &more_chr1,
+        "-o",
+        store,
+        "--force",
+        "--streaming-index",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("chr1_more.bed"), "{}", stderr);
+
+    // Multiple inputs need an explicit output path
+    assert!(!hgidx(["pack", &chr1, &chr2]).status.success());
+}
+
+#[test]
+fn test_pack_expect_records() {
+    let dir = TempDir::new().unwrap();
+    let bed = write_bed(dir.path(), "in.bed", &["chr1\t10\t20\ta", "chr2\t5\t50\tc"]);
+    let store = dir.path().join("in.hgidx");
+    let store = store.to_str().unwrap();
+
+    let output = hgidx(["pack", &bed, "-o", store, "--expect-records", "2"]);
+    assert!(output.status.success());
+
+    let output = hgidx([
+        "pack",
+        &bed,
+        "-o",
+        store,
+        "--force",
+        "--expect-records",
+        "3",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Expected 3 records, but 2 were indexed."),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_pack_chroms() {
+    let dir = TempDir::new().unwrap();
+    let bed = write_bed(
+        dir.path(),
+        "in.bed",
+        &["chr1\t10\t20\ta", "chr1\t30\t40\tb", "chr2\t5\t50\tc"],
+    );
+    let store = dir.path().join("in.hgidx");
+    let store = store.to_str().unwrap();
+
+    let output = hgidx(["pack", &bed, "-o", store, "--chroms", "chr2"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Skipped 2 records on chromosomes not given to --chroms."),
+        "{}",
+        stderr
+    );
+    assert_eq!(query(store, &["chr1:1-100"]), "");
+    assert_eq!(query(store, &["chr2:1-100"]), "chr2\t5\t50\tc\n");
+}
+
+#[test]
+fn test_query_json_formats() {
+    let dir = TempDir::new().unwrap();
+    let bed = write_bed(
+        dir.path(),
+        "in.bed",
+        &[
+            "chr1\t10\t20\ta\t1",
+            "chr1\t30\t40\tb\t2",
+            "chr2\t5\t50\tc\t3",
+        ],
+    );
+    let store = dir.path().join("in.hgidx");
+    let store = store.to_str().unwrap();
+    assert!(hgidx(["pack", &bed, "-o", store]).status.success());
+
+    let expected = serde_json::json!([
+        {"chrom": "chr1", "start": 10, "end": 20, "rest": "a\t1"},
+        {"chrom": "chr1", "start": 30, "end": 40, "rest": "b\t2"},
+    ]);
+    let json = query(store, &["chr1:1-100", "--format", "json"]);
+    let array: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(array, expected);
+
+    // One object per line, the elements of the JSON array
+    let ndjson = query(store, &["chr1:1-100", "--format", "ndjson"]);
+    let lines: Vec<serde_json::Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(serde_json::Value::from(lines), expected);
+
+    // An empty result is still valid JSON
+    let json = query(store, &["chr1:100-200", "--format", "json"]);
+    assert_eq!(json.trim(), "[]");
+    assert_eq!(query(store, &["chr1:100-200", "--format", "ndjson"]), "");
+}
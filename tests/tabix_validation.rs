@@ -1,3 +1,4 @@
+use hgindex::{BedRecord, GenomicDataStore};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -156,6 +157,20 @@ fn test_tabix_compatibility() -> Result<(), Box<dyn std::error::Error>> {
     // Verify HGIndex file exists
     assert!(Path::new(&hgindex).exists(), "HGIndex file was not created");
 
+    // Every input record should be indexed, catching silent drops in packing
+    let input_records = fs::read_to_string(&test_bed)?
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .count();
+    let store = GenomicDataStore::<BedRecord>::open(&hgindex, None)?;
+    assert_eq!(
+        store.len(),
+        input_records,
+        "Packed store has {} features, but the input has {} records",
+        store.len(),
+        input_records
+    );
+
     // Run queries with both tools and compare outputs
     let test_regions = vec![
         "chr1:15000-17000",
@@ -195,14 +210,14 @@ fn test_tabix_compatibility() -> Result<(), Box<dyn std::error::Error>> {
             return Err("HGIndex query failed".into());
         }
 
-        // Compare outputs line by line to avoid any newline issues
+        // Compare outputs line by line to avoid any newline issues. Single
+        // region queries output records in (start, end) order, which is
+        // tabix's file order here since random-bed sorts by start and end,
+        // so don't sort.
         let tabix_data = String::from_utf8(tabix_output.stdout)?;
-        let mut tabix_lines: Vec<&str> = tabix_data.lines().collect();
+        let tabix_lines: Vec<&str> = tabix_data.lines().collect();
         let hgindex_data = String::from_utf8(hgindex_output.stdout)?;
-        let mut hgindex_lines: Vec<&str> = hgindex_data.lines().collect();
-
-        tabix_lines.sort();
-        hgindex_lines.sort();
+        let hgindex_lines: Vec<&str> = hgindex_data.lines().collect();
 
         assert_eq!(tabix_lines.len(), hgindex_lines.len());
 
@@ -0,0 +1,115 @@
+use hgindex::{GenomicDataStore, VcfRecord};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Sites sorted by position, with multi-base REF alleles and a symbolic
+// deletion spanning to its INFO END, whose ends differ from their starts
+const VCF: &str = "\
+##fileformat=VCFv4.2
+##INFO=<ID=END,Number=1,Type=Integer,Description=\"End position\">
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO
+chr1\t100\trs1\tA\tG\t50\tPASS\t.
+chr1\t150\trs2\tACGTACGT\tA\t50\tPASS\t.
+chr1\t155\trs3\tT\tC\t50\tPASS\t.
+chr1\t200\tsv1\tN\t<DEL>\t50\tPASS\tSVTYPE=DEL;END=900
+chr1\t1000\trs4\tGG\tG\t50\tPASS\t.
+chr1\t20000\trs5\tC\tT\t50\tPASS\t.
+chr2\t1\trs6\tA\tT\t50\tPASS\t.
+chr2\t50000\trs7\tTTT\tT\t50\tPASS\t.
+";
+
+fn check_tool_exists(tool: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match Command::new(tool).arg("--version").output() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{} not found: {}", tool, e).into()),
+    }
+}
+
+#[test]
+fn test_tabix_vcf_compatibility() -> Result<(), Box<dyn std::error::Error>> {
+    check_tool_exists("bgzip")?;
+    check_tool_exists("tabix")?;
+
+    // Set up paths
+    let test_dir = Path::new("target/test_files");
+    fs::create_dir_all(test_dir)?;
+    let test_dir = test_dir.canonicalize()?;
+    let test_vcf = test_dir.join("test.vcf");
+    let bgzipped = test_dir.join("test.vcf.gz");
+    let hgindex = test_dir.join("test_vcf.hgidx");
+    fs::write(&test_vcf, VCF)?;
+
+    let bgzip_output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "bgzip -c {} > {}",
+            test_vcf.display(),
+            bgzipped.display()
+        ))
+        .output()?;
+    if !bgzip_output.status.success() {
+        return Err("BGZip compression failed".into());
+    }
+
+    let tabix_output = Command::new("tabix")
+        .arg("-f")
+        .arg("-p")
+        .arg("vcf")
+        .arg(&bgzipped)
+        .output()?;
+    if !tabix_output.status.success() {
+        let stderr = String::from_utf8_lossy(&tabix_output.stderr);
+        println!("Tabix error: {}", stderr);
+        return Err("Tabix indexing failed".into());
+    }
+
+    // Pack the VCF's sites
+    if hgindex.exists() {
+        fs::remove_dir_all(&hgindex)?;
+    }
+    let mut store = GenomicDataStore::<VcfRecord>::create(&hgindex, None)?;
+    for line in VCF.lines().filter(|line| !line.starts_with('#')) {
+        let (chrom, record) = VcfRecord::parse(line)?;
+        store.add_record(chrom, &record)?;
+    }
+    store.finalize()?;
+
+    // Regions are 1-based and closed, as tabix takes them
+    let test_regions = [
+        ("chr1", 1, 99),
+        ("chr1", 100, 100),
+        ("chr1", 156, 157),
+        ("chr1", 500, 600),
+        ("chr1", 901, 1000),
+        ("chr1", 1, 100_000),
+        ("chr2", 50_002, 50_002),
+        ("chr2", 50_003, 60_000),
+    ];
+
+    let mut store = GenomicDataStore::<VcfRecord>::open(&hgindex, None)?;
+    for (chrom, start, end) in test_regions {
+        let region = format!("{}:{}-{}", chrom, start, end);
+        println!("Testing region: {}", region);
+        let tabix_output = Command::new("tabix").arg(&bgzipped).arg(&region).output()?;
+        if !tabix_output.status.success() {
+            return Err("Tabix query failed".into());
+        }
+        let tabix_data = String::from_utf8(tabix_output.stdout)?;
+        let tabix_lines: Vec<&str> = tabix_data.lines().collect();
+
+        let mut hgindex_lines = Vec::new();
+        store.map_overlapping_sorted(chrom, start - 1, end, |record| {
+            hgindex_lines.push(format!("{}\t{}", chrom, record));
+            Ok(())
+        })?;
+
+        assert_eq!(
+            tabix_lines, hgindex_lines,
+            "Results don't match for region {}",
+            region
+        );
+    }
+
+    Ok(())
+}